@@ -1,10 +1,18 @@
 use crate::audio::GameAudioPlugin;
 use crate::camera::CameraPlugin;
+use crate::chat::ChatPlugin;
+use crate::correction::CorrectionPlugin;
 use crate::game_state::GameLifecyclePlugin;
+use crate::hits::HitFeedbackPlugin;
 use crate::input::ClientInputPlugin;
+use crate::interaction::InteractionPlugin;
+use crate::match_hud::MatchHudPlugin;
 use crate::menu::MenuPlugin;
 use crate::network::NetworkPlugin;
 use crate::render::RenderPlugin;
+use crate::tick_interpolation::TickInterpolationPlugin;
+#[cfg(not(target_family = "wasm"))]
+use crate::voice::VoicePlugin;
 
 use bevy::prelude::*;
 use bevy::prelude::{AssetPlugin, default};
@@ -50,6 +58,12 @@ pub fn add_basics_to_client_app(
         GameLifecyclePlugin,
         ClientInputPlugin,
         CameraPlugin,
+        CorrectionPlugin,
+        TickInterpolationPlugin,
+        ChatPlugin,
+        MatchHudPlugin,
+        HitFeedbackPlugin,
+        InteractionPlugin,
     ));
 
     app.insert_resource(crate::network::AutoConnect(autoconnect));
@@ -72,6 +86,9 @@ pub fn add_network_to_client_app(app: &mut App, client_id: u64) -> &mut App {
 
 pub fn add_audio_to_client_app(app: &mut App) -> &mut App {
     app.add_plugins(GameAudioPlugin);
+    // cpal capture and the Opus codec are native only
+    #[cfg(not(target_family = "wasm"))]
+    app.add_plugins(VoicePlugin);
     app
 }
 
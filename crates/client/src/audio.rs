@@ -3,33 +3,58 @@ use crate::game_state::GameState;
 use avian3d::prelude::LinearVelocity;
 use bevy::log::debug;
 use bevy::prelude::{
-    App, AssetServer, Commands, Component, Handle, IntoScheduleConfigs, Local, OnEnter, Plugin,
-    Query, Res, Resource, Startup, Time, Update, in_state,
+    App, AssetServer, Commands, Component, Entity, Event, EventReader, EventWriter, Handle,
+    IntoScheduleConfigs, Local, OnAdd, OnEnter, Or, Plugin, Query, Res, Resource, Startup, Time,
+    Trigger, Update, Vec3Swizzles, With, Without, in_state,
 };
 
 use bevy_kira_audio::AudioControl;
 use bevy_kira_audio::AudioSource;
-use bevy_kira_audio::prelude::{Audio, AudioPlugin};
+use bevy_kira_audio::prelude::{
+    Audio, AudioPlugin, DefaultSpatialRadius, SpatialAudioEmitter, SpatialAudioPlugin,
+    SpatialAudioReceiver,
+};
+use leafwing_input_manager::prelude::ActionState;
+use lightyear::prelude::{Client, Controlled, Interpolated, MessageReceiver, Predicted};
+use shared::input::{PlayerAction, Stamina};
+use shared::protocol::{Hit, PlayerId, PlayerSound, SOUND_RANGE, SoundMade};
 
-#[derive(Component)]
-pub struct PlayerController {
-    pub is_sprinting: bool,
-    pub is_crouching: bool,
-}
+use crate::camera::PlayerCamera;
+use crate::hits::HitReceived;
+
+/// Distance walked between two footsteps, in meters.
+const FOOTSTEP_STRIDE: f32 = 1.6;
+/// Players slower than this don't make footsteps.
+const FOOTSTEP_MIN_SPEED: f32 = 1.0;
 
 pub struct GameAudioPlugin;
 
 impl Plugin for GameAudioPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(AudioPlugin)
+        app.add_plugins((AudioPlugin, SpatialAudioPlugin))
+            .insert_resource(DefaultSpatialRadius {
+                radius: SOUND_RANGE,
+            })
+            .add_event::<SpatialSound>()
+            .add_observer(add_audio_receiver)
             .add_systems(Startup, setup_audio)
             .add_systems(OnEnter(GameState::Playing), cleanup_audio)
             .add_systems(
                 Update,
                 (
-                    play_footsteps,
+                    add_player_emitters,
+                    (
+                        play_footsteps,
+                        play_gunfire,
+                        play_breathing,
+                        play_server_sounds,
+                        play_damage_taken,
+                    ),
+                    play_spatial_sounds,
                     play_ambient_sounds,
-                ).run_if(in_state(GameState::Playing)),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
             );
     }
 }
@@ -42,8 +67,37 @@ pub struct GameAudio {
     pub weapon_fire: Handle<AudioSource>,
     pub damage_taken: Handle<AudioSource>,
     pub enemy_alert: Handle<AudioSource>,
+    pub breathing: Handle<AudioSource>,
+}
+
+/// Sounds played at the position of an entity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameSound {
+    Footstep,
+    WeaponFire,
+    WeaponReload,
+    DamageTaken,
+    EnemyAlert,
+    Breathing,
+}
+
+/// Plays `sound` from `emitter`, which needs a [`SpatialAudioEmitter`]. Players get one when they
+/// spawn.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SpatialSound {
+    pub emitter: Entity,
+    pub sound: GameSound,
+}
+
+/// Distance the player walked since its last footstep.
+#[derive(Component, Default)]
+struct FootstepTimer {
+    walked: f32,
 }
 
+pub(crate) type RenderedPlayerFilter = (With<PlayerId>, Or<(With<Predicted>, With<Interpolated>)>);
+type LocalPlayerFilter = (With<Predicted>, With<Controlled>);
+
 fn setup_audio(mut commands: Commands, asset_server: Res<AssetServer>) {
     let game_audio = GameAudio {
         footstep_concrete: asset_server.load("audio/footstep_concrete.mp3"),
@@ -52,6 +106,7 @@ fn setup_audio(mut commands: Commands, asset_server: Res<AssetServer>) {
         ambient_hum: asset_server.load("audio/ambient_hum.mp3"),
         enemy_alert: asset_server.load("audio/enemy_alert.mp3"),
         damage_taken: asset_server.load("audio/damage_taken.wav"),
+        breathing: asset_server.load("audio/breathing.wav"),
     };
 
     commands.insert_resource(game_audio);
@@ -59,46 +114,148 @@ fn setup_audio(mut commands: Commands, asset_server: Res<AssetServer>) {
     debug!("Audio system initialized");
 }
 
+/// Spatial sounds are heard from the camera.
+fn add_audio_receiver(trigger: Trigger<OnAdd, PlayerCamera>, mut commands: Commands) {
+    commands
+        .entity(trigger.target())
+        .insert(SpatialAudioReceiver);
+}
+
+fn add_player_emitters(
+    players: Query<Entity, (RenderedPlayerFilter, Without<SpatialAudioEmitter>)>,
+    mut commands: Commands,
+) {
+    for entity in players.iter() {
+        commands.entity(entity).insert((
+            SpatialAudioEmitter { instances: vec![] },
+            FootstepTimer::default(),
+        ));
+    }
+}
+
 fn play_footsteps(
     time: Res<Time>,
-    audio: Res<GameAudio>,
-    kira_audio: Res<Audio>,
-    player_query: Query<(&PlayerController, &LinearVelocity)>,
-    mut last_footstep_time: Local<f32>,
+    mut player_query: Query<(Entity, &LinearVelocity, &mut FootstepTimer), RenderedPlayerFilter>,
+    mut sounds: EventWriter<SpatialSound>,
 ) {
-    let current_time = time.elapsed_secs();
+    for (entity, velocity, mut timer) in player_query.iter_mut() {
+        let speed = velocity.xz().length();
+        if speed < FOOTSTEP_MIN_SPEED {
+            // The next step comes as soon as the player starts walking again
+            timer.walked = FOOTSTEP_STRIDE;
+            continue;
+        }
 
-    for (player_controller, velocity) in player_query.iter() {
-        let speed = velocity.length();
+        timer.walked += speed * time.delta_secs();
+        if timer.walked >= FOOTSTEP_STRIDE {
+            timer.walked = 0.0;
+            sounds.write(SpatialSound {
+                emitter: entity,
+                sound: GameSound::Footstep,
+            });
+        }
+    }
+}
 
-        if speed > 0.5 {
-            let footstep_volume = if player_controller.is_sprinting {
-                0.8
-            } else if player_controller.is_crouching {
-                0.2
-            } else {
-                0.5
-            };
+/// The local player's shots play as soon as they are predicted, the server only tells about the
+/// others' in [`play_server_sounds`].
+fn play_gunfire(
+    player_query: Query<(Entity, &ActionState<PlayerAction>), LocalPlayerFilter>,
+    mut sounds: EventWriter<SpatialSound>,
+) {
+    for (entity, action_state) in player_query.iter() {
+        if action_state.just_pressed(&PlayerAction::Shoot) {
+            sounds.write(SpatialSound {
+                emitter: entity,
+                sound: GameSound::WeaponFire,
+            });
+        }
+    }
+}
 
-            let footstep_interval = if player_controller.is_sprinting {
-                0.3
-            } else if player_controller.is_crouching {
-                0.8
-            } else {
-                0.5
+/// Shots and hits the server saw, played from the player who made them.
+fn play_server_sounds(
+    mut receivers: Query<&mut MessageReceiver<SoundMade>, With<Client>>,
+    players: Query<(Entity, &PlayerId), RenderedPlayerFilter>,
+    mut sounds: EventWriter<SpatialSound>,
+) {
+    for mut receiver in receivers.iter_mut() {
+        for message in receiver.receive() {
+            let Some((emitter, _)) = players.iter().find(|(_, id)| id.0 == message.player) else {
+                continue;
             };
+            sounds.write(SpatialSound {
+                emitter,
+                sound: match message.sound {
+                    PlayerSound::Gunshot => GameSound::WeaponFire,
+                    PlayerSound::DamageTaken => GameSound::DamageTaken,
+                },
+            });
+        }
+    }
+}
 
-            if speed > 1.0 && current_time - *last_footstep_time > footstep_interval {
-                kira_audio
-                    .play(audio.footstep_concrete.clone())
-                    .with_volume(footstep_volume);
-
-                *last_footstep_time = current_time;
-            }
+/// The server tells the local player about its own damage on the hit channel, which isn't held
+/// back by the bandwidth cap.
+fn play_damage_taken(
+    mut hits: EventReader<HitReceived>,
+    player_query: Query<Entity, LocalPlayerFilter>,
+    mut sounds: EventWriter<SpatialSound>,
+) {
+    for HitReceived(hit) in hits.read() {
+        let Ok(entity) = player_query.single() else {
+            continue;
+        };
+        if *hit == Hit::Taken {
+            sounds.write(SpatialSound {
+                emitter: entity,
+                sound: GameSound::DamageTaken,
+            });
         }
     }
 }
 
+/// Heavy breathing when the local player runs out of stamina.
+fn play_breathing(
+    player_query: Query<(Entity, &Stamina), LocalPlayerFilter>,
+    mut was_exhausted: Local<bool>,
+    mut sounds: EventWriter<SpatialSound>,
+) {
+    let Ok((entity, stamina)) = player_query.single() else {
+        return;
+    };
+    if stamina.exhausted && !*was_exhausted {
+        sounds.write(SpatialSound {
+            emitter: entity,
+            sound: GameSound::Breathing,
+        });
+    }
+    *was_exhausted = stamina.exhausted;
+}
+
+fn play_spatial_sounds(
+    mut sounds: EventReader<SpatialSound>,
+    audio: Res<GameAudio>,
+    kira_audio: Res<Audio>,
+    mut emitters: Query<&mut SpatialAudioEmitter>,
+) {
+    for sound in sounds.read() {
+        let Ok(mut emitter) = emitters.get_mut(sound.emitter) else {
+            continue;
+        };
+        let source = match sound.sound {
+            GameSound::Footstep => &audio.footstep_concrete,
+            GameSound::WeaponFire => &audio.weapon_fire,
+            GameSound::WeaponReload => &audio.weapon_reload,
+            GameSound::DamageTaken => &audio.damage_taken,
+            GameSound::EnemyAlert => &audio.enemy_alert,
+            GameSound::Breathing => &audio.breathing,
+        };
+        let instance = kira_audio.play(source.clone()).handle();
+        emitter.instances.push(instance);
+    }
+}
+
 fn play_ambient_sounds(
     time: Res<Time>,
     audio: Res<GameAudio>,
@@ -1,12 +1,18 @@
-use avian3d::prelude::{Position, Rotation};
+use avian3d::prelude::Position;
 use bevy::prelude::*;
+use bevy::transform::TransformSystem;
 use bevy::window::{CursorGrabMode, PrimaryWindow, WindowFocused};
 use leafwing_input_manager::prelude::ActionState;
 use lightyear::prelude::*;
 
-use shared::input::{MOUSE_SENSITIVITY, PITCH_LIMIT_RADIANS, PLAYER_CAPSULE_HEIGHT, PlayerAction};
+use shared::input::{
+    MOUSE_SENSITIVITY, PITCH_LIMIT_RADIANS, PLAYER_CAPSULE_HEIGHT, PlayerAction, Stance,
+};
 use shared::protocol::PlayerId;
 
+use crate::chat::ChatInput;
+use crate::tick_interpolation::{VisualPose, update_visual_pose};
+
 #[derive(Component, Default)]
 pub struct CameraPitch(pub f32);
 
@@ -19,7 +25,12 @@ impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
         app.add_observer(grab_cursor);
         app.add_observer(spawn_camera_when_player_spawn);
-        app.add_systems(PostUpdate, update_camera_transform_from_player);
+        app.add_systems(
+            PostUpdate,
+            update_camera_transform_from_player
+                .after(update_visual_pose)
+                .before(TransformSystem::TransformPropagate),
+        );
         app.add_systems(
             Update,
             (update_camera_pitch, toggle_cursor_grab, handle_focus_change),
@@ -148,16 +159,10 @@ fn update_camera_pitch(
     }
 }
 
+type LocalPlayerFilter = (With<PlayerId>, With<Predicted>, With<Controlled>);
+
 fn update_camera_transform_from_player(
-    player_query: Query<
-        (&Position, &Rotation),
-        (
-            With<PlayerId>,
-            With<Predicted>,
-            With<Controlled>,
-            Or<(Changed<Position>, Changed<Rotation>)>,
-        ),
-    >,
+    player_query: Query<(&VisualPose, &Stance), LocalPlayerFilter>,
     mut camera_query: Query<(&mut Transform, &CameraPitch), With<PlayerCamera>>,
 ) {
     let Ok((mut camera_transform, camera_pitch)) = camera_query.single_mut() else {
@@ -166,30 +171,32 @@ fn update_camera_transform_from_player(
     };
 
     // Find local player and update camera position and rotation
-    let Ok((player_position, player_rotation)) = player_query.single() else {
-        return; // If unlocking cursor, no more changes, Or<(Changed<Position>, Changed<Rotation>)> will not trigger and this query will fail
+    // Follow what is drawn rather than the raw prediction, it moves every frame between ticks
+    let Ok((pose, stance)) = player_query.single() else {
+        return;
     };
-
     camera_transform.translation = Vec3::new(
-        player_position.0.x,
-        player_position.0.y + PLAYER_CAPSULE_HEIGHT + 0.6,
-        player_position.0.z,
+        pose.translation.x,
+        pose.translation.y + stance.eye_height(),
+        pose.translation.z,
     );
 
-    let (player_yaw, _, _) = player_rotation.0.to_euler(EulerRot::YXZ);
+    let (player_yaw, _, _) = pose.rotation.to_euler(EulerRot::YXZ);
     let camera_quat = Quat::from_euler(EulerRot::YXZ, player_yaw, camera_pitch.0, 0.0);
     camera_transform.rotation = camera_quat;
 }
 
 fn toggle_cursor_grab(
     keys: Res<ButtonInput<KeyCode>>,
+    chat: Res<ChatInput>,
     mut action_query: Query<
         &mut ActionState<PlayerAction>,
         (With<PlayerId>, With<Predicted>, With<Controlled>),
     >,
     mut window_query: Query<&mut Window, With<PrimaryWindow>>,
 ) {
-    if keys.just_pressed(KeyCode::Escape) {
+    // Escape closes the chat box first
+    if keys.just_pressed(KeyCode::Escape) && !chat.open {
         if let Ok(mut window) = window_query.single_mut() {
             match window.cursor_options.grab_mode {
                 CursorGrabMode::None => {
@@ -0,0 +1,169 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_inspector_egui::bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use leafwing_input_manager::prelude::{ActionState, InputMap};
+use lightyear::prelude::*;
+use shared::chat::{ChatChannel, ChatLine, ChatScope, ChatSend, MAX_CHAT_LENGTH};
+use shared::input::PlayerAction;
+
+use crate::input::is_cursor_locked;
+
+/// Key opening the chat box.
+const CHAT_KEY: egui::Key = egui::Key::T;
+/// Lines kept in the chat log.
+const CHAT_LOG_LEN: usize = 100;
+/// Lines shown above the input.
+const VISIBLE_LINES: usize = 8;
+
+/// In-game chat box: `T` opens it, `Tab` switches between all, team and nearby players, `Enter`
+/// sends and `Escape` closes. Player controls are disabled while typing.
+pub struct ChatPlugin;
+
+impl Plugin for ChatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChatLog>();
+        app.init_resource::<ChatInput>();
+        app.add_systems(Update, (receive_chat, block_controls_while_typing));
+        app.add_systems(EguiPrimaryContextPass, draw_chat_box);
+    }
+}
+
+/// Chat lines received from the server, oldest first.
+#[derive(Resource, Default)]
+pub struct ChatLog(pub VecDeque<ChatLine>);
+
+#[derive(Resource, Default)]
+pub struct ChatInput {
+    pub open: bool,
+    text: String,
+    scope: ChatScope,
+}
+
+fn receive_chat(
+    mut receivers: Query<&mut MessageReceiver<ChatLine>, With<Client>>,
+    mut log: ResMut<ChatLog>,
+) {
+    for mut receiver in receivers.iter_mut() {
+        for line in receiver.receive() {
+            debug!("💬 [{}] {}: {}", line.scope.label(), line.sender, line.text);
+            if log.0.len() == CHAT_LOG_LEN {
+                log.0.pop_front();
+            }
+            log.0.push_back(line);
+        }
+    }
+}
+
+/// Controls come back when the chat closes, unless the cursor was released meanwhile.
+fn block_controls_while_typing(
+    input: Res<ChatInput>,
+    mut was_open: Local<bool>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut players: Query<&mut ActionState<PlayerAction>, With<InputMap<PlayerAction>>>,
+) {
+    if input.open == *was_open {
+        return;
+    }
+    *was_open = input.open;
+    for mut action_state in players.iter_mut() {
+        action_state.reset_all();
+        if input.open || !is_cursor_locked(&window_query) {
+            action_state.disable();
+        } else {
+            action_state.enable();
+        }
+    }
+}
+
+fn scope_color(scope: ChatScope) -> egui::Color32 {
+    match scope {
+        ChatScope::All => egui::Color32::WHITE,
+        ChatScope::Team => egui::Color32::LIGHT_BLUE,
+        ChatScope::Proximity => egui::Color32::LIGHT_GREEN,
+    }
+}
+
+fn draw_chat_box(
+    mut contexts: EguiContexts,
+    log: Res<ChatLog>,
+    mut input: ResMut<ChatInput>,
+    mut senders: Query<&mut MessageSender<ChatSend>, With<Client>>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    // Opened after this pass so the key doesn't end up in the text
+    let open_requested =
+        !input.open && !ctx.wants_keyboard_input() && ctx.input(|i| i.key_pressed(CHAT_KEY));
+
+    if !log.0.is_empty() || input.open {
+        draw_chat_window(ctx, &log, &mut input, &mut senders);
+    }
+    if open_requested {
+        input.open = true;
+    }
+    Ok(())
+}
+
+fn draw_chat_window(
+    ctx: &egui::Context,
+    log: &ChatLog,
+    input: &mut ChatInput,
+    senders: &mut Query<&mut MessageSender<ChatSend>, With<Client>>,
+) {
+    egui::Window::new("Chat")
+        .anchor(egui::Align2::LEFT_BOTTOM, [10.0, -10.0])
+        .title_bar(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            let skip = log.0.len().saturating_sub(VISIBLE_LINES);
+            for line in log.0.iter().skip(skip) {
+                let mut color = scope_color(line.scope);
+                if line.history {
+                    color = color.gamma_multiply(0.6);
+                }
+                ui.colored_label(
+                    color,
+                    format!("[{}] {}: {}", line.scope.label(), line.sender, line.text),
+                );
+            }
+
+            if !input.open {
+                return;
+            }
+            if ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Tab)) {
+                input.scope = input.scope.next();
+            }
+            ui.horizontal(|ui| {
+                let scope = input.scope;
+                if ui
+                    .button(egui::RichText::new(scope.label()).color(scope_color(scope)))
+                    .clicked()
+                {
+                    input.scope = scope.next();
+                }
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut input.text)
+                        .char_limit(MAX_CHAT_LENGTH)
+                        .desired_width(300.0),
+                );
+                response.request_focus();
+
+                if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                    input.text.clear();
+                    input.open = false;
+                } else if ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    let text = std::mem::take(&mut input.text);
+                    if !text.trim().is_empty() {
+                        for mut sender in senders.iter_mut() {
+                            sender.send::<ChatChannel>(ChatSend {
+                                scope: input.scope,
+                                text: text.clone(),
+                            });
+                        }
+                    }
+                    input.open = false;
+                }
+            });
+        });
+}
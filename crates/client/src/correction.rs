@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use avian3d::prelude::{Position, Rotation};
+use bevy::prelude::*;
+use lightyear::prelude::*;
+
+/// Time for a rollback correction to shrink to half of its size.
+const CORRECTION_HALF_LIFE: Duration = Duration::from_millis(50);
+
+/// Below this distance the remaining correction is dropped.
+const CORRECTION_EPSILON: f32 = 0.001;
+
+/// Hides rollback pops of the local player: instead of snapping to the corrected prediction, the
+/// visuals start where they were and catch up over a few frames. `Position` and `Rotation` stay
+/// untouched, the offset is added to the [`VisualPose`](crate::tick_interpolation::VisualPose)
+/// followed by the player mesh and the camera.
+pub struct CorrectionPlugin;
+
+impl Plugin for CorrectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            (
+                record_visual_state
+                    .after(RollbackSet::Check)
+                    .before(RollbackSet::Prepare),
+                compute_correction_offset.after(RollbackSet::EndRollback),
+            ),
+        );
+        app.add_systems(PostUpdate, decay_correction_offset);
+    }
+}
+
+/// Visual error left by the last rollbacks, decaying towards zero.
+#[derive(Component, Debug, Default)]
+pub struct CorrectionOffset {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    /// What was on screen right before the rollback of this frame.
+    before_rollback: Option<(Vec3, Quat)>,
+}
+
+fn record_visual_state(
+    manager: Single<&PredictionManager>,
+    mut query: Query<(&Position, &Rotation, &mut CorrectionOffset)>,
+) {
+    if !manager.is_rollback() {
+        return;
+    }
+    for (position, rotation, mut offset) in query.iter_mut() {
+        offset.before_rollback = Some((
+            position.0 + offset.translation,
+            offset.rotation * rotation.0,
+        ));
+    }
+}
+
+fn compute_correction_offset(mut query: Query<(&Position, &Rotation, &mut CorrectionOffset)>) {
+    for (position, rotation, mut offset) in query.iter_mut() {
+        let Some((visual_position, visual_rotation)) = offset.before_rollback.take() else {
+            continue;
+        };
+        offset.translation = visual_position - position.0;
+        offset.rotation = visual_rotation * rotation.0.inverse();
+        if offset.translation.length() > CORRECTION_EPSILON {
+            debug!(
+                "🪄 Smoothing a {:.3}m rollback correction",
+                offset.translation.length()
+            );
+        }
+    }
+}
+
+pub fn decay_correction_offset(time: Res<Time>, mut query: Query<&mut CorrectionOffset>) {
+    let remaining = 0.5_f32.powf(time.delta_secs() / CORRECTION_HALF_LIFE.as_secs_f32());
+    for mut offset in query.iter_mut() {
+        if offset.translation == Vec3::ZERO && offset.rotation == Quat::IDENTITY {
+            continue;
+        }
+        offset.translation *= remaining;
+        offset.rotation = Quat::IDENTITY.slerp(offset.rotation, remaining);
+        if offset.translation.length() < CORRECTION_EPSILON
+            && offset.rotation.angle_between(Quat::IDENTITY) < CORRECTION_EPSILON
+        {
+            offset.translation = Vec3::ZERO;
+            offset.rotation = Quat::IDENTITY;
+        }
+    }
+}
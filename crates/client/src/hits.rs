@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use lightyear::prelude::*;
+use shared::protocol::Hit;
+
+/// How long the hit marker stays on screen, in seconds.
+const HIT_MARKER_SECS: f32 = 0.2;
+
+/// A hit of the local player, as told by the server. The audio plays the damage on [`Hit::Taken`].
+#[derive(Event, Clone, Copy, Debug)]
+pub struct HitReceived(pub Hit);
+
+/// Time left showing the hit marker.
+#[derive(Resource, Default)]
+struct HitMarker(f32);
+
+/// Shows a marker at the center of the screen when the local player hits something.
+pub struct HitFeedbackPlugin;
+
+impl Plugin for HitFeedbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HitReceived>();
+        app.init_resource::<HitMarker>();
+        app.add_systems(Update, receive_hits);
+        app.add_systems(EguiPrimaryContextPass, draw_hit_marker);
+    }
+}
+
+fn receive_hits(
+    time: Res<Time>,
+    mut receivers: Query<&mut MessageReceiver<Hit>, With<Client>>,
+    mut marker: ResMut<HitMarker>,
+    mut hits: EventWriter<HitReceived>,
+) {
+    marker.0 = (marker.0 - time.delta_secs()).max(0.0);
+    for mut receiver in receivers.iter_mut() {
+        for hit in receiver.receive() {
+            if hit == Hit::Dealt {
+                marker.0 = HIT_MARKER_SECS;
+            }
+            hits.write(HitReceived(hit));
+        }
+    }
+}
+
+fn draw_hit_marker(mut contexts: EguiContexts, marker: Res<HitMarker>) -> Result {
+    if marker.0 <= 0.0 {
+        return Ok(());
+    }
+    let ctx = contexts.ctx_mut()?;
+    egui::Area::new(egui::Id::new("hit_marker"))
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            ui.label(
+                egui::RichText::new("X")
+                    .size(24.0)
+                    .strong()
+                    .color(egui::Color32::RED),
+            );
+        });
+    Ok(())
+}
@@ -5,9 +5,13 @@ use bevy::window::{CursorGrabMode, PrimaryWindow};
 use leafwing_input_manager::prelude::*;
 
 use crate::app::LocalPlayerId;
+use crate::correction::CorrectionOffset;
+use crate::tick_interpolation::{PlayerVisual, TickInterpolation, VisualPose};
 use lightyear::prelude::*;
+use shared::game_state::MatchState;
 use shared::input::{
-    PLAYER_CAPSULE_HEIGHT, PLAYER_CAPSULE_RADIUS, PlayerAction, shared_player_movement,
+    PLAYER_CAPSULE_HEIGHT, PLAYER_CAPSULE_RADIUS, PlayerAction, Stamina, Stance,
+    apply_stance_collider, is_grounded, shared_player_movement, update_player_stance,
 };
 use shared::protocol::{PlayerColor, PlayerId};
 use shared::scene::*;
@@ -15,7 +19,11 @@ pub struct ClientInputPlugin;
 
 impl Plugin for ClientInputPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(FixedUpdate, client_player_movement);
+        app.add_systems(
+            FixedUpdate,
+            (client_player_movement, apply_stance_collider).chain(),
+        );
+        app.add_systems(Update, show_player_stance);
         app.add_observer(handle_player_spawn);
         app.add_observer(handle_other_players_spawn);
         app.add_systems(FixedUpdate, debug_player_position);
@@ -60,11 +68,22 @@ fn handle_player_spawn(
                 "🚀 Attaching mesh, physics, and input map to PREDICTED player: {:?} ({:?})",
                 entity, name
             );
-            commands.entity(entity).insert((
-                Mesh3d(meshes.add(Capsule3d::new(PLAYER_CAPSULE_RADIUS, PLAYER_CAPSULE_HEIGHT))),
-                MeshMaterial3d(materials.add(color.0)),
-                PlayerPhysicsBundle::default(),
-            ));
+            // The mesh lives on a child so it can be drawn away from the simulated body
+            commands
+                .entity(entity)
+                .insert((
+                    PlayerPhysicsBundle::default(),
+                    CorrectionOffset::default(),
+                    TickInterpolation::default(),
+                    VisualPose::default(),
+                ))
+                .with_child((
+                    Mesh3d(
+                        meshes.add(Capsule3d::new(PLAYER_CAPSULE_RADIUS, PLAYER_CAPSULE_HEIGHT)),
+                    ),
+                    MeshMaterial3d(materials.add(color.0)),
+                    PlayerVisual,
+                ));
 
             let input_map = get_player_input_map();
             commands.entity(entity).insert(input_map);
@@ -98,12 +117,28 @@ fn client_player_movement(
             Entity,
             &mut Rotation,
             &mut LinearVelocity,
+            &mut Stance,
+            &mut Position,
+            &mut Stamina,
             &ActionState<PlayerAction>,
         ),
         (With<PlayerId>, With<Predicted>, With<Controlled>),
     >,
+    spatial_query: Res<SpatialQueryPipeline>,
+    time: Res<Time>,
+    match_state: Option<Single<&MatchState>>,
 ) {
-    for (entity, mut rotation, mut velocity, action_state) in player_query.iter_mut() {
+    // Predicts the server, which drops the inputs of locked players. The phase arrives through
+    // replication, after the tick at which the server changed it, so the first ticks of a phase
+    // are predicted with the previous one. The server's state then disagrees and the rollback
+    // replays those ticks with the phase now known, which costs a small correction at each
+    // phase change rather than tracking the tick of every change.
+    let neutral = ActionState::<PlayerAction>::default();
+    let allows_input = MatchState::allows_input(match_state.as_deref().copied());
+    for (entity, mut rotation, mut velocity, stance, position, stamina, action_state) in
+        player_query.iter_mut()
+    {
+        let action_state = if allows_input { action_state } else { &neutral };
         let move_axis_pair = action_state.axis_pair(&PlayerAction::Move);
         let look_axis_pair = action_state.axis_pair(&PlayerAction::Look);
 
@@ -118,7 +153,43 @@ fn client_player_movement(
             );
         }
 
-        shared_player_movement(action_state, &mut rotation, &mut velocity);
+        let grounded = is_grounded(entity, position.0, *stance, &spatial_query);
+        let stance = update_player_stance(
+            action_state,
+            entity,
+            stance,
+            position,
+            &stamina,
+            &spatial_query,
+        );
+        shared_player_movement(
+            action_state,
+            stance,
+            grounded,
+            stamina,
+            &mut rotation,
+            &mut velocity,
+            time.delta_secs(),
+        );
+    }
+}
+
+/// Squashes the capsule of crouched players. The local player's mesh is on a child, so that its
+/// collider isn't scaled with it.
+fn show_player_stance(
+    players: Query<(Entity, &Stance, Option<&Children>), (With<PlayerId>, Changed<Stance>)>,
+    mut transforms: Query<&mut Transform>,
+    visuals: Query<(), With<PlayerVisual>>,
+) {
+    let full_height = PLAYER_CAPSULE_HEIGHT + 2.0 * PLAYER_CAPSULE_RADIUS;
+    for (entity, stance, children) in players.iter() {
+        let scale_y = (stance.capsule_height() + 2.0 * PLAYER_CAPSULE_RADIUS) / full_height;
+        let visual = children
+            .and_then(|children| children.iter().find(|child| visuals.contains(*child)))
+            .unwrap_or(entity);
+        if let Ok(mut transform) = transforms.get_mut(visual) {
+            transform.scale.y = scale_y;
+        }
     }
 }
 
@@ -126,6 +197,10 @@ fn get_player_input_map() -> InputMap<PlayerAction> {
     let input_map = InputMap::<PlayerAction>::new([
         (PlayerAction::Jump, KeyCode::Space),
         (PlayerAction::Shoot, KeyCode::Enter),
+        (PlayerAction::Sprint, KeyCode::ShiftLeft),
+        (PlayerAction::Crouch, KeyCode::ControlLeft),
+        (PlayerAction::Crouch, KeyCode::KeyC),
+        (PlayerAction::Interact, KeyCode::KeyE),
     ])
     .with_dual_axis(PlayerAction::Move, VirtualDPad::wasd())
     .with_dual_axis(PlayerAction::Move, VirtualDPad::arrow_keys())
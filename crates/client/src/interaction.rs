@@ -0,0 +1,149 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use leafwing_input_manager::prelude::ActionState;
+use lightyear::prelude::*;
+use shared::input::PlayerAction;
+use shared::interaction::{
+    Door, INTERACT_RANGE, InteractTarget, Interactable, InteractionChannel,
+    add_interactable_physics, apply_door_state,
+};
+use shared::protocol::PlayerId;
+
+use crate::camera::PlayerCamera;
+
+/// Finds the [`Interactable`] under the crosshair, tells the server about it and shows what
+/// using it does. The server decides whether the player can actually use it.
+pub struct InteractionPlugin;
+
+impl Plugin for InteractionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LookedAt>();
+        app.add_observer(add_interactable_physics);
+        app.add_systems(
+            FixedPostUpdate,
+            apply_door_state.before(PhysicsSet::Prepare),
+        );
+        app.add_systems(Update, (update_looked_at, track_hold).chain());
+        app.add_systems(EguiPrimaryContextPass, draw_interaction_prompt);
+    }
+}
+
+/// Interactable under the crosshair, last sent to the server.
+#[derive(Resource, Default)]
+struct LookedAt {
+    target: Option<Entity>,
+    /// Seconds the interaction has been held on it, for the progress bar. The server keeps the
+    /// count that matters.
+    held: Option<f32>,
+}
+
+type LocalPlayerFilter = (With<PlayerId>, With<Controlled>);
+
+type OpenDoor = (
+    Entity,
+    &'static Position,
+    &'static Rotation,
+    &'static Collider,
+);
+
+/// Spatial queries skip disabled colliders, open doors are hit separately so they can be closed.
+fn update_looked_at(
+    camera: Option<Single<&GlobalTransform, With<PlayerCamera>>>,
+    local_players: Query<Entity, LocalPlayerFilter>,
+    interactables: Query<(), With<Interactable>>,
+    open_doors: Query<OpenDoor, (With<Door>, With<ColliderDisabled>)>,
+    spatial_query: SpatialQuery,
+    mut looked_at: ResMut<LookedAt>,
+    mut senders: Query<&mut MessageSender<InteractTarget>, With<Client>>,
+) {
+    let target = camera.and_then(|camera| {
+        let origin = camera.translation();
+        let direction = camera.forward();
+        let filter = SpatialQueryFilter::from_excluded_entities(local_players.iter());
+        let hit = spatial_query
+            .cast_ray(origin, direction, INTERACT_RANGE, true, &filter)
+            .map(|hit| (hit.entity, hit.distance));
+        let door_hit = open_doors
+            .iter()
+            .filter_map(|(entity, position, rotation, collider)| {
+                collider
+                    .cast_ray(
+                        position.0,
+                        rotation.0,
+                        origin,
+                        direction.into(),
+                        INTERACT_RANGE,
+                        true,
+                    )
+                    .map(|(distance, _)| (entity, distance))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+        [hit, door_hit]
+            .into_iter()
+            .flatten()
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(entity, _)| entity)
+            .filter(|entity| interactables.contains(*entity))
+    });
+    if looked_at.target == target {
+        return;
+    }
+    *looked_at = LookedAt { target, held: None };
+    for mut sender in senders.iter_mut() {
+        sender.send::<InteractionChannel>(InteractTarget { target });
+    }
+}
+
+fn track_hold(
+    time: Res<Time>,
+    action_state: Option<Single<&ActionState<PlayerAction>, LocalPlayerFilter>>,
+    mut looked_at: ResMut<LookedAt>,
+) {
+    let pressed =
+        action_state.is_some_and(|action_state| action_state.pressed(&PlayerAction::Interact));
+    looked_at.held = if pressed && looked_at.target.is_some() {
+        Some(looked_at.held.unwrap_or_default() + time.delta_secs())
+    } else {
+        None
+    };
+}
+
+fn draw_interaction_prompt(
+    mut contexts: EguiContexts,
+    looked_at: Res<LookedAt>,
+    interactables: Query<(&Interactable, Option<&Door>)>,
+) -> Result {
+    let Some((interactable, door)) = looked_at
+        .target
+        .and_then(|target| interactables.get(target).ok())
+    else {
+        return Ok(());
+    };
+    let ctx = contexts.ctx_mut()?;
+    let verb = if door.is_some_and(|door| door.open) {
+        "Close door"
+    } else {
+        interactable.kind.verb()
+    };
+    egui::Area::new(egui::Id::new("interaction_prompt"))
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 60.0])
+        .show(ctx, |ui| {
+            if interactable.locked {
+                ui.colored_label(egui::Color32::GRAY, format!("{verb} (Locked)"));
+                return;
+            }
+            if interactable.hold_secs <= 0.0 {
+                ui.colored_label(egui::Color32::WHITE, format!("[E] {verb}"));
+                return;
+            }
+            ui.colored_label(egui::Color32::WHITE, format!("Hold [E] to {verb}"));
+            if let Some(held) = looked_at.held {
+                ui.add(
+                    egui::ProgressBar::new((held / interactable.hold_secs).min(1.0))
+                        .desired_width(150.0),
+                );
+            }
+        });
+    Ok(())
+}
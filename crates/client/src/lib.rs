@@ -1,8 +1,16 @@
 pub mod app;
 pub mod audio;
 pub mod camera;
+pub mod chat;
+pub mod correction;
 pub mod game_state;
+pub mod hits;
 pub mod input;
+pub mod interaction;
+pub mod match_hud;
 pub mod menu;
 pub mod network;
 pub mod render;
+pub mod tick_interpolation;
+#[cfg(not(target_family = "wasm"))]
+pub mod voice;
@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use shared::game_state::{MatchPhase, MatchState};
+use shared::objective::{Objective, ObjectiveState, ObjectiveStatus};
+
+use crate::network::ShutdownCountdown;
+
+/// Shows the round, its phase and countdown at the top of the screen, the result of the round
+/// once it is over and the mission objectives on the right. Controls are locked by the movement
+/// systems, see [`MatchPhase::allows_input`]. A server shutdown is counted down below the round.
+pub struct MatchHudPlugin;
+
+impl Plugin for MatchHudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            EguiPrimaryContextPass,
+            (draw_match_hud, draw_objective_tracker, draw_shutdown_countdown),
+        );
+    }
+}
+
+fn phase_color(phase: MatchPhase) -> egui::Color32 {
+    match phase {
+        MatchPhase::Warmup | MatchPhase::Intermission => egui::Color32::LIGHT_GRAY,
+        MatchPhase::InProgress => egui::Color32::WHITE,
+        MatchPhase::Extraction => egui::Color32::YELLOW,
+        MatchPhase::Victory => egui::Color32::LIGHT_GREEN,
+        MatchPhase::Defeat => egui::Color32::LIGHT_RED,
+    }
+}
+
+fn draw_match_hud(mut contexts: EguiContexts, state: Option<Single<&MatchState>>) -> Result {
+    let Some(state) = state else {
+        return Ok(());
+    };
+    let ctx = contexts.ctx_mut()?;
+    let color = phase_color(state.phase);
+
+    egui::Window::new("Match")
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 10.0])
+        .title_bar(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.colored_label(
+                color,
+                format!(
+                    "Round {} - {} {}:{:02}",
+                    state.round,
+                    state.phase.label(),
+                    state.seconds_left / 60,
+                    state.seconds_left % 60
+                ),
+            );
+        });
+
+    if matches!(state.phase, MatchPhase::Victory | MatchPhase::Defeat) {
+        egui::Area::new(egui::Id::new("match_result"))
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(
+                    egui::RichText::new(state.phase.label())
+                        .size(64.0)
+                        .strong()
+                        .color(color),
+                );
+            });
+    }
+    Ok(())
+}
+
+fn draw_shutdown_countdown(
+    mut contexts: EguiContexts,
+    countdown: Option<Res<ShutdownCountdown>>,
+) -> Result {
+    let Some(countdown) = countdown else {
+        return Ok(());
+    };
+    let ctx = contexts.ctx_mut()?;
+    egui::Area::new(egui::Id::new("shutdown_countdown"))
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 50.0])
+        .show(ctx, |ui| {
+            ui.label(
+                egui::RichText::new(format!(
+                    "{} - disconnecting in {:.0}s",
+                    countdown.reason,
+                    countdown.timer.remaining_secs().ceil()
+                ))
+                .size(20.0)
+                .strong()
+                .color(egui::Color32::LIGHT_RED),
+            );
+        });
+    Ok(())
+}
+
+fn draw_objective_tracker(
+    mut contexts: EguiContexts,
+    objectives: Query<(&Objective, &ObjectiveState)>,
+) -> Result {
+    if objectives.is_empty() {
+        return Ok(());
+    }
+    let ctx = contexts.ctx_mut()?;
+    let mut objectives: Vec<_> = objectives.iter().collect();
+    objectives.sort_by_key(|(objective, _)| objective.order);
+
+    egui::Window::new("Objectives")
+        .anchor(egui::Align2::RIGHT_TOP, [-10.0, 10.0])
+        .resizable(false)
+        .show(ctx, |ui| {
+            for (objective, state) in objectives {
+                match state.status {
+                    ObjectiveStatus::Locked => {
+                        ui.colored_label(egui::Color32::DARK_GRAY, "Locked");
+                    }
+                    ObjectiveStatus::Active => {
+                        ui.colored_label(
+                            egui::Color32::WHITE,
+                            format!(
+                                "[{}] {} {}%",
+                                objective.kind.label(),
+                                objective.description,
+                                state.progress
+                            ),
+                        );
+                    }
+                    ObjectiveStatus::Completed => {
+                        ui.label(
+                            egui::RichText::new(&objective.description)
+                                .strikethrough()
+                                .color(egui::Color32::GRAY),
+                        );
+                    }
+                }
+            }
+        });
+    Ok(())
+}
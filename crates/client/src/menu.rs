@@ -1,4 +1,5 @@
 use crate::game_state::GameState;
+use crate::network::DisconnectReason;
 
 use bevy::log::debug;
 use bevy::log::info;
@@ -11,7 +12,7 @@ use bevy::{
     color::palettes::tailwind::SLATE_800,
     prelude::{
         AlignItems, App, BackgroundColor, Camera2d, Commands, Component, FlexDirection,
-        JustifyContent, Name, Node, Plugin, Query, Text, UiRect, Val, With, default,
+        JustifyContent, Name, Node, Plugin, Query, Res, Text, UiRect, Val, With, default,
     },
 };
 
@@ -64,7 +65,11 @@ pub struct MainMenuStatusText;
 #[derive(Component)]
 pub struct ConnectButton;
 
-fn spawn_main_menu_ui(mut commands: Commands, q_main_menu: Query<Entity, With<MainMenu>>) {
+fn spawn_main_menu_ui(
+    mut commands: Commands,
+    q_main_menu: Query<Entity, With<MainMenu>>,
+    disconnect_reason: Option<Res<DisconnectReason>>,
+) {
     for entity in &q_main_menu {
         commands.entity(entity).despawn();
     }
@@ -98,6 +103,16 @@ fn spawn_main_menu_ui(mut commands: Commands, q_main_menu: Query<Entity, With<Ma
                 ))
                 .insert(MainMenuStatusText);
 
+            if let Some(reason) = disconnect_reason.as_ref().and_then(|r| r.0.as_ref()) {
+                child_builder.spawn((
+                    Text::new(format!("Disconnected: {reason}")),
+                    Node {
+                        padding: UiRect::bottom(Val::Px(20.)),
+                        ..default()
+                    },
+                ));
+            }
+
             child_builder
                 .spawn((
                     Text::new("Connect"),
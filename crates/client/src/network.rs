@@ -1,16 +1,19 @@
-use bevy::log::debug;
+use bevy::log::{debug, info};
 use bevy::prelude::{
-    App, Commands, CommandsStatesExt, Entity, Name, OnAdd, OnEnter, OnRemove, Plugin, Query, Res,
-    ResMut, Resource, Startup, State, Trigger, Update, With, error,
+    App, Commands, CommandsStatesExt, Entity, IntoScheduleConfigs, Name, OnAdd, OnEnter, OnRemove,
+    Plugin, Query, Res, ResMut, Resource, Startup, State, Time, Timer, TimerMode, Trigger, Update,
+    With, error, warn,
 };
 
 use lightyear::prelude::client::{NetcodeClient, NetcodeConfig};
 
 use lightyear::prelude::{
-    Authentication, Client, Connect, Connected, InterpolationManager, Link, LocalAddr, PeerAddr,
-    PredictionManager, ReplicationReceiver, UdpIo,
+    Authentication, Client, Connect, Connected, Disconnect, InterpolationManager, Link, LocalAddr,
+    MessageReceiver, PeerAddr, PredictionManager, ReplicationReceiver, UdpIo,
 };
 
+use shared::link_conditioner::NetworkConditions;
+use shared::protocol::{Kicked, ServerShutdown};
 use shared::{SERVER_ADDR, SHARED_SETTINGS};
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
@@ -24,6 +27,18 @@ pub struct ConnectionState {
     pub logged_waiting: bool,
 }
 
+/// Why the last session ended, shown on the main menu.
+#[derive(Resource, Default)]
+pub struct DisconnectReason(pub Option<String>);
+
+/// Time left before the server shuts down, from its notice. Shown by the match HUD until the
+/// client is back on the main menu.
+#[derive(Resource, Debug)]
+pub struct ShutdownCountdown {
+    pub reason: String,
+    pub timer: Timer,
+}
+
 #[derive(Resource)]
 pub struct AutoConnect(pub bool);
 
@@ -36,6 +51,7 @@ impl Default for AutoConnect {
 impl Plugin for NetworkPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(ConnectionState::default());
+        app.init_resource::<DisconnectReason>();
 
         if !app.world().contains_resource::<AutoConnect>() {
             app.insert_resource(AutoConnect::default());
@@ -48,6 +64,8 @@ impl Plugin for NetworkPlugin {
 
         app.add_systems(Update, monitor_connection_status);
         app.add_systems(Update, log_connection_events);
+        app.add_systems(Update, receive_kicked);
+        app.add_systems(Update, (receive_server_shutdown, tick_shutdown_countdown).chain());
 
         app.add_observer(handle_client_connected);
         app.add_observer(handle_client_disconnected);
@@ -55,17 +73,61 @@ impl Plugin for NetworkPlugin {
 }
 
 fn cleanup_client_connection(mut commands: Commands, client_query: Query<Entity, With<Client>>) {
+    commands.remove_resource::<ShutdownCountdown>();
     for client_entity in client_query.iter() {
         debug!("🧹 Cleaning up client connection: {:?}", client_entity);
         commands.entity(client_entity).despawn();
     }
 }
 
+fn receive_kicked(
+    mut receivers: Query<(Entity, &mut MessageReceiver<Kicked>), With<Client>>,
+    mut disconnect_reason: ResMut<DisconnectReason>,
+    mut commands: Commands,
+) {
+    for (client_entity, mut receiver) in receivers.iter_mut() {
+        for kicked in receiver.receive() {
+            warn!("👢 Kicked by the server: {}", kicked.reason);
+            disconnect_reason.0 = Some(kicked.reason);
+            commands.trigger_targets(Disconnect, client_entity);
+        }
+    }
+}
+
+fn receive_server_shutdown(
+    mut receivers: Query<&mut MessageReceiver<ServerShutdown>, With<Client>>,
+    mut disconnect_reason: ResMut<DisconnectReason>,
+    mut commands: Commands,
+) {
+    for mut receiver in receivers.iter_mut() {
+        for notice in receiver.receive() {
+            info!(
+                "🛑 Server shutting down in {:.0}s: {}",
+                notice.countdown_secs, notice.reason
+            );
+            disconnect_reason.0 = Some(notice.reason.clone());
+            commands.insert_resource(ShutdownCountdown {
+                reason: notice.reason,
+                timer: Timer::from_seconds(notice.countdown_secs, TimerMode::Once),
+            });
+        }
+    }
+}
+
+fn tick_shutdown_countdown(time: Res<Time>, countdown: Option<ResMut<ShutdownCountdown>>) {
+    if let Some(mut countdown) = countdown {
+        countdown.timer.tick(time.delta());
+    }
+}
+
 fn start_connection(
     mut commands: Commands,
     client_id: Res<LocalPlayerId>,
     existing_clients: Query<Entity, With<Client>>,
+    mut disconnect_reason: ResMut<DisconnectReason>,
+    conditions: Res<NetworkConditions>,
 ) {
+    disconnect_reason.0 = None;
     if !existing_clients.is_empty() {
         debug!("🔄 Client already exists, skipping connection creation");
         for client_entity in existing_clients.iter() {
@@ -84,6 +146,9 @@ fn start_connection(
     );
 
     debug!("📋 Using client ID: {}", client_id.0);
+    if conditions.is_enabled() {
+        info!("🐢 Simulating network conditions: {:?}", *conditions);
+    }
 
     let client_port = 4000 + client_id.0 as u16;
     let client_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), client_port);
@@ -119,7 +184,7 @@ fn start_connection(
             Client::default(),
             LocalAddr(client_addr),
             PeerAddr(SERVER_ADDR),
-            Link::new(None),
+            Link::new(conditions.recv_conditioner()),
             ReplicationReceiver::default(),
             // 🔧 FIX: Manually add PredictionManager like examples show
             PredictionManager::default(),
@@ -1,9 +1,38 @@
+use std::time::Duration;
+
+use avian3d::prelude::{Position, Rotation};
 use bevy::prelude::*;
 use bevy_inspector_egui::{
-    bevy_egui::{EguiGlobalSettings, EguiPlugin, PrimaryEguiContext},
+    bevy_egui::{
+        EguiContexts, EguiGlobalSettings, EguiPlugin, EguiPrimaryContextPass, PrimaryEguiContext,
+        egui,
+    },
     quick::WorldInspectorPlugin,
 };
-use shared::render::{add_floor_visuals, add_wall_visuals, setup_lighting};
+use lightyear::prelude::client::*;
+use lightyear::prelude::*;
+use shared::FIXED_TIMESTEP_HZ;
+use shared::enemy::Enemy;
+use shared::input::{PLAYER_CAPSULE_HEIGHT, PLAYER_CAPSULE_RADIUS};
+use shared::link_conditioner::NetworkConditions;
+use shared::network_stats::NetworkStats;
+use shared::render::{
+    add_floor_visuals, add_interactable_visuals, add_wall_visuals, setup_lighting,
+};
+
+/// Key toggling the network diagnostics overlay.
+const NETWORK_OVERLAY_KEY: KeyCode = KeyCode::F3;
+
+type ClientDiagnostics<'a> = (
+    &'a Link,
+    &'a NetworkStats,
+    &'a LocalTimeline,
+    Option<&'a RemoteTimeline>,
+    Option<&'a InputTimeline>,
+    Option<&'a InterpolationTimeline>,
+);
+
+type InterpolatedEnemyFilter = (With<Enemy>, With<Interpolated>);
 
 pub struct RenderPlugin;
 
@@ -12,14 +41,24 @@ impl Plugin for RenderPlugin {
         app.add_systems(Startup, (setup_lighting, spawn_debug_camera));
         app.add_observer(add_floor_visuals);
         app.add_observer(add_wall_visuals);
+        app.add_observer(add_enemy_visuals);
+        app.add_observer(add_interactable_visuals);
         app.insert_resource(EguiGlobalSettings {
             auto_create_primary_context: false,
             ..Default::default()
         });
         app.add_plugins((EguiPlugin::default(), WorldInspectorPlugin::default()));
+        app.init_resource::<NetworkOverlay>();
+        app.add_systems(Update, toggle_network_overlay);
+        app.add_systems(EguiPrimaryContextPass, draw_network_overlay);
     }
 }
 
+#[derive(Resource, Default)]
+struct NetworkOverlay {
+    visible: bool,
+}
+
 #[derive(Component)]
 struct DebugCamera;
 
@@ -34,3 +73,137 @@ fn spawn_debug_camera(mut commands: Commands) {
         PrimaryEguiContext,
     ));
 }
+
+/// Enemies are drawn with the example character, its feet at the bottom of their capsule.
+fn add_enemy_visuals(
+    trigger: Trigger<OnAdd, (Enemy, Interpolated)>,
+    enemies: Query<(&Position, &Rotation), InterpolatedEnemyFilter>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let Ok((position, rotation)) = enemies.get(trigger.target()) else {
+        return;
+    };
+    commands
+        .entity(trigger.target())
+        .insert((
+            Transform::from_translation(position.0).with_rotation(rotation.0),
+            Visibility::default(),
+        ))
+        .with_child((
+            SceneRoot(
+                asset_server
+                    .load(GltfAssetLabel::Scene(0).from_asset("scenes/example_character.glb")),
+            ),
+            Transform::from_xyz(
+                0.0,
+                -(PLAYER_CAPSULE_HEIGHT / 2.0 + PLAYER_CAPSULE_RADIUS),
+                0.0,
+            ),
+        ));
+    debug!("👹 Added enemy visuals to {:?}", trigger.target());
+}
+
+fn toggle_network_overlay(keys: Res<ButtonInput<KeyCode>>, mut overlay: ResMut<NetworkOverlay>) {
+    if keys.just_pressed(NETWORK_OVERLAY_KEY) {
+        overlay.visible = !overlay.visible;
+    }
+}
+
+fn draw_network_overlay(
+    overlay: Res<NetworkOverlay>,
+    mut contexts: EguiContexts,
+    clients: Query<ClientDiagnostics, With<Client>>,
+    mut conditions: ResMut<NetworkConditions>,
+) -> Result {
+    if !overlay.visible {
+        return Ok(());
+    }
+
+    let ms_per_tick = 1000.0 / FIXED_TIMESTEP_HZ as f32;
+    egui::Window::new("Network")
+        .anchor(egui::Align2::RIGHT_TOP, [-10.0, 10.0])
+        .resizable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            if let Ok((link, stats, local, remote, input, interpolation)) = clients.single() {
+                egui::Grid::new("network_overlay").show(ui, |ui| {
+                    let mut row = |name: &str, value: String| {
+                        ui.label(name);
+                        ui.label(value);
+                        ui.end_row();
+                    };
+                    row(
+                        "RTT",
+                        format!("{:.1} ms", link.stats.rtt.as_secs_f32() * 1000.0),
+                    );
+                    row(
+                        "Jitter",
+                        format!("{:.1} ms", link.stats.jitter.as_secs_f32() * 1000.0),
+                    );
+                    row("Packet loss", format!("{:.1} %", stats.packet_loss * 100.0));
+                    match remote {
+                        Some(remote) => row(
+                            "Tick client / server",
+                            format!(
+                                "{} / {} ({:+})",
+                                local.tick().0,
+                                remote.tick().0,
+                                local.tick() - remote.tick()
+                            ),
+                        ),
+                        None => row("Tick client / server", format!("{} / ?", local.tick().0)),
+                    }
+                    if let Some(input) = input {
+                        row(
+                            "Input delay",
+                            format!(
+                                "{} ticks ({:.1} ms)",
+                                input.input_delay(),
+                                input.input_delay() as f32 * ms_per_tick
+                            ),
+                        );
+                    }
+                    if let (Some(remote), Some(interpolation)) = (remote, interpolation) {
+                        let delay = (remote.now() - interpolation.now()).to_f32();
+                        row(
+                            "Interpolation delay",
+                            format!("{delay:.1} ticks ({:.1} ms)", delay * ms_per_tick),
+                        );
+                    }
+                    row("Rollbacks", format!("{:.1} /s", stats.rollbacks_per_sec));
+                    row("Max correction", format!("{:.3} m", stats.max_correction));
+                    row(
+                        "Bytes in / out",
+                        format!(
+                            "{:.0} / {:.0} B/s",
+                            stats.bytes_received_per_sec, stats.bytes_sent_per_sec
+                        ),
+                    );
+                });
+            } else {
+                ui.label("Not connected");
+            }
+
+            ui.separator();
+            link_conditioner_controls(ui, &mut conditions);
+        });
+    Ok(())
+}
+
+/// Sliders editing the simulated [`NetworkConditions`], applied to the live link.
+fn link_conditioner_controls(ui: &mut egui::Ui, conditions: &mut ResMut<NetworkConditions>) {
+    let mut latency_ms = conditions.latency.as_millis() as u64;
+    let mut jitter_ms = conditions.jitter.as_millis() as u64;
+    let mut loss_percent = conditions.loss * 100.0;
+
+    ui.label("Simulated conditions");
+    ui.add(egui::Slider::new(&mut latency_ms, 0..=500).text("RTT (ms)"));
+    ui.add(egui::Slider::new(&mut jitter_ms, 0..=100).text("Jitter (ms)"));
+    ui.add(egui::Slider::new(&mut loss_percent, 0.0..=50.0).text("Loss (%)"));
+
+    conditions.set_if_neq(NetworkConditions {
+        latency: Duration::from_millis(latency_ms),
+        jitter: Duration::from_millis(jitter_ms),
+        loss: loss_percent / 100.0,
+    });
+}
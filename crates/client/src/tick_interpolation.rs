@@ -0,0 +1,123 @@
+use avian3d::prelude::{Position, Rotation};
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use leafwing_input_manager::prelude::ActionState;
+use lightyear::prelude::client::input::InputSet;
+use lightyear::prelude::*;
+use shared::input::{MOUSE_SENSITIVITY, PlayerAction};
+
+use crate::correction::{CorrectionOffset, decay_correction_offset};
+
+/// Draws the local player between its last two fixed ticks so the 64 Hz simulation looks smooth
+/// at any refresh rate. Mouse yaw is shown as soon as it happens and handed to the simulation as
+/// one accumulated delta on the next tick, so nothing is lost or counted twice.
+pub struct TickInterpolationPlugin;
+
+impl Plugin for TickInterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedPreUpdate,
+            write_accumulated_look.in_set(InputSet::WriteClientInputs),
+        );
+        app.add_systems(FixedLast, record_tick_pose);
+        app.add_systems(Update, accumulate_look);
+        app.add_systems(
+            PostUpdate,
+            (update_visual_pose, apply_visual_pose)
+                .chain()
+                .after(decay_correction_offset)
+                .before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// Simulated pose of the two last fixed ticks and the look input the simulation hasn't seen yet.
+#[derive(Component, Debug, Default)]
+pub struct TickInterpolation {
+    previous: Option<(Vec3, Quat)>,
+    current: Option<(Vec3, Quat)>,
+    pending_look: Vec2,
+}
+
+/// Where the local player is drawn this frame, in world space.
+#[derive(Component, Debug, Default)]
+pub struct VisualPose {
+    pub translation: Vec3,
+    pub rotation: Quat,
+}
+
+/// Child entity holding the meshes of the local player, moved to its [`VisualPose`].
+#[derive(Component)]
+pub struct PlayerVisual;
+
+fn accumulate_look(mut query: Query<(&ActionState<PlayerAction>, &mut TickInterpolation)>) {
+    for (action_state, mut interpolation) in query.iter_mut() {
+        interpolation.pending_look += action_state.axis_pair(&PlayerAction::Look);
+    }
+}
+
+/// The first tick of a frame consumes the accumulated look, the following ones get none.
+fn write_accumulated_look(
+    manager: Single<&PredictionManager>,
+    mut query: Query<(&mut ActionState<PlayerAction>, &mut TickInterpolation)>,
+) {
+    // Rollbacks replay the buffered inputs
+    if manager.is_rollback() {
+        return;
+    }
+    for (mut action_state, mut interpolation) in query.iter_mut() {
+        let look = std::mem::take(&mut interpolation.pending_look);
+        action_state.set_axis_pair(&PlayerAction::Look, look);
+    }
+}
+
+fn record_tick_pose(mut query: Query<(&Position, &Rotation, &mut TickInterpolation)>) {
+    for (position, rotation, mut interpolation) in query.iter_mut() {
+        interpolation.previous = interpolation.current;
+        interpolation.current = Some((position.0, rotation.0));
+    }
+}
+
+pub fn update_visual_pose(
+    time: Res<Time<Fixed>>,
+    mut query: Query<(
+        &Position,
+        &Rotation,
+        &TickInterpolation,
+        Option<&CorrectionOffset>,
+        &mut VisualPose,
+    )>,
+) {
+    let overstep = time.overstep_fraction();
+    for (position, rotation, interpolation, correction, mut pose) in query.iter_mut() {
+        let (current_position, current_rotation) =
+            interpolation.current.unwrap_or((position.0, rotation.0));
+        let (previous_position, _) = interpolation
+            .previous
+            .unwrap_or((current_position, current_rotation));
+        let pending_yaw = -interpolation.pending_look.x * MOUSE_SENSITIVITY;
+
+        pose.translation = previous_position.lerp(current_position, overstep);
+        pose.rotation = Quat::from_rotation_y(pending_yaw) * current_rotation;
+        if let Some(correction) = correction {
+            pose.translation += correction.translation;
+            pose.rotation = correction.rotation * pose.rotation;
+        }
+    }
+}
+
+/// The pose is in world space, the child transform is relative to the simulated body.
+fn apply_visual_pose(
+    parents: Query<(&Position, &Rotation, &VisualPose, &Children)>,
+    mut visuals: Query<&mut Transform, With<PlayerVisual>>,
+) {
+    for (position, rotation, pose, children) in parents.iter() {
+        let inverse = rotation.0.inverse();
+        for child in children.iter() {
+            if let Ok(mut transform) = visuals.get_mut(child) {
+                transform.translation = inverse * (pose.translation - position.0);
+                transform.rotation = inverse * pose.rotation;
+            }
+        }
+    }
+}
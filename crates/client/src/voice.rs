@@ -0,0 +1,512 @@
+use std::collections::VecDeque;
+use std::f32::consts::{FRAC_PI_2, TAU};
+use std::sync::{Arc, Mutex};
+
+use bevy::platform::collections::HashMap;
+use bevy::platform::collections::hash_map::Entry;
+use bevy::prelude::*;
+use bevy::utils::synccell::SyncCell;
+use bevy_inspector_egui::bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use bevy_kira_audio::prelude::SpatialAudioReceiver;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample};
+use kira::clock::clock_info::ClockInfoProvider;
+use kira::manager::{AudioManager, AudioManagerSettings, DefaultBackend};
+use kira::modulator::value_provider::ModulatorValueProvider;
+use kira::sound::{Sound, SoundData};
+use kira::{Frame, OutputDestination};
+use lightyear::prelude::*;
+use opus::{Application, Channels, Decoder, Encoder};
+use shared::protocol::PlayerId;
+use shared::voice::{
+    MAX_VOICE_FRAME_BYTES, VOICE_FRAME_SAMPLES, VOICE_SAMPLE_RATE, VoiceChannel, VoiceFrame,
+    VoiceLevel, VoicePacket,
+};
+
+use crate::audio::RenderedPlayerFilter;
+use crate::chat::ChatInput;
+
+/// Key held to talk.
+const PUSH_TO_TALK_KEY: KeyCode = KeyCode::KeyV;
+/// Key switching between whisper, normal and shout.
+const VOICE_LEVEL_KEY: KeyCode = KeyCode::KeyB;
+/// Captured samples kept while the game can't keep up, about half a second.
+const MAX_CAPTURED_SAMPLES: usize = 24 * VOICE_FRAME_SAMPLES;
+/// Decoded samples of a speaker buffered before its voice starts playing, 60 ms. Frames arriving
+/// late by less than that play without a gap.
+const JITTER_BUFFER_SAMPLES: usize = 3 * VOICE_FRAME_SAMPLES;
+/// Decoded samples kept for a speaker, older ones are dropped so a burst of late frames does not
+/// delay the voice for good.
+const MAX_BUFFERED_SAMPLES: usize = 15 * VOICE_FRAME_SAMPLES;
+/// Pitch of the loopback test tone, in Hz.
+const TEST_TONE_PITCH: f32 = 440.0;
+
+/// Proximity voice chat: hold `V` to talk and press `B` to switch between whisper, normal and
+/// shout. Voices are Opus encoded, relayed by the server to the players in range and streamed into
+/// kira, panned and attenuated from the speaker's position.
+pub struct VoicePlugin;
+
+impl Plugin for VoicePlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world().contains_resource::<VoiceSource>() {
+            app.insert_resource(VoiceSource::Microphone);
+        }
+        app.init_resource::<VoiceInput>();
+        app.init_resource::<CapturedSamples>();
+        app.init_resource::<VoiceSpeakers>();
+        app.init_resource::<LoopbackFrames>();
+        app.add_systems(Startup, (start_voice_capture, start_voice_playback));
+        app.add_systems(
+            Update,
+            (
+                update_voice_input,
+                generate_test_tone,
+                send_voice,
+                receive_voice,
+                place_voices,
+            )
+                .chain(),
+        );
+        app.add_systems(EguiPrimaryContextPass, draw_voice_indicator);
+    }
+}
+
+/// Where the voice sent to other players comes from.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VoiceSource {
+    /// Default input device, sent while the push to talk key is held.
+    Microphone,
+    /// Loopback test source: a beeping tone sent all the time, to try voice chat without a
+    /// microphone. It is also decoded and played back at the player's own position.
+    Loopback,
+}
+
+#[derive(Resource, Default)]
+pub struct VoiceInput {
+    pub level: VoiceLevel,
+    pub talking: bool,
+}
+
+/// Mono samples at [`VOICE_SAMPLE_RATE`] waiting to be encoded.
+#[derive(Resource, Default, Clone)]
+struct CapturedSamples(Arc<Mutex<VecDeque<f32>>>);
+
+impl CapturedSamples {
+    fn push(&self, samples: impl IntoIterator<Item = f32>) {
+        let mut captured = self.0.lock().unwrap();
+        captured.extend(samples);
+        let overflow = captured.len().saturating_sub(MAX_CAPTURED_SAMPLES);
+        captured.drain(..overflow);
+    }
+}
+
+/// Keeps the microphone stream open. cpal streams can't leave the thread that opened them.
+struct MicrophoneStream {
+    _stream: cpal::Stream,
+}
+
+/// kira manager the voices are played through. bevy_kira_audio only plays static sounds, the
+/// voices are streams fed as they are decoded.
+struct VoiceOutput(AudioManager);
+
+#[derive(Resource)]
+struct VoiceEncoder(SyncCell<Encoder>);
+
+/// Voice being received from each other player.
+#[derive(Resource, Default)]
+struct VoiceSpeakers(HashMap<PeerId, SpeakerStream>);
+
+struct SpeakerStream {
+    decoder: SyncCell<Decoder>,
+    level: VoiceLevel,
+    /// Read by the speaker's [`VoiceSound`], `None` without an audio output.
+    feed: Option<SpeakerFeed>,
+}
+
+/// Frames sent with the loopback source, played back as if the server relayed them.
+#[derive(Resource, Default)]
+struct LoopbackFrames(Vec<VoicePacket>);
+
+/// Decoded samples of a speaker and where it stands, shared with its [`VoiceSound`].
+#[derive(Default, Clone)]
+struct SpeakerFeed(Arc<Mutex<FeedState>>);
+
+#[derive(Default)]
+struct FeedState {
+    samples: Vec<f32>,
+    /// Left and right volume, from where the speaker stands.
+    gains: [f32; 2],
+}
+
+impl SpeakerFeed {
+    fn push(&self, samples: &[f32]) {
+        let mut feed = self.0.lock().unwrap();
+        feed.samples.extend_from_slice(samples);
+        let overflow = feed.samples.len().saturating_sub(MAX_BUFFERED_SAMPLES);
+        feed.samples.drain(..overflow);
+    }
+
+    fn set_gains(&self, gains: [f32; 2]) {
+        self.0.lock().unwrap().gains = gains;
+    }
+}
+
+/// Plays the voice of one speaker, for as long as its [`SpeakerFeed`] is kept.
+struct VoiceSound {
+    feed: SpeakerFeed,
+    buffer: SpeakerBuffer,
+    gains: [f32; 2],
+}
+
+impl SoundData for VoiceSound {
+    type Error = ();
+    type Handle = ();
+
+    fn into_sound(self) -> Result<(Box<dyn Sound>, Self::Handle), Self::Error> {
+        Ok((Box::new(self), ()))
+    }
+}
+
+impl Sound for VoiceSound {
+    fn output_destination(&mut self) -> OutputDestination {
+        OutputDestination::MAIN_TRACK
+    }
+
+    /// Takes what was decoded since the last batch of frames, the feed is only locked once per
+    /// batch.
+    fn on_start_processing(&mut self) {
+        let mut feed = self.feed.0.lock().unwrap();
+        self.buffer.samples.extend(feed.samples.drain(..));
+        self.gains = feed.gains;
+        let overflow = self
+            .buffer
+            .samples
+            .len()
+            .saturating_sub(MAX_BUFFERED_SAMPLES);
+        self.buffer.samples.drain(..overflow);
+    }
+
+    fn process(&mut self, dt: f64, _: &ClockInfoProvider, _: &ModulatorValueProvider) -> Frame {
+        let step = dt as f32 * VOICE_SAMPLE_RATE as f32;
+        let sample = self.buffer.next_sample(step).unwrap_or_default();
+        Frame {
+            left: sample * self.gains[0],
+            right: sample * self.gains[1],
+        }
+    }
+
+    fn finished(&self) -> bool {
+        Arc::strong_count(&self.feed.0) == 1
+    }
+}
+
+/// Jitter buffer of one speaker.
+#[derive(Default)]
+struct SpeakerBuffer {
+    samples: VecDeque<f32>,
+    /// Position between the first two samples, the output rate may differ from the voice's.
+    phase: f32,
+    /// Set once the buffer filled up, cleared when it runs dry so it fills up again.
+    playing: bool,
+}
+
+impl SpeakerBuffer {
+    /// Next sample at an output rate `step` times slower than [`VOICE_SAMPLE_RATE`].
+    fn next_sample(&mut self, step: f32) -> Option<f32> {
+        if !self.playing {
+            if self.samples.len() < JITTER_BUFFER_SAMPLES {
+                return None;
+            }
+            self.playing = true;
+        }
+        let Some(&current) = self.samples.front() else {
+            self.playing = false;
+            return None;
+        };
+        let next = self.samples.get(1).copied().unwrap_or(current);
+        let sample = current + (next - current) * self.phase;
+        self.phase += step;
+        while self.phase >= 1.0 && !self.samples.is_empty() {
+            self.phase -= 1.0;
+            self.samples.pop_front();
+        }
+        Some(sample)
+    }
+}
+
+fn start_voice_capture(world: &mut World) {
+    match Encoder::new(VOICE_SAMPLE_RATE, Channels::Mono, Application::Voip) {
+        Ok(encoder) => world.insert_resource(VoiceEncoder(SyncCell::new(encoder))),
+        Err(error) => {
+            warn!("🎙️ Voice chat disabled, could not create the Opus encoder: {error}");
+            return;
+        }
+    }
+    if *world.resource::<VoiceSource>() != VoiceSource::Microphone {
+        info!("🎙️ Using the loopback test tone as voice");
+        return;
+    }
+    let captured = world.resource::<CapturedSamples>().clone();
+    match open_microphone(captured) {
+        Ok(stream) => world.insert_non_send_resource(MicrophoneStream { _stream: stream }),
+        Err(error) => warn!("🎙️ No microphone, voice chat will only be received: {error}"),
+    }
+}
+
+fn open_microphone(captured: CapturedSamples) -> Result<cpal::Stream, String> {
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or("no input device")?;
+    let config = device
+        .default_input_config()
+        .map_err(|error| error.to_string())?;
+    info!(
+        "🎙️ Capturing voice from {} at {} Hz",
+        device.name().unwrap_or_default(),
+        config.sample_rate().0
+    );
+    let stream = match config.sample_format() {
+        SampleFormat::F32 => build_input_stream::<f32>(&device, &config.into(), captured),
+        SampleFormat::I16 => build_input_stream::<i16>(&device, &config.into(), captured),
+        SampleFormat::U16 => build_input_stream::<u16>(&device, &config.into(), captured),
+        format => return Err(format!("unsupported sample format {format}")),
+    }
+    .map_err(|error| error.to_string())?;
+    stream.play().map_err(|error| error.to_string())?;
+    Ok(stream)
+}
+
+/// Mixes the input down to mono and resamples it linearly to [`VOICE_SAMPLE_RATE`].
+fn build_input_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    captured: CapturedSamples,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels as usize;
+    let step = config.sample_rate.0 as f32 / VOICE_SAMPLE_RATE as f32;
+    let mut previous = 0.0;
+    let mut position = 0.0;
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            let mut resampled = Vec::with_capacity(data.len());
+            for frame in data.chunks(channels) {
+                let sample =
+                    frame.iter().map(|s| s.to_sample::<f32>()).sum::<f32>() / channels as f32;
+                while position < 1.0 {
+                    resampled.push(previous + (sample - previous) * position);
+                    position += step;
+                }
+                position -= 1.0;
+                previous = sample;
+            }
+            captured.push(resampled);
+        },
+        |error| warn!("🎙️ Microphone error: {error}"),
+        None,
+    )
+}
+
+fn start_voice_playback(world: &mut World) {
+    match AudioManager::<DefaultBackend>::new(AudioManagerSettings::default()) {
+        Ok(manager) => world.insert_non_send_resource(VoiceOutput(manager)),
+        Err(error) => warn!("🔈 No audio output, voices won't be heard: {error}"),
+    }
+}
+
+fn update_voice_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    chat: Res<ChatInput>,
+    source: Res<VoiceSource>,
+    mut input: ResMut<VoiceInput>,
+) {
+    let talking = match *source {
+        VoiceSource::Microphone => !chat.open && keys.pressed(PUSH_TO_TALK_KEY),
+        VoiceSource::Loopback => true,
+    };
+    if input.talking != talking {
+        input.talking = talking;
+    }
+    if !chat.open && keys.just_pressed(VOICE_LEVEL_KEY) {
+        input.level = input.level.next();
+        debug!("🎙️ Voice level: {}", input.level.label());
+    }
+}
+
+/// Half a second of tone, half a second of silence.
+fn generate_test_tone(
+    time: Res<Time>,
+    source: Res<VoiceSource>,
+    captured: Res<CapturedSamples>,
+    mut phase: Local<f32>,
+    mut elapsed: Local<f32>,
+) {
+    if *source != VoiceSource::Loopback {
+        return;
+    }
+    let count = (time.delta_secs() * VOICE_SAMPLE_RATE as f32).round() as usize;
+    let tone = (0..count).map(|_| {
+        *phase = (*phase + TAU * TEST_TONE_PITCH / VOICE_SAMPLE_RATE as f32) % TAU;
+        *elapsed += 1.0 / VOICE_SAMPLE_RATE as f32;
+        if elapsed.fract() < 0.5 {
+            0.3 * phase.sin()
+        } else {
+            0.0
+        }
+    });
+    captured.push(tone.collect::<Vec<_>>());
+}
+
+fn send_voice(
+    input: Res<VoiceInput>,
+    source: Res<VoiceSource>,
+    captured: Res<CapturedSamples>,
+    encoder: Option<ResMut<VoiceEncoder>>,
+    mut senders: Query<(&mut MessageSender<VoiceFrame>, &LocalId), With<Client>>,
+    mut loopback: ResMut<LoopbackFrames>,
+) {
+    let mut captured = captured.0.lock().unwrap();
+    let Some(mut encoder) = encoder.filter(|_| input.talking) else {
+        captured.clear();
+        return;
+    };
+    while captured.len() >= VOICE_FRAME_SAMPLES {
+        let samples: Vec<f32> = captured.drain(..VOICE_FRAME_SAMPLES).collect();
+        let data = match encoder
+            .0
+            .get()
+            .encode_vec_float(&samples, MAX_VOICE_FRAME_BYTES)
+        {
+            Ok(data) => data,
+            Err(error) => {
+                warn!("🎙️ Failed to encode voice: {error}");
+                continue;
+            }
+        };
+        for (mut sender, local_id) in senders.iter_mut() {
+            if *source == VoiceSource::Loopback {
+                loopback.0.push(VoicePacket {
+                    speaker: local_id.0,
+                    level: input.level,
+                    data: data.clone(),
+                });
+            }
+            sender.send::<VoiceChannel>(VoiceFrame {
+                level: input.level,
+                data: data.clone(),
+            });
+        }
+    }
+}
+
+fn receive_voice(
+    mut receivers: Query<&mut MessageReceiver<VoicePacket>, With<Client>>,
+    mut loopback: ResMut<LoopbackFrames>,
+    mut speakers: ResMut<VoiceSpeakers>,
+    mut output: Option<NonSendMut<VoiceOutput>>,
+) {
+    let mut packets: Vec<VoicePacket> = loopback.0.drain(..).collect();
+    for mut receiver in receivers.iter_mut() {
+        packets.extend(receiver.receive());
+    }
+    for packet in packets {
+        let speaker = match speakers.0.entry(packet.speaker) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let decoder = match Decoder::new(VOICE_SAMPLE_RATE, Channels::Mono) {
+                    Ok(decoder) => decoder,
+                    Err(error) => {
+                        warn!("🔈 Failed to create an Opus decoder: {error}");
+                        continue;
+                    }
+                };
+                let feed = output.as_mut().and_then(|output| {
+                    let feed = SpeakerFeed::default();
+                    let sound = VoiceSound {
+                        feed: feed.clone(),
+                        buffer: SpeakerBuffer::default(),
+                        gains: [0.0; 2],
+                    };
+                    match output.0.play(sound) {
+                        Ok(()) => Some(feed),
+                        Err(error) => {
+                            warn!("🔈 Can't play the voice of {:?}: {error:?}", packet.speaker);
+                            None
+                        }
+                    }
+                });
+                entry.insert(SpeakerStream {
+                    decoder: SyncCell::new(decoder),
+                    level: packet.level,
+                    feed,
+                })
+            }
+        };
+        let mut samples = [0.0; VOICE_FRAME_SAMPLES];
+        match speaker
+            .decoder
+            .get()
+            .decode_float(&packet.data, &mut samples, false)
+        {
+            Ok(count) => {
+                if let Some(feed) = &speaker.feed {
+                    feed.push(&samples[..count]);
+                }
+            }
+            Err(error) => debug!("🔈 Dropping undecodable voice frame: {error}"),
+        }
+        speaker.level = packet.level;
+    }
+}
+
+/// Sets how loud each speaker is in each ear, from where its player stands relative to the
+/// listener. Same falloff and panning as the game's spatial sounds, over the range of the
+/// speaker's voice level.
+fn place_voices(
+    speakers: Res<VoiceSpeakers>,
+    players: Query<(&PlayerId, &GlobalTransform), RenderedPlayerFilter>,
+    receiver: Query<&GlobalTransform, With<SpatialAudioReceiver>>,
+) {
+    let receiver = receiver.single().ok();
+    for (peer_id, speaker) in speakers.0.iter() {
+        let Some(feed) = &speaker.feed else {
+            continue;
+        };
+        let emitter = players
+            .iter()
+            .find(|(id, _)| id.0 == *peer_id)
+            .map(|(_, transform)| transform.translation());
+        let (Some(receiver), Some(emitter)) = (receiver, emitter) else {
+            feed.set_gains([0.0; 2]);
+            continue;
+        };
+        let path = emitter - receiver.translation();
+        let volume = 1.0 - (path.length() / speaker.level.range()).clamp(0.0, 1.0);
+        let right_ear_angle = if path == Vec3::ZERO {
+            FRAC_PI_2
+        } else {
+            receiver.right().angle_between(path)
+        };
+        let panning = (right_ear_angle.cos() + 1.0) / 2.0;
+        feed.set_gains([volume * (1.0 - panning).sqrt(), volume * panning.sqrt()]);
+    }
+}
+
+fn draw_voice_indicator(mut contexts: EguiContexts, input: Res<VoiceInput>) -> Result {
+    if !input.talking {
+        return Ok(());
+    }
+    egui::Area::new(egui::Id::new("voice_indicator"))
+        .anchor(egui::Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.colored_label(
+                egui::Color32::LIGHT_GREEN,
+                format!("🎙 {}", input.level.label()),
+            );
+        });
+    Ok(())
+}
@@ -1,10 +1,19 @@
 #![cfg(not(target_family = "wasm"))]
 
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use bevy::app::AppExit;
+use bevy::log::warn;
 use bevy::prelude::App;
 use clap::{Parser, ValueEnum};
 use client::app::{add_audio_to_client_app, add_basics_to_client_app, add_network_to_client_app};
+use client::voice::VoiceSource;
 
-use server::app::{add_basics_to_server_app, add_network_to_server_app};
+use server::app::{ServerConfig, add_basics_to_server_app, add_network_to_server_app};
+use server::shutdown::ShutdownSignal;
+use shared::link_conditioner::NetworkConditions;
 
 #[derive(Parser)]
 #[command(name = "yolo-game")]
@@ -22,6 +31,62 @@ struct Cli {
 
     #[arg(long, default_value_t = false)]
     autoconnect: bool,
+
+    /// Local TCP port of the admin RCON interface (server only, disabled when omitted)
+    #[arg(long)]
+    rcon_port: Option<u16>,
+
+    /// Password required by RCON connections
+    #[arg(long)]
+    rcon_password: Option<String>,
+
+    /// File storing bans and the whitelist (server only)
+    #[arg(long, default_value = "access_list.ron")]
+    access_list: PathBuf,
+
+    /// Only accept clients present in the whitelist (server only)
+    #[arg(long, default_value_t = false)]
+    whitelist_only: bool,
+
+    /// Seconds between the shutdown announcement and disconnecting clients (server only)
+    #[arg(long, default_value_t = 5)]
+    shutdown_countdown: u64,
+
+    /// Local HTTP port serving Prometheus metrics on /metrics (server only, disabled when omitted)
+    #[arg(long)]
+    metrics_port: Option<u16>,
+
+    /// Print logs as JSON lines with client_id/entity/tick fields (server only)
+    #[arg(long, default_value_t = false)]
+    json_logs: bool,
+
+    /// Outgoing bandwidth per client in kilobytes per second, 0 for no cap (server only)
+    #[arg(long, default_value_t = 0)]
+    bandwidth_cap: u32,
+
+    /// RON file with the AI director tunables (server only)
+    #[arg(long, default_value = "assets/director.ron")]
+    director: PathBuf,
+
+    /// RON file with the mission played every round (server only)
+    #[arg(long, default_value = "assets/missions/data_recovery.ron")]
+    mission: PathBuf,
+
+    /// Send a test tone as voice instead of the microphone (client only)
+    #[arg(long, default_value_t = false)]
+    voice_loopback: bool,
+
+    /// Simulated latency added to the round trip of this side's connections, in milliseconds
+    #[arg(long, default_value_t = 0)]
+    sim_latency: u64,
+
+    /// Simulated round trip jitter added by this side, in milliseconds
+    #[arg(long, default_value_t = 0)]
+    sim_jitter: u64,
+
+    /// Simulated packet loss in each direction, in percent
+    #[arg(long, default_value_t = 0.0)]
+    sim_loss: f32,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -33,6 +98,11 @@ enum Mode {
 pub fn run() {
     let cli = Cli::parse();
     let asset_path = "../../assets".to_string();
+    let network_conditions = NetworkConditions {
+        latency: Duration::from_millis(cli.sim_latency),
+        jitter: Duration::from_millis(cli.sim_jitter),
+        loss: cli.sim_loss / 100.0,
+    };
 
     match cli.mode {
         Mode::Client => {
@@ -43,6 +113,7 @@ pub fn run() {
             }
 
             let mut client_app = App::new();
+            client_app.insert_resource(network_conditions);
             add_basics_to_client_app(
                 &mut client_app,
                 asset_path.clone(),
@@ -50,14 +121,42 @@ pub fn run() {
                 cli.client_id,
             );
             add_network_to_client_app(&mut client_app, cli.client_id);
+            if cli.voice_loopback {
+                client_app.insert_resource(VoiceSource::Loopback);
+            }
             add_audio_to_client_app(&mut client_app);
             client_app.run();
         }
         Mode::Server => {
             let mut server_app = App::new();
-            add_basics_to_server_app(&mut server_app, cli.headless);
+            server_app.insert_resource(network_conditions);
+            server_app.insert_resource(ServerConfig {
+                rcon_addr: cli
+                    .rcon_port
+                    .map(|port| SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port)),
+                rcon_password: cli.rcon_password,
+                access_list_path: Some(cli.access_list),
+                whitelist_only: cli.whitelist_only,
+                shutdown_countdown: Duration::from_secs(cli.shutdown_countdown),
+                metrics_addr: cli
+                    .metrics_port
+                    .map(|port| SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port)),
+                bandwidth_cap: (cli.bandwidth_cap > 0).then(|| cli.bandwidth_cap * 1000),
+                director_path: Some(cli.director),
+                mission_path: Some(cli.mission),
+            });
+            add_basics_to_server_app(&mut server_app, cli.headless, cli.json_logs);
+            let signal = ShutdownSignal::default();
+            if let Err(e) = signal.install_handler() {
+                warn!("Failed to install the SIGINT/SIGTERM handler: {e}");
+            }
+            server_app.insert_resource(signal);
             add_network_to_server_app(&mut server_app);
-            server_app.run();
+            // 0 after a graceful shutdown, non-zero when it was forced or failed
+            match server_app.run() {
+                AppExit::Success => {}
+                AppExit::Error(code) => std::process::exit(code.get() as i32),
+            }
         }
     }
 }
@@ -0,0 +1,193 @@
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use server::access::{AccessDenied, AccessList, ban_address, format_duration, parse_duration};
+
+const NOW: u64 = 1_700_000_000;
+const CLIENT: u64 = 7;
+
+/// Durations take a whole number and one of the s, m, h and d units
+#[test]
+fn test_parse_duration() {
+    assert_eq!(parse_duration("90s"), Ok(Some(Duration::from_secs(90))));
+    assert_eq!(
+        parse_duration("30m"),
+        Ok(Some(Duration::from_secs(30 * 60)))
+    );
+    assert_eq!(
+        parse_duration("12h"),
+        Ok(Some(Duration::from_secs(12 * 3600)))
+    );
+    assert_eq!(
+        parse_duration("7d"),
+        Ok(Some(Duration::from_secs(7 * 86400)))
+    );
+
+    // Anything else is not a duration, the ban command reads it as the reason
+    for text in ["cheating", "d", "12", "5w", "1.5h", "-3m", ""] {
+        assert_eq!(parse_duration(text), Ok(None), "{text:?} is not a duration");
+    }
+}
+
+/// A duration past what seconds can count is an error, not a reason or a permanent ban
+#[test]
+fn test_parse_duration_overflow() {
+    assert!(parse_duration(&format!("{}d", u64::MAX / 86400 + 1)).is_err());
+    assert!(parse_duration(&format!("{}s", u64::MAX)).is_ok());
+    assert!(parse_duration("99999999999999999999999s").is_err());
+}
+
+/// A ban that would end past the last representable time is rejected and changes nothing
+#[test]
+fn test_ban_overflow_is_rejected() {
+    let mut access_list = AccessList::default();
+    let duration = Duration::from_secs(u64::MAX - NOW + 1);
+    assert!(
+        access_list
+            .ban(CLIENT, None, "spam".to_string(), Some(duration), NOW)
+            .is_err()
+    );
+    assert!(access_list.data().bans.is_empty());
+    assert_eq!(access_list.check(CLIENT, None, NOW), Ok(()));
+}
+
+/// Timed bans deny access until they run out, then get pruned
+#[test]
+fn test_ban_expiry() {
+    let mut access_list = AccessList::default();
+    access_list
+        .ban(
+            CLIENT,
+            None,
+            "spam".to_string(),
+            Some(Duration::from_secs(3600)),
+            NOW,
+        )
+        .unwrap();
+
+    assert_eq!(
+        access_list.check(CLIENT, None, NOW + 600),
+        Err(AccessDenied::Banned {
+            reason: "spam".to_string(),
+            remaining: Some(Duration::from_secs(3000)),
+        })
+    );
+    assert_eq!(access_list.check(CLIENT + 1, None, NOW), Ok(()));
+
+    assert_eq!(access_list.prune_expired(NOW + 3599), 0);
+    assert_eq!(access_list.check(CLIENT, None, NOW + 3600), Ok(()));
+    assert_eq!(access_list.prune_expired(NOW + 3600), 1);
+    assert!(access_list.data().bans.is_empty());
+}
+
+/// Permanent bans never run out, and a new ban replaces the previous one
+#[test]
+fn test_permanent_ban_and_unban() {
+    let mut access_list = AccessList::default();
+    access_list
+        .ban(
+            CLIENT,
+            None,
+            "spam".to_string(),
+            Some(Duration::from_secs(60)),
+            NOW,
+        )
+        .unwrap();
+    access_list
+        .ban(CLIENT, None, "cheating".to_string(), None, NOW)
+        .unwrap();
+
+    assert_eq!(access_list.data().bans.len(), 1);
+    assert_eq!(access_list.prune_expired(u64::MAX), 0);
+    assert_eq!(
+        access_list.check(CLIENT, None, u64::MAX),
+        Err(AccessDenied::Banned {
+            reason: "cheating".to_string(),
+            remaining: None,
+        })
+    );
+
+    assert!(access_list.unban(CLIENT));
+    assert!(!access_list.unban(CLIENT));
+    assert_eq!(access_list.check(CLIENT, None, NOW), Ok(()));
+}
+
+/// Whitelist-only servers admit allowed clients only, bans still win over the whitelist
+#[test]
+fn test_whitelist_check() {
+    let mut access_list = AccessList::default();
+    access_list.set_whitelist_only(true);
+    access_list.allow(CLIENT);
+
+    assert_eq!(access_list.check(CLIENT, None, NOW), Ok(()));
+    assert_eq!(
+        access_list.check(CLIENT + 1, None, NOW),
+        Err(AccessDenied::NotWhitelisted)
+    );
+
+    access_list
+        .ban(CLIENT, None, "spam".to_string(), None, NOW)
+        .unwrap();
+    assert!(matches!(
+        access_list.check(CLIENT, None, NOW),
+        Err(AccessDenied::Banned { .. })
+    ));
+
+    access_list.disallow(CLIENT + 1);
+    access_list.set_whitelist_only(false);
+    assert_eq!(access_list.check(CLIENT + 1, None, NOW), Ok(()));
+}
+
+/// A ban with an address also denies other client ids from that address, until it is lifted
+#[test]
+fn test_ban_covers_address() {
+    let address: IpAddr = "203.0.113.7".parse().unwrap();
+    let other: IpAddr = "203.0.113.8".parse().unwrap();
+    let mut access_list = AccessList::default();
+    access_list
+        .ban(CLIENT, Some(address), "cheating".to_string(), None, NOW)
+        .unwrap();
+
+    let banned = Err(AccessDenied::Banned {
+        reason: "cheating".to_string(),
+        remaining: None,
+    });
+    assert_eq!(access_list.check(CLIENT, None, NOW), banned);
+    assert_eq!(access_list.check(CLIENT + 1, Some(address), NOW), banned);
+    assert_eq!(access_list.check(CLIENT + 1, Some(other), NOW), Ok(()));
+    assert_eq!(access_list.check(CLIENT + 1, None, NOW), Ok(()));
+
+    // Bans without an address don't match clients without one
+    access_list
+        .ban(CLIENT + 2, None, "spam".to_string(), None, NOW)
+        .unwrap();
+    assert_eq!(access_list.check(CLIENT + 1, None, NOW), Ok(()));
+
+    assert!(access_list.unban(CLIENT));
+    assert_eq!(access_list.check(CLIENT + 1, Some(address), NOW), Ok(()));
+}
+
+/// Local clients all connect from the loopback address, which is never banned
+#[test]
+fn test_ban_address_skips_local_clients() {
+    let remote: SocketAddr = "203.0.113.7:5000".parse().unwrap();
+    assert_eq!(ban_address(remote), Some(remote.ip()));
+    for local in ["127.0.0.1:5000", "[::1]:5000", "0.0.0.0:5000"] {
+        assert_eq!(ban_address(local.parse().unwrap()), None, "{local}");
+    }
+}
+
+/// Players are told how long their ban still lasts, not a raw timestamp
+#[test]
+fn test_ban_message_shows_time_left() {
+    let denied = AccessDenied::Banned {
+        reason: "spam".to_string(),
+        remaining: Some(Duration::from_secs(2 * 86400 + 5 * 3600 + 59)),
+    };
+    assert_eq!(denied.to_string(), "banned for 2d 5h: spam");
+
+    assert_eq!(format_duration(Duration::from_secs(45)), "45s");
+    assert_eq!(format_duration(Duration::from_secs(750)), "12m 30s");
+    assert_eq!(format_duration(Duration::from_secs(3600)), "1h");
+    assert_eq!(format_duration(Duration::ZERO), "0s");
+}
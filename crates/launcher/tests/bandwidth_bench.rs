@@ -0,0 +1,384 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use avian3d::prelude::{LinearVelocity, PhysicsSet, Position, Rotation};
+use bevy::prelude::*;
+use lightyear::prelude::client::{ClientPlugins, NetcodeClient, NetcodeConfig};
+use lightyear::prelude::{
+    Authentication, Client, Confirmed, Connect, InterpolationManager, Link, LocalAddr, PeerAddr,
+    PredictionManager, ReplicationReceiver, UdpIo,
+};
+use lightyear_serde::reader::Reader;
+use lightyear_serde::registry::SerializeFns;
+use lightyear_serde::writer::Writer;
+use server::app::{ServerConfig, add_network_to_server_app};
+use shared::network_stats::NetworkStats;
+use shared::protocol::PlayerId;
+use shared::quantization::{
+    POSITION_STEP, VELOCITY_STEP, WireFormat, linear_velocity_serde, position_serde, rotation_serde,
+};
+use shared::{FIXED_TIMESTEP_HZ, SERVER_ADDR, SHARED_SETTINGS, SharedPlugin};
+
+const PLAYERS: usize = 16;
+/// Client ids, and local ports from 4000 up, of the benchmark clients.
+const FIRST_CLIENT_ID: u64 = 100;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const WARMUP: Duration = Duration::from_secs(2);
+const MEASURE: Duration = Duration::from_secs(5);
+/// Time for the last updates to reach the clients once the players stopped.
+const SETTLE: Duration = Duration::from_secs(2);
+
+/// Loose bound on what a client receives with every player running around it.
+const MAX_BYTES_PER_CLIENT_PER_SEC: f32 = 20_000.0;
+
+/// When a byte counter was refreshed and its value then.
+type CounterRefresh = (Instant, u64);
+
+/// Where the players are along their circles.
+#[derive(Resource, Default)]
+struct Running {
+    /// Seconds spent running, `None` until they start.
+    elapsed: Option<f32>,
+    /// Stopped players stay where they are.
+    moving: bool,
+}
+
+/// Players running around circles of different sizes, jumping from time to time. The circles
+/// stay in the room at the center of the level, every client sees every player.
+fn player_state(player: usize, time: f32) -> (Position, Rotation, LinearVelocity) {
+    let radius = 2.0 + player as f32 * 0.5;
+    let speed = 5.0;
+    let angle = time * speed / radius + player as f32;
+    let jump = (time * 2.0 + player as f32).sin();
+
+    let position = Vec3::new(
+        radius * angle.cos(),
+        1.0 + jump.max(0.0),
+        radius * angle.sin(),
+    );
+    let direction = Vec3::new(-angle.sin(), 0.0, angle.cos());
+    let vertical = if jump > 0.0 {
+        (time * 2.0 + player as f32).cos() * 2.0
+    } else {
+        0.0
+    };
+    (
+        Position(position),
+        Rotation(Quat::from_rotation_y(-angle)),
+        LinearVelocity(direction * speed + Vec3::Y * vertical),
+    )
+}
+
+fn round_trip<M>(fns: &SerializeFns<M>, value: &M) -> M {
+    let mut writer = Writer::default();
+    (fns.serialize)(value, &mut writer).expect("Failed to serialize");
+    let mut reader = Reader::from(writer.to_bytes());
+    (fns.deserialize)(&mut reader).expect("Failed to deserialize")
+}
+
+/// Runs every player along its circle, or keeps it where it stopped. Runs after the physics step
+/// so the level geometry doesn't get in the way.
+fn run_in_circles(
+    time: Res<Time>,
+    mut running: ResMut<Running>,
+    mut players: Query<(&PlayerId, &mut Position, &mut Rotation, &mut LinearVelocity)>,
+) {
+    let Running {
+        elapsed: Some(elapsed),
+        moving,
+    } = &mut *running
+    else {
+        return;
+    };
+    if *moving {
+        *elapsed += time.delta_secs();
+    }
+    let (elapsed, moving) = (*elapsed, *moving);
+    for (player_id, mut position, mut rotation, mut velocity) in players.iter_mut() {
+        let player = (player_id.0.to_bits() - FIRST_CLIENT_ID) as usize;
+        let (new_position, new_rotation, new_velocity) = player_state(player, elapsed);
+        *position = new_position;
+        *rotation = new_rotation;
+        *velocity = if moving {
+            new_velocity
+        } else {
+            LinearVelocity::ZERO
+        };
+    }
+}
+
+fn headless_plugins(app: &mut App, format: WireFormat) {
+    app.insert_resource(format);
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        AssetPlugin::default(),
+        bevy::scene::ScenePlugin,
+        bevy::render::mesh::MeshPlugin,
+        SharedPlugin,
+    ));
+}
+
+fn server_app(format: WireFormat) -> App {
+    let mut app = App::new();
+    headless_plugins(&mut app, format);
+    app.insert_resource(ServerConfig::default());
+    add_network_to_server_app(&mut app);
+    app.init_resource::<Running>();
+    app.add_systems(FixedPostUpdate, run_in_circles.after(PhysicsSet::Sync));
+    app.finish();
+    app.cleanup();
+    app
+}
+
+/// A client app with only the networking, connected like the game client does it.
+fn client_app(client_id: u64, format: WireFormat) -> App {
+    let mut app = App::new();
+    headless_plugins(&mut app, format);
+    app.add_plugins(ClientPlugins {
+        tick_duration: Duration::from_secs_f64(1.0 / FIXED_TIMESTEP_HZ),
+    });
+    app.finish();
+    app.cleanup();
+
+    let auth = Authentication::Manual {
+        server_addr: SERVER_ADDR,
+        client_id,
+        private_key: SHARED_SETTINGS.private_key,
+        protocol_id: SHARED_SETTINGS.protocol_id,
+    };
+    let netcode_client = NetcodeClient::new(auth, NetcodeConfig::default())
+        .expect("Failed to create netcode client");
+    let client = app
+        .world_mut()
+        .spawn((
+            Client::default(),
+            LocalAddr(SocketAddr::new(
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+                4000 + client_id as u16,
+            )),
+            PeerAddr(SERVER_ADDR),
+            Link::new(None),
+            ReplicationReceiver::default(),
+            PredictionManager::default(),
+            InterpolationManager::default(),
+            netcode_client,
+            UdpIo::default(),
+        ))
+        .id();
+    app.world_mut().trigger_targets(Connect, client);
+    app
+}
+
+fn update_all(server: &mut App, clients: &mut [App]) {
+    server.update();
+    for client in clients.iter_mut() {
+        client.update();
+    }
+    std::thread::sleep(Duration::from_millis(1));
+}
+
+/// Updates every app for `duration`, calling `sample` after each round.
+fn run_for(
+    duration: Duration,
+    server: &mut App,
+    clients: &mut [App],
+    mut sample: impl FnMut(&mut App, &mut [App]),
+) {
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        update_all(server, clients);
+        sample(server, clients);
+    }
+}
+
+fn client_stats(client: &mut App) -> NetworkStats {
+    client
+        .world_mut()
+        .query_filtered::<&NetworkStats, With<Client>>()
+        .single(client.world())
+        .expect("Client should have network stats")
+        .clone()
+}
+
+/// Player positions and rotations by client id, the confirmed ones on a client.
+fn player_states<F: bevy::ecs::query::QueryFilter>(app: &mut App) -> Vec<(u64, Vec3, Quat)> {
+    let mut states: Vec<_> = app
+        .world_mut()
+        .query_filtered::<(&PlayerId, &Position, &Rotation), F>()
+        .iter(app.world())
+        .map(|(player_id, position, rotation)| (player_id.0.to_bits(), position.0, rotation.0))
+        .collect();
+    states.sort_by_key(|(id, _, _)| *id);
+    states
+}
+
+/// Runs a server and 16 clients over UDP on localhost, with every player running around, and
+/// measures what the clients receive with their [`NetworkStats`] byte counters. That is
+/// everything on the wire: packet headers, pings and acks as well as the replicated state, after
+/// relevancy and the bandwidth cap. Returns the average and the max bytes/s over the clients.
+///
+/// Once the players stop, every client's copy of the players must match the server's.
+fn measure_bandwidth(format: WireFormat) -> (f32, f32) {
+    let mut server = server_app(format);
+    let mut clients: Vec<App> = (0..PLAYERS as u64)
+        .map(|index| client_app(FIRST_CLIENT_ID + index, format))
+        .collect();
+
+    println!("📶 {format:?}: connecting {PLAYERS} clients...");
+    let start = Instant::now();
+    while player_states::<()>(&mut server).len() < PLAYERS {
+        assert!(
+            start.elapsed() < CONNECT_TIMEOUT,
+            "Only {} of {PLAYERS} players spawned",
+            player_states::<()>(&mut server).len()
+        );
+        update_all(&mut server, &mut clients);
+    }
+
+    server.world_mut().resource_mut::<Running>().elapsed = Some(0.0);
+    server.world_mut().resource_mut::<Running>().moving = true;
+    run_for(WARMUP, &mut server, &mut clients, |_, _| {});
+
+    // The counters move once per second, the rate is taken between their first and last refresh
+    let mut refreshes: Vec<Option<(CounterRefresh, CounterRefresh)>> = vec![None; PLAYERS];
+    let mut last_seen: Vec<u64> = clients
+        .iter_mut()
+        .map(|c| client_stats(c).bytes_received)
+        .collect();
+    run_for(MEASURE, &mut server, &mut clients, |_, clients| {
+        for ((client, refresh), last_seen) in clients
+            .iter_mut()
+            .zip(refreshes.iter_mut())
+            .zip(last_seen.iter_mut())
+        {
+            let bytes = client_stats(client).bytes_received;
+            if bytes == *last_seen {
+                continue;
+            }
+            *last_seen = bytes;
+            let now = (Instant::now(), bytes);
+            *refresh = Some((refresh.map_or(now, |(first, _)| first), now));
+        }
+    });
+    let rates: Vec<f32> = refreshes
+        .iter()
+        .map(|refresh| {
+            let ((first_at, first), (last_at, last)) =
+                refresh.expect("Every client should have received packets");
+            (last - first) as f32 / (last_at - first_at).as_secs_f32()
+        })
+        .collect();
+    let average = rates.iter().sum::<f32>() / rates.len() as f32;
+    let max = rates.iter().copied().fold(0.0, f32::max);
+
+    server.world_mut().resource_mut::<Running>().moving = false;
+    run_for(SETTLE, &mut server, &mut clients, |_, _| {});
+    let server_states = player_states::<()>(&mut server);
+    for (index, client) in clients.iter_mut().enumerate() {
+        let client_states = player_states::<With<Confirmed>>(client);
+        assert!(
+            !client_states.is_empty(),
+            "{format:?}: client {index} should see at least its own player"
+        );
+        for (id, position, rotation) in client_states {
+            let (_, server_position, server_rotation) = server_states
+                .iter()
+                .find(|(server_id, _, _)| *server_id == id)
+                .expect("Client has a player the server doesn't");
+            assert!(
+                position.distance(*server_position) < 0.01,
+                "{format:?}: client {index} has player {id} at {position}, the server at \
+                 {server_position}"
+            );
+            assert!(
+                rotation.angle_between(*server_rotation) < 0.01,
+                "{format:?}: client {index} has player {id} rotated {rotation}, the server \
+                 {server_rotation}"
+            );
+        }
+    }
+    (average, max)
+}
+
+/// Compares the wire formats against lightyear's plain bincode encoding, one server and its
+/// clients at a time. The quantized format the game uses must send less, and stay under the
+/// budget. Run with `cargo test --test bandwidth_bench -- --nocapture` to see the numbers.
+#[test]
+fn bench_replication_bandwidth() {
+    let formats = [
+        WireFormat::Plain,
+        WireFormat::Quantized,
+        WireFormat::QuantizedDelta,
+    ];
+    let results: Vec<(WireFormat, (f32, f32))> = formats
+        .into_iter()
+        .map(|format| (format, measure_bandwidth(format)))
+        .collect();
+
+    let (_, (baseline, _)) = results[0];
+    println!("📶 {PLAYERS} players running, received per client:");
+    println!(
+        "  {:<16} {:>9} {:>9} {:>9}",
+        "format", "average", "max", "vs plain"
+    );
+    for (format, (average, max)) in &results {
+        println!(
+            "  {:<16} {average:>9.0} {max:>9.0} {:>8.0}%",
+            format!("{format:?}"),
+            average / baseline * 100.0
+        );
+    }
+
+    let rate = |format| {
+        results
+            .iter()
+            .find(|(measured, _)| *measured == format)
+            .map(|(_, rates)| *rates)
+            .unwrap()
+    };
+    let (quantized, max) = rate(WireFormat::default());
+    assert!(
+        quantized < baseline,
+        "{:?} should send less than plain bincode, got {quantized} bytes/s against {baseline}",
+        WireFormat::default()
+    );
+    assert!(
+        max < MAX_BYTES_PER_CLIENT_PER_SEC,
+        "Clients should receive less than {MAX_BYTES_PER_CLIENT_PER_SEC} bytes/s, got {max}"
+    );
+}
+
+/// The quantized values must stay within the precision the game relies on
+#[test]
+fn test_quantization_precision() {
+    for player in 0..PLAYERS {
+        for step in 0..100 {
+            let (position, rotation, velocity) = player_state(player, step as f32 * 0.37);
+
+            let decoded = round_trip(&position_serde(), &position);
+            assert!(
+                (decoded.0 - position.0).abs().max_element() <= POSITION_STEP,
+                "Position {:?} decoded as {:?}",
+                position.0,
+                decoded.0
+            );
+
+            let decoded = round_trip(&linear_velocity_serde(), &velocity);
+            assert!(
+                (decoded.0 - velocity.0).abs().max_element() <= VELOCITY_STEP,
+                "Velocity {:?} decoded as {:?}",
+                velocity.0,
+                decoded.0
+            );
+
+            let decoded = round_trip(&rotation_serde(), &rotation);
+            assert!(
+                decoded.0.angle_between(rotation.0) < 0.005,
+                "Rotation {:?} decoded as {:?}",
+                rotation.0,
+                decoded.0
+            );
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use avian3d::prelude::{LinearVelocity, PhysicsSet, Position};
+use bevy::prelude::*;
+use lightyear::prelude::client::{ClientPlugins, NetcodeClient, NetcodeConfig};
+use lightyear::prelude::{
+    Authentication, Client, Connect, InterpolationManager, Link, LocalAddr, MessageReceiver,
+    MessageSender, PeerAddr, PredictionManager, ReplicationReceiver, UdpIo,
+};
+use server::app::{ServerConfig, add_network_to_server_app};
+use shared::chat::{ChatChannel, ChatLine, ChatScope, ChatSend, PROXIMITY_CHAT_RANGE};
+use shared::protocol::{PlayerId, TEAM_COUNT};
+use shared::{FIXED_TIMESTEP_HZ, SERVER_ADDR, SHARED_SETTINGS, SharedPlugin};
+
+/// Client ids, and local ports from 4000 up, of the test clients. Teams go by client id.
+const FIRST_CLIENT_ID: u64 = 400;
+/// Where each player stands: the sender, a player of the other team next to it, and a team mate
+/// across the level. Players joining later stand in the middle.
+const PLAYER_SPOTS: [Vec3; 3] = [
+    Vec3::new(-9.0, 1.0, -9.0),
+    Vec3::new(-6.0, 1.0, -9.0),
+    Vec3::new(9.0, 1.0, 9.0),
+];
+const LATE_SPOT: Vec3 = Vec3::new(0.0, 1.0, 0.0);
+const SENDER: usize = 0;
+const NEAR_OPPONENT: usize = 1;
+const FAR_TEAM_MATE: usize = 2;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Time for chat to go through the server, it is sent reliably.
+const RELAY: Duration = Duration::from_secs(1);
+
+/// Each test runs its own server on the same address.
+static SERVER_LOCK: Mutex<()> = Mutex::new(());
+
+/// Keeps every player on its spot. Runs after the physics step so the level geometry doesn't get
+/// in the way.
+fn hold_spots(mut players: Query<(&PlayerId, &mut Position, &mut LinearVelocity)>) {
+    for (player_id, mut position, mut velocity) in players.iter_mut() {
+        let player = (player_id.0.to_bits() - FIRST_CLIENT_ID) as usize;
+        position.0 = PLAYER_SPOTS.get(player).copied().unwrap_or(LATE_SPOT);
+        *velocity = LinearVelocity::ZERO;
+    }
+}
+
+/// Chat lines a client received. Unread messages are dropped at the end of each update.
+#[derive(Resource, Default)]
+struct Heard(Vec<ChatLine>);
+
+fn record_chat(
+    mut receivers: Query<&mut MessageReceiver<ChatLine>, With<Client>>,
+    mut heard: ResMut<Heard>,
+) {
+    for mut receiver in receivers.iter_mut() {
+        heard.0.extend(receiver.receive());
+    }
+}
+
+fn headless_plugins(app: &mut App) {
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        AssetPlugin::default(),
+        bevy::scene::ScenePlugin,
+        bevy::render::mesh::MeshPlugin,
+        SharedPlugin,
+    ));
+}
+
+fn server_app() -> App {
+    let mut app = App::new();
+    headless_plugins(&mut app);
+    app.insert_resource(ServerConfig::default());
+    add_network_to_server_app(&mut app);
+    app.add_systems(FixedPostUpdate, hold_spots.after(PhysicsSet::Sync));
+    app.finish();
+    app.cleanup();
+    app
+}
+
+/// A client app with only the networking, connected like the game client does it.
+fn client_app(client_id: u64) -> App {
+    let mut app = App::new();
+    headless_plugins(&mut app);
+    app.add_plugins(ClientPlugins {
+        tick_duration: Duration::from_secs_f64(1.0 / FIXED_TIMESTEP_HZ),
+    });
+    app.init_resource::<Heard>();
+    app.add_systems(Update, record_chat);
+    app.finish();
+    app.cleanup();
+
+    let auth = Authentication::Manual {
+        server_addr: SERVER_ADDR,
+        client_id,
+        private_key: SHARED_SETTINGS.private_key,
+        protocol_id: SHARED_SETTINGS.protocol_id,
+    };
+    let netcode_client = NetcodeClient::new(auth, NetcodeConfig::default())
+        .expect("Failed to create netcode client");
+    let client = app
+        .world_mut()
+        .spawn((
+            Client::default(),
+            LocalAddr(SocketAddr::new(
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+                4000 + client_id as u16,
+            )),
+            PeerAddr(SERVER_ADDR),
+            Link::new(None),
+            ReplicationReceiver::default(),
+            PredictionManager::default(),
+            InterpolationManager::default(),
+            netcode_client,
+            UdpIo::default(),
+        ))
+        .id();
+    app.world_mut().trigger_targets(Connect, client);
+    app
+}
+
+fn update_all(server: &mut App, clients: &mut [App]) {
+    server.update();
+    for client in clients.iter_mut() {
+        client.update();
+    }
+    std::thread::sleep(Duration::from_millis(1));
+}
+
+fn run_for(duration: Duration, server: &mut App, clients: &mut [App]) {
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        update_all(server, clients);
+    }
+}
+
+fn player_count(server: &mut App) -> usize {
+    server
+        .world_mut()
+        .query::<&PlayerId>()
+        .iter(server.world())
+        .count()
+}
+
+/// Connects clients until every one of them has a player, then lets the players reach their
+/// spots.
+fn connect(server: &mut App, clients: &mut Vec<App>, count: usize) {
+    let players = player_count(server) + count;
+    for _ in 0..count {
+        clients.push(client_app(FIRST_CLIENT_ID + clients.len() as u64));
+    }
+    let start = Instant::now();
+    while player_count(server) < players {
+        assert!(
+            start.elapsed() < CONNECT_TIMEOUT,
+            "Only {} of {players} players spawned",
+            player_count(server)
+        );
+        update_all(server, clients);
+    }
+    run_for(RELAY, server, clients);
+}
+
+fn send(client: &mut App, scope: ChatScope, text: &str) {
+    client
+        .world_mut()
+        .query_filtered::<&mut MessageSender<ChatSend>, With<Client>>()
+        .single_mut(client.world_mut())
+        .expect("Client should send chat")
+        .send::<ChatChannel>(ChatSend {
+            scope,
+            text: text.to_string(),
+        });
+}
+
+/// Texts every client received since the last call.
+fn heard(clients: &mut [App]) -> Vec<Vec<String>> {
+    clients
+        .iter_mut()
+        .map(|client| {
+            std::mem::take(&mut client.world_mut().resource_mut::<Heard>().0)
+                .into_iter()
+                .map(|line| line.text)
+                .collect()
+        })
+        .collect()
+}
+
+fn setup() -> (App, Vec<App>) {
+    let mut server = server_app();
+    let mut clients = Vec::new();
+    connect(&mut server, &mut clients, PLAYER_SPOTS.len());
+    heard(&mut clients);
+    (server, clients)
+}
+
+/// All messages reach everyone, team messages the sender's team wherever they are, and proximity
+/// messages the players close to the sender whatever their team
+#[test]
+fn test_chat_scopes() {
+    let _lock = SERVER_LOCK
+        .lock()
+        .unwrap_or_else(|error| error.into_inner());
+    let team = |index: usize| (FIRST_CLIENT_ID + index as u64) % TEAM_COUNT as u64;
+    assert_ne!(team(SENDER), team(NEAR_OPPONENT));
+    assert_eq!(team(SENDER), team(FAR_TEAM_MATE));
+    assert!(PLAYER_SPOTS[SENDER].distance(PLAYER_SPOTS[NEAR_OPPONENT]) < PROXIMITY_CHAT_RANGE);
+    assert!(PLAYER_SPOTS[SENDER].distance(PLAYER_SPOTS[FAR_TEAM_MATE]) > PROXIMITY_CHAT_RANGE);
+
+    let (mut server, mut clients) = setup();
+    send(&mut clients[SENDER], ChatScope::All, "everyone");
+    send(&mut clients[SENDER], ChatScope::Team, "team");
+    send(&mut clients[SENDER], ChatScope::Proximity, "nearby");
+    run_for(RELAY, &mut server, &mut clients);
+
+    let heard = heard(&mut clients);
+    assert_eq!(heard[SENDER], ["everyone", "team", "nearby"]);
+    assert_eq!(heard[NEAR_OPPONENT], ["everyone", "nearby"]);
+    assert_eq!(heard[FAR_TEAM_MATE], ["everyone", "team"]);
+}
+
+/// A client sending too fast gets the first few messages through, then one more for every second
+/// it waits
+#[test]
+fn test_chat_rate_limit() {
+    let _lock = SERVER_LOCK
+        .lock()
+        .unwrap_or_else(|error| error.into_inner());
+    let (mut server, mut clients) = setup();
+
+    let spam: Vec<String> = (0..10).map(|index| format!("spam {index}")).collect();
+    for text in &spam {
+        send(&mut clients[SENDER], ChatScope::All, text);
+    }
+    run_for(RELAY, &mut server, &mut clients);
+    let heard_spam = &heard(&mut clients)[NEAR_OPPONENT];
+    assert_eq!(heard_spam, &spam[..5], "Only the burst should get through");
+
+    // A second and a half after the burst, one more message fits but not two
+    run_for(Duration::from_millis(500), &mut server, &mut clients);
+    send(&mut clients[SENDER], ChatScope::All, "again");
+    send(&mut clients[SENDER], ChatScope::All, "too soon");
+    run_for(RELAY, &mut server, &mut clients);
+    assert_eq!(heard(&mut clients)[NEAR_OPPONENT], ["again"]);
+}
+
+/// Players joining later get the all messages and their team's messages sent before, marked as
+/// history, but not the proximity ones
+#[test]
+fn test_chat_history_replay() {
+    let _lock = SERVER_LOCK
+        .lock()
+        .unwrap_or_else(|error| error.into_inner());
+    let (mut server, mut clients) = setup();
+    send(&mut clients[SENDER], ChatScope::All, "everyone");
+    send(&mut clients[SENDER], ChatScope::Team, "team");
+    send(&mut clients[SENDER], ChatScope::Proximity, "nearby");
+    run_for(RELAY, &mut server, &mut clients);
+    heard(&mut clients);
+
+    // One late player on each team
+    connect(&mut server, &mut clients, 2);
+    let late: Vec<(u64, Vec<ChatLine>)> = clients[PLAYER_SPOTS.len()..]
+        .iter_mut()
+        .enumerate()
+        .map(|(index, client)| {
+            let id = FIRST_CLIENT_ID + (PLAYER_SPOTS.len() + index) as u64;
+            (
+                id,
+                std::mem::take(&mut client.world_mut().resource_mut::<Heard>().0),
+            )
+        })
+        .collect();
+    let sender_team = (FIRST_CLIENT_ID + SENDER as u64) % TEAM_COUNT as u64;
+    for (id, lines) in late {
+        assert!(
+            lines.iter().all(|line| line.history),
+            "Client {id} should only get replayed lines"
+        );
+        let texts: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+        if id % TEAM_COUNT as u64 == sender_team {
+            assert_eq!(
+                texts,
+                ["everyone", "team"],
+                "Client {id} is on the sender's team"
+            );
+        } else {
+            assert_eq!(texts, ["everyone"], "Client {id} is on the other team");
+        }
+    }
+
+    // Players who were there don't get the history again
+    assert!(
+        heard(&mut clients)[..PLAYER_SPOTS.len()]
+            .iter()
+            .all(|lines| lines.is_empty())
+    );
+}
@@ -0,0 +1,118 @@
+use bevy::math::Vec3;
+use server::director::{Director, DirectorPhase, DirectorSpawn, DirectorTunables};
+
+/// Seconds per director step, one fixed tick.
+const STEP: f32 = 1.0 / 64.0;
+
+/// Runs the director for `seconds` at a constant intensity and returns what it spawned.
+fn run(director: &mut Director, intensity: f32, enemies: u32, seconds: f32) -> Vec<DirectorSpawn> {
+    let steps = (seconds / STEP).round() as usize;
+    (0..steps)
+        .filter_map(|_| director.update(intensity, enemies, STEP))
+        .collect()
+}
+
+/// Test that the same seed and inputs always give the same spawns and spawn positions
+#[test]
+fn test_director_is_deterministic() {
+    let candidates: Vec<Vec3> = (0..50).map(|i| Vec3::new(i as f32, 0.0, 0.0)).collect();
+    let record = |seed: u64| {
+        let mut director = Director::new(DirectorTunables::default(), seed);
+        let mut history = Vec::new();
+        for intensity in [0.0, 10.0, 80.0, 50.0, 0.0, 0.0, 90.0] {
+            for spawn in run(&mut director, intensity, 0, 40.0) {
+                history.push((director.phase(), spawn, director.pick(&candidates)));
+            }
+        }
+        history
+    };
+
+    let first = record(42);
+    assert!(
+        !first.is_empty(),
+        "the director should have spawned enemies"
+    );
+    assert_eq!(first, record(42));
+    assert_ne!(first, record(7));
+}
+
+/// Test the build-up, peak and relax cycle
+#[test]
+fn test_director_phases() {
+    let tunables = DirectorTunables::default();
+    let mut director = Director::new(tunables.clone(), 1);
+    assert_eq!(director.phase(), DirectorPhase::BuildUp);
+
+    // Calm players only meet wandering enemies
+    let spawns = run(&mut director, 0.0, 0, tunables.wanderer_interval.1 * 2.0);
+    assert!(!spawns.is_empty());
+    assert!(spawns.iter().all(|spawn| *spawn == DirectorSpawn::Wanderer));
+    assert_eq!(director.phase(), DirectorPhase::BuildUp);
+
+    // Stressed players get a wave and a peak
+    let spawns = run(&mut director, tunables.peak_intensity, 0, STEP);
+    let Some(DirectorSpawn::Wave(size)) = spawns.first() else {
+        panic!("expected a wave, got {spawns:?}");
+    };
+    assert!((tunables.wave_size.0..=tunables.wave_size.1).contains(size));
+    assert_eq!(director.phase(), DirectorPhase::Peak);
+
+    // Nothing spawns while the peak and the relax last
+    assert!(run(&mut director, 100.0, 0, tunables.peak_duration).is_empty());
+    assert_eq!(director.phase(), DirectorPhase::Relax);
+    assert!(run(&mut director, 100.0, 0, tunables.relax_duration * 2.0).is_empty());
+    assert_eq!(director.phase(), DirectorPhase::Relax);
+
+    // The next build-up waits for the players to calm down
+    run(&mut director, 0.0, 0, STEP);
+    assert_eq!(director.phase(), DirectorPhase::BuildUp);
+}
+
+/// Test that quiet teams still get a wave and that waves respect the enemy cap
+#[test]
+fn test_director_forces_capped_peak() {
+    let tunables = DirectorTunables {
+        max_enemies: 10,
+        wave_size: (5, 5),
+        ..Default::default()
+    };
+    let mut director = Director::new(tunables.clone(), 3);
+    let spawns = run(&mut director, 0.0, 8, tunables.max_build_up + STEP);
+    assert_eq!(spawns.last(), Some(&DirectorSpawn::Wave(2)));
+    assert_eq!(director.phase(), DirectorPhase::Peak);
+
+    // Full house, no more spawns
+    let mut director = Director::new(tunables.clone(), 3);
+    assert!(run(&mut director, 0.0, 10, tunables.max_build_up + STEP).is_empty());
+}
+
+/// Test that a tunables file with an inverted range is rejected instead of panicking the director
+#[test]
+fn test_director_rejects_inverted_ranges() {
+    let path = std::env::temp_dir().join(format!("director_tunables_{}.ron", std::process::id()));
+    let load = |text: &str| {
+        std::fs::write(&path, text).expect("Failed to write the tunables");
+        DirectorTunables::load(&path)
+    };
+
+    let tunables = load("(wave_size: (2, 4), max_enemies: 8)");
+    assert_eq!(tunables.wave_size, (2, 4));
+    assert_eq!(tunables.max_enemies, 8);
+
+    for text in [
+        "(wave_size: (6, 3), max_enemies: 8)",
+        "(wanderer_interval: (20.0, 10.0))",
+        "(spawn_distance: (30.0, 12.0))",
+    ] {
+        let tunables = load(text);
+        assert_eq!(
+            tunables,
+            DirectorTunables::default(),
+            "{text} should be rejected"
+        );
+        // Picks in the ranges without panicking
+        let mut director = Director::new(tunables.clone(), 1);
+        run(&mut director, tunables.peak_intensity, 0, STEP);
+    }
+    std::fs::remove_file(&path).ok();
+}
@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use avian3d::prelude::*;
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use lightyear::prelude::PeerId;
+use server::director::{DirectorPlugin, PlayerCondition};
+use server::enemy::{EnemyPlugin, spawn_enemy};
+use server::navigation::NavigationPlugin;
+use server::noise::{NoiseEvent, NoisePlugin, NoiseSource};
+use shared::enemy::EnemyState;
+use shared::input::Stance;
+use shared::protocol::PlayerId;
+use shared::scene::FloorMarker;
+
+/// Height of a character capsule center standing on the floor.
+const STANDING_HEIGHT: f32 = 1.0;
+
+/// An open floor, with the enemies and the director but without a match.
+fn level_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        AssetPlugin::default(),
+        bevy::scene::ScenePlugin,
+        bevy::render::mesh::MeshPlugin,
+        PhysicsPlugins::default(),
+        NavigationPlugin,
+        NoisePlugin,
+        DirectorPlugin,
+        EnemyPlugin,
+    ));
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+        20,
+    )));
+    app.finish();
+    app.cleanup();
+
+    app.world_mut().spawn((
+        FloorMarker,
+        Position(Vec3::new(0.0, -0.5, 0.0)),
+        RigidBody::Static,
+        Collider::cuboid(30.0, 1.0, 30.0),
+    ));
+    run(&mut app, 10);
+    app
+}
+
+fn run(app: &mut App, updates: usize) {
+    for _ in 0..updates {
+        app.update();
+    }
+}
+
+fn enemy(app: &mut App, position: Vec3, investigate: Option<Vec3>) -> Entity {
+    let enemy = app
+        .world_mut()
+        .run_system_once(move |mut commands: Commands| {
+            spawn_enemy(&mut commands, position, investigate)
+        })
+        .unwrap();
+    app.update();
+    enemy
+}
+
+fn player(app: &mut App, position: Vec3) -> Entity {
+    app.world_mut()
+        .spawn((
+            PlayerId(PeerId::Netcode(1)),
+            Position(position),
+            RigidBody::Kinematic,
+            Stance::Standing.collider(),
+        ))
+        .id()
+}
+
+fn state(app: &App, enemy: Entity) -> EnemyState {
+    *app.world().get::<EnemyState>(enemy).unwrap()
+}
+
+/// Updates the app until `enemy` is in `expected`, for at most `seconds`
+fn run_until(app: &mut App, enemy: Entity, expected: EnemyState, seconds: f32) -> bool {
+    for _ in 0..(seconds / 0.02) as usize {
+        app.update();
+        if state(app, enemy) == expected {
+            return true;
+        }
+    }
+    false
+}
+
+/// Test that a loud enough noise wakes a sleeping enemy up and sends it where the noise was made
+#[test]
+fn test_noise_wakes_sleeping_enemy() {
+    let mut app = level_app();
+    let enemy = enemy(&mut app, Vec3::Y * STANDING_HEIGHT, None);
+    run(&mut app, 5);
+    assert_eq!(state(&app, enemy), EnemyState::Sleeping);
+
+    // Too quiet to wake it up
+    app.world_mut().send_event(NoiseEvent {
+        position: Vec3::new(8.0, STANDING_HEIGHT, 0.0),
+        loudness: 0.05,
+        source: NoiseSource::Footstep(Stance::Crouching),
+        emitter: Entity::PLACEHOLDER,
+    });
+    run(&mut app, 5);
+    assert_eq!(state(&app, enemy), EnemyState::Sleeping);
+
+    let noise = Vec3::new(4.0, STANDING_HEIGHT, 0.0);
+    app.world_mut().send_event(NoiseEvent {
+        position: noise,
+        loudness: 1.0,
+        source: NoiseSource::Gunfire,
+        emitter: Entity::PLACEHOLDER,
+    });
+    run(&mut app, 5);
+    assert_eq!(state(&app, enemy), EnemyState::Investigating);
+
+    // It walks to the noise, then searches around it
+    let start = app.world().get::<Position>(enemy).unwrap().0;
+    assert!(run_until(&mut app, enemy, EnemyState::Searching, 10.0));
+    let position = app.world().get::<Position>(enemy).unwrap().0;
+    assert!(position.xz().distance(noise.xz()) < start.xz().distance(noise.xz()));
+}
+
+/// Test that an enemy chases the player it sees, hurts it within reach and searches where it lost
+/// it
+#[test]
+fn test_chase_attack_and_lose_target() {
+    let mut app = level_app();
+    // Enemies look down -Z when they spawn
+    let player = player(&mut app, Vec3::new(0.0, STANDING_HEIGHT, -6.0));
+    let enemy = enemy(
+        &mut app,
+        Vec3::Y * STANDING_HEIGHT,
+        Some(Vec3::new(0.0, STANDING_HEIGHT, -1.0)),
+    );
+    assert!(run_until(&mut app, enemy, EnemyState::Chasing, 1.0));
+    assert!(run_until(&mut app, enemy, EnemyState::Attacking, 10.0));
+
+    // A hit every second
+    run(&mut app, 120);
+    let health = app.world().get::<PlayerCondition>(player).unwrap().health;
+    assert!(
+        (0.5..1.0).contains(&health),
+        "the player should have been hit a few times, health is {health}"
+    );
+
+    // Out of sight, the enemy goes to where it last saw the player and searches around there
+    let last_seen = app.world().get::<Position>(player).unwrap().0;
+    app.world_mut().despawn(player);
+    assert!(run_until(&mut app, enemy, EnemyState::Searching, 10.0));
+    let position = app.world().get::<Position>(enemy).unwrap().0;
+    assert!(position.xz().distance(last_seen.xz()) < 2.0);
+}
@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use avian3d::prelude::*;
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use lightyear::prelude::PeerId;
+use server::interaction::{Interacted, InteractionPlugin, can_reach};
+use server::noise::NoisePlugin;
+use shared::input::Stance;
+use shared::interaction::{Door, Interactable, InteractableKind, apply_door_state};
+use shared::protocol::PlayerId;
+use shared::scene::{WALL_HEIGHT, WALL_THICKNESS, WallMarker, WallSize, add_wall_physics};
+
+fn door_app() -> (App, Entity) {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        AssetPlugin::default(),
+        bevy::scene::ScenePlugin,
+        bevy::render::mesh::MeshPlugin,
+        PhysicsPlugins::default(),
+    ));
+    app.add_plugins((NoisePlugin, InteractionPlugin));
+    app.add_observer(add_wall_physics);
+    app.add_systems(
+        FixedPostUpdate,
+        apply_door_state.before(PhysicsSet::Prepare),
+    );
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+        20,
+    )));
+    app.finish();
+    app.cleanup();
+
+    let door = app
+        .world_mut()
+        .spawn((
+            Name::new("Door"),
+            WallMarker,
+            WallSize(Vec3::new(1.5, WALL_HEIGHT, WALL_THICKNESS)),
+            Door::default(),
+            Interactable {
+                kind: InteractableKind::Door,
+                hold_secs: 0.0,
+                locked: false,
+            },
+            Position(Vec3::new(0.0, WALL_HEIGHT / 2.0, 0.0)),
+            Rotation::default(),
+        ))
+        .id();
+    app.update();
+    app.update();
+    (app, door)
+}
+
+fn use_door(app: &mut App, door: Entity) {
+    let player = app.world_mut().spawn_empty().id();
+    app.world_mut().trigger(Interacted {
+        player,
+        target: door,
+        kind: InteractableKind::Door,
+    });
+    app.update();
+    app.update();
+}
+
+/// Test that using a door opens it, letting rays through, and that it won't close on a player
+#[test]
+fn test_door_opens_and_stays_open_when_blocked() {
+    let (mut app, door) = door_app();
+    use_door(&mut app, door);
+    assert!(app.world().get::<Door>(door).unwrap().open);
+    assert!(app.world().get::<ColliderDisabled>(door).is_some());
+    let blocked = app
+        .world_mut()
+        .run_system_once(|spatial_query: SpatialQuery| {
+            spatial_query
+                .cast_ray(
+                    Vec3::new(0.0, 1.0, 2.0),
+                    Dir3::NEG_Z,
+                    4.0,
+                    true,
+                    &SpatialQueryFilter::default(),
+                )
+                .is_some()
+        })
+        .unwrap();
+    assert!(!blocked, "an open door should let rays through");
+
+    // A player in the doorway keeps it open
+    let player = app
+        .world_mut()
+        .spawn((
+            PlayerId(PeerId::Netcode(1)),
+            Stance::Standing.collider(),
+            RigidBody::Kinematic,
+            Position(Vec3::new(0.0, 1.0, 0.0)),
+        ))
+        .id();
+    app.update();
+    use_door(&mut app, door);
+    assert!(app.world().get::<Door>(door).unwrap().open);
+
+    app.world_mut().despawn(player);
+    app.update();
+    use_door(&mut app, door);
+    assert!(!app.world().get::<Door>(door).unwrap().open);
+    assert!(app.world().get::<ColliderDisabled>(door).is_none());
+}
+
+/// Test that players have to be close to an interactable and facing it to use it
+#[test]
+fn test_reach_needs_range_and_facing() {
+    let (mut app, door) = door_app();
+    let reach = move |eye: Vec3, forward: Vec3| {
+        move |spatial_query: SpatialQuery, aabbs: Query<&ColliderAabb>| {
+            let filter = SpatialQueryFilter::default();
+            can_reach(
+                eye,
+                forward,
+                door,
+                aabbs.get(door).unwrap(),
+                &spatial_query,
+                &filter,
+            )
+        }
+    };
+    let world = app.world_mut();
+    let eye = Vec3::new(0.0, 1.6, 1.5);
+    assert!(world.run_system_once(reach(eye, Vec3::NEG_Z)).unwrap());
+    assert!(!world.run_system_once(reach(eye, Vec3::Z)).unwrap());
+    let far = Vec3::new(0.0, 1.6, 5.0);
+    assert!(!world.run_system_once(reach(far, Vec3::NEG_Z)).unwrap());
+}
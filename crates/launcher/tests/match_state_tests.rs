@@ -0,0 +1,154 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use lightyear::connection::server::Started;
+use lightyear::prelude::PeerId;
+use server::match_state::{EndRound, MatchStatePlugin, StartExtraction};
+use shared::game_state::{MatchPhase, MatchState};
+use shared::protocol::PlayerId;
+
+/// Seconds per update. Virtual time moves at most this much per frame.
+const STEP: f32 = 0.25;
+
+/// A server that just started, with its match in warmup.
+fn match_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, MatchStatePlugin));
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+        STEP,
+    )));
+    app.finish();
+    app.cleanup();
+    app.world_mut().spawn(Started);
+    app.update();
+    app
+}
+
+fn state(app: &mut App) -> MatchState {
+    *app.world_mut()
+        .query::<&MatchState>()
+        .single(app.world())
+        .expect("the match should have spawned")
+}
+
+fn run(app: &mut App, seconds: f32) {
+    for _ in 0..(seconds / STEP).ceil() as usize {
+        app.update();
+    }
+}
+
+fn join(app: &mut App) -> Entity {
+    app.world_mut().spawn(PlayerId(PeerId::Netcode(1))).id()
+}
+
+/// Plays the warmup with one player in, leaving the round in progress.
+fn start_round(app: &mut App) -> Entity {
+    let player = join(app);
+    run(app, 30.0 + STEP);
+    assert_eq!(state(app).phase, MatchPhase::InProgress);
+    player
+}
+
+/// Test that the warmup only counts down once a player is in, then starts the round
+#[test]
+fn test_warmup_waits_for_players() {
+    let mut app = match_app();
+    assert_eq!(
+        state(&mut app),
+        MatchState {
+            phase: MatchPhase::Warmup,
+            round: 1,
+            seconds_left: 30,
+        }
+    );
+
+    run(&mut app, 60.0);
+    assert_eq!(state(&mut app).phase, MatchPhase::Warmup);
+    assert_eq!(state(&mut app).seconds_left, 30);
+
+    join(&mut app);
+    run(&mut app, 10.0);
+    assert_eq!(state(&mut app).phase, MatchPhase::Warmup);
+    assert!(state(&mut app).seconds_left <= 21);
+
+    run(&mut app, 20.0 + STEP);
+    assert_eq!(
+        state(&mut app),
+        MatchState {
+            phase: MatchPhase::InProgress,
+            round: 1,
+            seconds_left: 600,
+        }
+    );
+}
+
+/// Test a won round: extraction, victory, intermission and the warmup of the next round
+#[test]
+fn test_extraction_wins_the_round() {
+    let mut app = match_app();
+    start_round(&mut app);
+
+    app.world_mut().trigger(StartExtraction);
+    assert_eq!(state(&mut app).phase, MatchPhase::Extraction);
+    assert_eq!(state(&mut app).seconds_left, 60);
+
+    // A second extraction doesn't restart the countdown
+    run(&mut app, 30.0);
+    app.world_mut().trigger(StartExtraction);
+    assert!(state(&mut app).seconds_left <= 30);
+
+    run(&mut app, 30.0 + STEP);
+    assert_eq!(state(&mut app).phase, MatchPhase::Victory);
+    run(&mut app, 8.0);
+    assert_eq!(state(&mut app).phase, MatchPhase::Intermission);
+    run(&mut app, 10.0);
+    assert_eq!(
+        state(&mut app),
+        MatchState {
+            phase: MatchPhase::Warmup,
+            round: 2,
+            seconds_left: 30,
+        }
+    );
+}
+
+/// Test that running out of time loses the round
+#[test]
+fn test_round_times_out_to_defeat() {
+    let mut app = match_app();
+    start_round(&mut app);
+
+    run(&mut app, 600.0 - 1.0);
+    assert_eq!(state(&mut app).phase, MatchPhase::InProgress);
+    run(&mut app, 1.0 + STEP);
+    assert_eq!(state(&mut app).phase, MatchPhase::Defeat);
+}
+
+/// Test that rounds only end or extract while they are played
+#[test]
+fn test_end_round_only_during_the_round() {
+    let mut app = match_app();
+    app.world_mut().trigger(EndRound { victory: true });
+    app.world_mut().trigger(StartExtraction);
+    assert_eq!(state(&mut app).phase, MatchPhase::Warmup);
+
+    start_round(&mut app);
+    app.world_mut().trigger(EndRound { victory: false });
+    assert_eq!(state(&mut app).phase, MatchPhase::Defeat);
+    assert_eq!(state(&mut app).seconds_left, 8);
+
+    app.world_mut().trigger(EndRound { victory: true });
+    assert_eq!(state(&mut app).phase, MatchPhase::Defeat);
+}
+
+/// Test that a round nobody plays anymore goes straight to the intermission
+#[test]
+fn test_empty_round_goes_to_intermission() {
+    let mut app = match_app();
+    let player = start_round(&mut app);
+
+    app.world_mut().despawn(player);
+    app.update();
+    assert_eq!(state(&mut app).phase, MatchPhase::Intermission);
+}
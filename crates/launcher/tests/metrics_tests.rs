@@ -0,0 +1,65 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const METRICS_PORT: &str = "27116";
+
+fn scrape(path: &str) -> Option<String> {
+    let mut stream = TcpStream::connect(format!("127.0.0.1:{METRICS_PORT}")).ok()?;
+    write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").ok()?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).ok()?;
+    Some(response)
+}
+
+/// Test that the metrics endpoint serves Prometheus text once enabled
+#[test]
+fn test_metrics_endpoint() {
+    println!("📈 Testing metrics endpoint...");
+
+    let mut server_process = Command::new("cargo")
+        .args([
+            "run",
+            "--package",
+            "launcher",
+            "--",
+            "server",
+            "--headless",
+            "--metrics-port",
+            METRICS_PORT,
+        ])
+        .current_dir("../../")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start server process");
+
+    // The page is rebuilt every second, wait until the first rendering is there
+    let start = Instant::now();
+    let mut metrics = None;
+    while start.elapsed() < Duration::from_secs(30) {
+        if let Some(response) = scrape("/metrics")
+            && response.contains("yolo_connected_clients")
+        {
+            metrics = Some(response);
+            break;
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
+    let metrics = metrics.expect("metrics endpoint should answer");
+
+    assert!(metrics.starts_with("HTTP/1.1 200 OK"));
+    assert!(metrics.contains("yolo_connected_clients 0"));
+    assert!(metrics.contains("yolo_tick_duration_seconds_count"));
+    assert!(metrics.contains("yolo_replicated_entities"));
+
+    let not_found = scrape("/").expect("metrics endpoint should answer");
+    assert!(not_found.starts_with("HTTP/1.1 404"));
+
+    let _ = server_process.kill();
+    let _ = server_process.wait();
+
+    println!("✅ Metrics test completed");
+}
@@ -1,3 +1,5 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
 use std::process::{Command, Stdio};
 use std::thread;
 use std::time::{Duration, Instant};
@@ -380,3 +382,75 @@ fn test_game_session_lifecycle() {
 
     println!("✅ Game session lifecycle test completed");
 }
+
+/// Test that clients stay connected through a simulated 150ms RTT link with jitter and loss, set
+/// on the client alone
+#[test]
+fn test_clients_under_simulated_conditions() {
+    println!("🐢 Testing clients under simulated network conditions...");
+
+    const METRICS_PORT: &str = "27117";
+    let sim_args = [
+        "--sim-latency",
+        "150",
+        "--sim-jitter",
+        "20",
+        "--sim-loss",
+        "5",
+    ];
+
+    let mut server_process = Command::new("cargo")
+        .args(["run", "--package", "launcher", "--", "server", "--headless"])
+        .args(["--metrics-port", METRICS_PORT])
+        .current_dir("../../")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start server process");
+
+    thread::sleep(Duration::from_millis(1000));
+
+    let mut client_process = Command::new("cargo")
+        .args(["run", "--package", "launcher", "--", "client"])
+        .args(["--client-id", "1", "--autoconnect"])
+        .args(sim_args)
+        .current_dir("../../")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start client process");
+
+    // The server measures the RTT through the client's conditioned link, both ways
+    let start = Instant::now();
+    let mut rtt = None;
+    while start.elapsed() < Duration::from_secs(30) && rtt.is_none() {
+        thread::sleep(Duration::from_millis(500));
+        let Ok(mut stream) = TcpStream::connect(format!("127.0.0.1:{METRICS_PORT}")) else {
+            continue;
+        };
+        let mut metrics = String::new();
+        if write!(stream, "GET /metrics HTTP/1.1\r\n\r\n").is_err()
+            || stream.read_to_string(&mut metrics).is_err()
+        {
+            continue;
+        }
+        rtt = metrics
+            .lines()
+            .find(|line| line.starts_with("yolo_client_rtt_seconds{"))
+            .and_then(|line| line.split_whitespace().last()?.parse::<f64>().ok())
+            .filter(|rtt| *rtt > 0.0);
+    }
+
+    let _ = client_process.kill();
+    let _ = client_process.wait();
+    let _ = server_process.kill();
+    let _ = server_process.wait();
+
+    let rtt = rtt.expect("client should connect through the conditioned link");
+    assert!(
+        rtt >= 0.14,
+        "RTT should include the whole simulated latency, got {rtt}s"
+    );
+
+    println!("✅ Simulated conditions test completed with {rtt:.3}s RTT");
+}
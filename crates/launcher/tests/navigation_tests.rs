@@ -0,0 +1,203 @@
+use std::time::Duration;
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use server::navigation::{NavGrid, NavLink, NavigationPlugin};
+use shared::interaction::Door;
+use shared::scene::{FloorMarker, WallMarker};
+
+/// A floor split by a wall with a gap at one end.
+fn level_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        AssetPlugin::default(),
+        bevy::scene::ScenePlugin,
+        bevy::render::mesh::MeshPlugin,
+        PhysicsPlugins::default(),
+        NavigationPlugin,
+    ));
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+        20,
+    )));
+    app.finish();
+    app.cleanup();
+
+    app.world_mut().spawn((
+        FloorMarker,
+        Position(Vec3::new(0.0, -0.5, 0.0)),
+        RigidBody::Static,
+        Collider::cuboid(20.0, 1.0, 20.0),
+    ));
+    app.world_mut().spawn((
+        WallMarker,
+        Position(Vec3::new(-3.0, 1.5, 0.0)),
+        RigidBody::Static,
+        Collider::cuboid(14.0, 3.0, 0.5),
+    ));
+    for _ in 0..10 {
+        app.update();
+    }
+    app
+}
+
+#[test]
+fn test_path_goes_around_wall() {
+    let app = level_app();
+    let grid = app.world().resource::<NavGrid>();
+    assert!(grid.edges().next().is_some(), "grid should have been built");
+
+    let from = Vec3::new(-6.0, 1.25, -5.0);
+    let to = Vec3::new(-6.0, 1.25, 5.0);
+    let path = grid
+        .find_path(from, to)
+        .expect("the gap should connect both sides");
+
+    // The wall spans x in [-10, 4], so the path has to go through the gap past x = 4
+    assert!(
+        path.iter().any(|waypoint| waypoint.x > 4.0),
+        "path {path:?} should go through the gap"
+    );
+    assert!(path.last().unwrap().distance(to.with_y(0.0)) < 1.0);
+}
+
+#[test]
+fn test_grid_rebuilds_when_level_changes() {
+    let mut app = level_app();
+    let wall = app
+        .world_mut()
+        .query_filtered::<Entity, With<WallMarker>>()
+        .single(app.world())
+        .unwrap();
+    app.world_mut().despawn(wall);
+    for _ in 0..10 {
+        app.update();
+    }
+
+    let grid = app.world().resource::<NavGrid>();
+    let from = Vec3::new(-6.0, 1.25, -5.0);
+    let to = Vec3::new(-6.0, 1.25, 5.0);
+    let path = grid.find_path(from, to).expect("path should exist");
+    // Nothing in the way anymore, smoothing walks straight there
+    assert_eq!(path.len(), 1, "path {path:?} should be a straight line");
+}
+
+/// Test that a door in the gap cuts the path while closed and lets it through once open, and that
+/// only the cells around the door change
+#[test]
+fn test_grid_updates_around_doors() {
+    let mut app = level_app();
+    let door = app
+        .world_mut()
+        .spawn((
+            WallMarker,
+            Door::default(),
+            Position(Vec3::new(7.0, 1.5, 0.0)),
+            RigidBody::Static,
+            Collider::cuboid(6.0, 3.0, 0.5),
+        ))
+        .id();
+    for _ in 0..10 {
+        app.update();
+    }
+    let from = Vec3::new(-6.0, 1.25, -5.0);
+    let to = Vec3::new(-6.0, 1.25, 5.0);
+    let closed: Vec<Vec3> = app
+        .world()
+        .resource::<NavGrid>()
+        .walkable_positions()
+        .collect();
+    assert!(
+        app.world()
+            .resource::<NavGrid>()
+            .find_path(from, to)
+            .is_none(),
+        "the closed door should block the gap"
+    );
+
+    app.world_mut()
+        .entity_mut(door)
+        .insert((Door { open: true }, ColliderDisabled));
+    for _ in 0..10 {
+        app.update();
+    }
+    let grid = app.world().resource::<NavGrid>();
+    let path = grid
+        .find_path(from, to)
+        .expect("the open door should let the path through");
+    assert!(path.iter().any(|waypoint| waypoint.x > 4.0));
+
+    // Cells away from the door are kept as they were
+    let open: Vec<Vec3> = grid.walkable_positions().collect();
+    assert!(open.len() > closed.len());
+    let far_from_door = |position: &&Vec3| position.xz().distance(Vec2::new(7.0, 0.0)) > 5.0;
+    assert!(
+        closed
+            .iter()
+            .filter(far_from_door)
+            .all(|cell| open.contains(cell))
+    );
+    assert_eq!(
+        closed.iter().filter(far_from_door).count(),
+        open.iter().filter(far_from_door).count()
+    );
+}
+
+/// Test that a link connects both sides of a wall with the gap closed, and that removing it cuts
+/// them apart again
+#[test]
+fn test_links_connect_what_the_grid_cannot() {
+    let mut app = level_app();
+    app.world_mut().spawn((
+        WallMarker,
+        Position(Vec3::new(7.0, 1.5, 0.0)),
+        RigidBody::Static,
+        Collider::cuboid(6.0, 3.0, 0.5),
+    ));
+    for _ in 0..10 {
+        app.update();
+    }
+    let from = Vec3::new(-6.0, 1.25, -5.0);
+    let to = Vec3::new(-6.0, 1.25, 5.0);
+    assert!(
+        app.world()
+            .resource::<NavGrid>()
+            .find_path(from, to)
+            .is_none(),
+        "the wall should split the level"
+    );
+
+    let link = NavLink {
+        from: Vec3::new(0.0, 0.0, -4.0),
+        to: Vec3::new(0.0, 0.0, 4.0),
+    };
+    let link_entity = app.world_mut().spawn(link).id();
+    for _ in 0..10 {
+        app.update();
+    }
+    let path = app
+        .world()
+        .resource::<NavGrid>()
+        .find_path(from, to)
+        .expect("the link should connect both sides");
+    let crossing = path
+        .windows(2)
+        .find(|pair| pair[0].z < 0.0 && pair[1].z > 0.0)
+        .expect("the path should cross the wall");
+    assert!(crossing[0].distance(link.from) < 1.0, "path {path:?}");
+    assert!(crossing[1].distance(link.to) < 1.0, "path {path:?}");
+
+    app.world_mut().despawn(link_entity);
+    for _ in 0..10 {
+        app.update();
+    }
+    assert!(
+        app.world()
+            .resource::<NavGrid>()
+            .find_path(from, to)
+            .is_none(),
+        "the removed link shouldn't connect anything anymore"
+    );
+}
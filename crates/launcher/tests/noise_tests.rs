@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use avian3d::prelude::*;
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use server::noise::{
+    HeardNoise, Hearing, NOISE_RANGE, NoiseEvent, NoiseListener, NoisePlugin, NoiseSource,
+    WALL_OCCLUSION,
+};
+use shared::scene::WallMarker;
+
+/// Noises are made at this height, in the middle of the walls.
+const EAR_HEIGHT: f32 = 1.0;
+
+/// Two walls across the +x axis, at x = 5 and x = 10. The other directions are open.
+fn level_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        AssetPlugin::default(),
+        bevy::scene::ScenePlugin,
+        bevy::render::mesh::MeshPlugin,
+        PhysicsPlugins::default(),
+        NoisePlugin,
+    ));
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+        20,
+    )));
+    app.finish();
+    app.cleanup();
+
+    for x in [5.0, 10.0] {
+        app.world_mut().spawn((
+            WallMarker,
+            Position(Vec3::new(x, 1.5, 0.0)),
+            RigidBody::Static,
+            Collider::cuboid(0.5, 3.0, 4.0),
+        ));
+    }
+    app
+}
+
+fn listener(app: &mut App, position: Vec3) -> Entity {
+    app.world_mut()
+        .spawn((Position(position), NoiseListener::default()))
+        .id()
+}
+
+/// Lets the physics pick up the walls, then asks who heard `noise`
+fn heard_by(app: &mut App, noise: NoiseEvent) -> Vec<HeardNoise> {
+    for _ in 0..5 {
+        app.update();
+    }
+    app.world_mut()
+        .run_system_once(move |hearing: Hearing| hearing.heard_by(&noise))
+        .unwrap()
+}
+
+fn loudness_of(heard: &[HeardNoise], listener: Entity) -> Option<f32> {
+    heard
+        .iter()
+        .find(|heard| heard.listener == listener)
+        .map(|heard| heard.loudness)
+}
+
+fn noise(app: &mut App, loudness: f32) -> NoiseEvent {
+    NoiseEvent {
+        position: Vec3::Y * EAR_HEIGHT,
+        loudness,
+        source: NoiseSource::Gunfire,
+        emitter: app.world_mut().spawn_empty().id(),
+    }
+}
+
+/// Test that loudness fades linearly with distance and is gone past the noise's range
+#[test]
+fn test_hearing_falloff() {
+    let mut app = level_app();
+    let loudness = 0.5;
+    let range = loudness * NOISE_RANGE;
+    let near = listener(&mut app, Vec3::new(0.0, EAR_HEIGHT, range * 0.25));
+    let far = listener(&mut app, Vec3::new(-range * 0.75, EAR_HEIGHT, 0.0));
+    let out_of_range = listener(&mut app, Vec3::new(0.0, EAR_HEIGHT, -range * 1.1));
+
+    let noise = noise(&mut app, loudness);
+    let heard = heard_by(&mut app, noise);
+
+    let near = loudness_of(&heard, near).expect("near listener should hear the noise");
+    let far = loudness_of(&heard, far).expect("far listener should hear the noise");
+    assert!((near - loudness * 0.75).abs() < 1e-4, "near heard {near}");
+    assert!((far - loudness * 0.25).abs() < 1e-4, "far heard {far}");
+    assert_eq!(loudness_of(&heard, out_of_range), None);
+}
+
+/// Test that every wall in the way keeps only WALL_OCCLUSION of the loudness
+#[test]
+fn test_hearing_wall_occlusion() {
+    let mut app = level_app();
+    // Same distances with and without the walls in between
+    let open_one = listener(&mut app, Vec3::new(0.0, EAR_HEIGHT, 7.5));
+    let one_wall = listener(&mut app, Vec3::new(7.5, EAR_HEIGHT, 0.0));
+    let open_two = listener(&mut app, Vec3::new(0.0, EAR_HEIGHT, -15.0));
+    let two_walls = listener(&mut app, Vec3::new(15.0, EAR_HEIGHT, 0.0));
+
+    let noise = noise(&mut app, 1.0);
+    let heard = heard_by(&mut app, noise);
+    let loudness = |listener| loudness_of(&heard, listener).expect("listener should hear");
+
+    let expected = loudness(open_one) * WALL_OCCLUSION;
+    assert!(
+        (loudness(one_wall) - expected).abs() < 1e-4,
+        "one wall: heard {}, expected {expected}",
+        loudness(one_wall)
+    );
+    let expected = loudness(open_two) * WALL_OCCLUSION * WALL_OCCLUSION;
+    assert!(
+        (loudness(two_walls) - expected).abs() < 1e-4,
+        "two walls: heard {}, expected {expected}",
+        loudness(two_walls)
+    );
+}
+
+/// Test that the entity making the noise doesn't hear itself, others standing there do
+#[test]
+fn test_hearing_excludes_emitter() {
+    let mut app = level_app();
+    let emitter = listener(&mut app, Vec3::Y * EAR_HEIGHT);
+    let other = listener(&mut app, Vec3::Y * EAR_HEIGHT);
+
+    let noise = NoiseEvent {
+        emitter,
+        ..noise(&mut app, 1.0)
+    };
+    let heard = heard_by(&mut app, noise);
+
+    assert_eq!(loudness_of(&heard, emitter), None);
+    assert_eq!(loudness_of(&heard, other), Some(1.0));
+}
@@ -0,0 +1,185 @@
+use std::path::Path;
+use std::time::Duration;
+
+use avian3d::prelude::*;
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use server::app::ServerConfig;
+use server::interaction::Interacted;
+use server::objective::{MissionDefinition, ObjectivePlugin, spawn_mission};
+use shared::game_state::{MatchPhase, MatchState};
+use shared::interaction::{Interactable, InteractableKind};
+use shared::objective::{Objective, ObjectiveKind, ObjectiveState, ObjectiveStatus};
+
+const MISSION: &str = r#"(
+    name: "Test",
+    objectives: [
+        (id: "hack", kind: Hack, description: "Hack", position: (0.0, 0.0, 0.0), radius: 2.0, amount: 1.0),
+        (id: "collect", kind: Collect, description: "Collect", position: (10.0, 0.0, 0.0), radius: 1.0, amount: 2.0, requires: ["hack"]),
+    ],
+)"#;
+
+/// The collect objective reuses the id of the hack objective, the requirement on it names the
+/// first one.
+const DUPLICATE_MISSION: &str = r#"(
+    name: "Duplicate",
+    objectives: [
+        (id: "hack", kind: Hack, description: "Hack", position: (0.0, 0.0, 0.0), radius: 2.0, amount: 1.0),
+        (id: "hack", kind: Collect, description: "Collect", position: (10.0, 0.0, 0.0), radius: 1.0, amount: 2.0),
+        (id: "defend", kind: Defend, description: "Defend", position: (5.0, 0.0, 0.0), radius: 3.0, amount: 10.0, requires: ["hack"]),
+    ],
+)"#;
+
+fn mission_app(text: &str) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        AssetPlugin::default(),
+        bevy::scene::ScenePlugin,
+        bevy::render::mesh::MeshPlugin,
+        PhysicsPlugins::default(),
+    ));
+    app.insert_resource(ServerConfig::default());
+    app.add_plugins(ObjectivePlugin);
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+        20,
+    )));
+    app.finish();
+    app.cleanup();
+
+    let mission = MissionDefinition::from_ron(text).unwrap();
+    app.world_mut()
+        .run_system_once(move |mut commands: Commands| spawn_mission(&mut commands, &mission))
+        .unwrap();
+    app.world_mut().spawn(MatchState {
+        phase: MatchPhase::InProgress,
+        round: 1,
+        seconds_left: 600,
+    });
+    app
+}
+
+fn use_all(app: &mut App, kind: InteractableKind) -> usize {
+    let targets: Vec<(Entity, bool)> = app
+        .world_mut()
+        .query::<(Entity, &Interactable)>()
+        .iter(app.world())
+        .filter(|(_, interactable)| interactable.kind == kind)
+        .map(|(entity, interactable)| (entity, interactable.locked))
+        .collect();
+    let player = app.world_mut().spawn_empty().id();
+    for &(target, _) in &targets {
+        app.world_mut().trigger(Interacted {
+            player,
+            target,
+            kind,
+        });
+        app.update();
+    }
+    targets.iter().filter(|(_, locked)| !locked).count()
+}
+
+fn statuses(app: &mut App) -> Vec<ObjectiveStatus> {
+    let mut objectives: Vec<(Objective, ObjectiveState)> = app
+        .world_mut()
+        .query::<(&Objective, &ObjectiveState)>()
+        .iter(app.world())
+        .map(|(objective, state)| (objective.clone(), *state))
+        .collect();
+    objectives.sort_by_key(|(objective, _)| objective.order);
+    objectives.iter().map(|(_, state)| state.status).collect()
+}
+
+/// Test that the shipped mission parses and starts with an objective without dependencies
+#[test]
+fn test_shipped_mission_loads() {
+    let text = std::fs::read_to_string(Path::new("../../assets/missions/data_recovery.ron"))
+        .expect("mission file should exist");
+    let mission = MissionDefinition::from_ron(&text).expect("mission should parse");
+    assert!(!mission.objectives.is_empty());
+    assert!(mission.objectives[0].requires.is_empty());
+    for objective in &mission.objectives {
+        for required in &objective.requires {
+            assert!(
+                mission.objectives.iter().any(|other| other.id == *required),
+                "{} requires unknown objective {required}",
+                objective.id
+            );
+        }
+    }
+}
+
+/// Test that objectives unlock in order and progress as their terminal and items are used
+#[test]
+fn test_objectives_follow_dependencies() {
+    let mut app = mission_app(MISSION);
+    // Items of the locked objective can't be picked up yet
+    assert_eq!(use_all(&mut app, InteractableKind::Pickup), 0);
+    app.update();
+    assert_eq!(
+        statuses(&mut app),
+        [ObjectiveStatus::Active, ObjectiveStatus::Locked]
+    );
+
+    assert_eq!(use_all(&mut app, InteractableKind::Terminal), 1);
+    app.update();
+    assert_eq!(
+        statuses(&mut app),
+        [ObjectiveStatus::Completed, ObjectiveStatus::Active]
+    );
+
+    assert_eq!(use_all(&mut app, InteractableKind::Pickup), 2);
+    app.update();
+    assert_eq!(
+        statuses(&mut app),
+        [ObjectiveStatus::Completed, ObjectiveStatus::Completed]
+    );
+}
+
+/// Test that an objective reusing an earlier id is skipped, so requirements on that id and the
+/// order of the objectives after it stay right
+#[test]
+fn test_duplicate_objective_ids_are_skipped() {
+    let mut app = mission_app(DUPLICATE_MISSION);
+    app.update();
+    let mut objectives: Vec<(u8, ObjectiveKind)> = app
+        .world_mut()
+        .query::<&Objective>()
+        .iter(app.world())
+        .map(|objective| (objective.order, objective.kind))
+        .collect();
+    objectives.sort_by_key(|(order, _)| *order);
+    assert_eq!(
+        objectives,
+        [(0, ObjectiveKind::Hack), (1, ObjectiveKind::Defend)]
+    );
+    assert_eq!(use_all(&mut app, InteractableKind::Pickup), 0);
+    app.update();
+    assert_eq!(
+        statuses(&mut app),
+        [ObjectiveStatus::Active, ObjectiveStatus::Locked]
+    );
+
+    assert_eq!(use_all(&mut app, InteractableKind::Terminal), 1);
+    app.update();
+    assert_eq!(
+        statuses(&mut app),
+        [ObjectiveStatus::Completed, ObjectiveStatus::Active]
+    );
+}
+
+/// Test that the plugin builds without a server config and plays without objectives then
+#[test]
+fn test_plugin_builds_without_config() {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, PhysicsPlugins::default()));
+    app.add_plugins(ObjectivePlugin);
+    assert!(
+        app.world()
+            .resource::<MissionDefinition>()
+            .objectives
+            .is_empty()
+    );
+}
@@ -0,0 +1,246 @@
+use std::any::TypeId;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use avian3d::prelude::{LinearVelocity, PhysicsSet, Position};
+use bevy::prelude::*;
+use lightyear::prelude::client::{ClientPlugins, NetcodeClient, NetcodeConfig};
+use lightyear::prelude::server::ClientOf;
+use lightyear::prelude::{
+    Authentication, ChannelRegistry, Client, Confirmed, Connect, InterpolationManager, Link,
+    LocalAddr, PeerAddr, PeerId, PredictionManager, RemoteId, ReplicationReceiver,
+    ReplicationSender, UdpIo,
+};
+use server::app::{ServerConfig, add_network_to_server_app};
+use server::priority::{OWN_PLAYER_PRIORITY, PROP_PRIORITY};
+use shared::protocol::{HitChannel, PlayerId};
+use shared::{FIXED_TIMESTEP_HZ, SERVER_ADDR, SHARED_SETTINGS, SharedPlugin};
+
+/// Client ids, and local ports from 4000 up, of the test clients.
+const FIRST_CLIENT_ID: u64 = 200;
+/// Where each player stands: the first one, whose client is checked, another right next to it
+/// and the last one across the room.
+const PLAYER_SPOTS: [Vec3; 3] = [
+    Vec3::new(-8.0, 1.0, -8.0),
+    Vec3::new(-6.0, 1.0, -8.0),
+    Vec3::new(8.0, 1.0, 8.0),
+];
+/// Low enough that the players' updates don't all fit in each send.
+const BANDWIDTH_CAP: u32 = 1_000;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const WARMUP: Duration = Duration::from_secs(5);
+const MEASURE: Duration = Duration::from_secs(5);
+
+/// Keeps every player on its spot, swaying a little so it always has a change to send. Runs after
+/// the physics step so the level geometry doesn't get in the way.
+fn sway_on_spots(
+    time: Res<Time>,
+    mut players: Query<(&PlayerId, &mut Position, &mut LinearVelocity)>,
+) {
+    let sway = Vec3::X * (time.elapsed_secs() * 3.0).sin() * 0.5;
+    for (player_id, mut position, mut velocity) in players.iter_mut() {
+        let player = (player_id.0.to_bits() - FIRST_CLIENT_ID) as usize;
+        position.0 = PLAYER_SPOTS[player] + sway;
+        *velocity = LinearVelocity::ZERO;
+    }
+}
+
+fn headless_plugins(app: &mut App) {
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        AssetPlugin::default(),
+        bevy::scene::ScenePlugin,
+        bevy::render::mesh::MeshPlugin,
+        SharedPlugin,
+    ));
+}
+
+fn server_app() -> App {
+    let mut app = App::new();
+    headless_plugins(&mut app);
+    app.insert_resource(ServerConfig {
+        bandwidth_cap: Some(BANDWIDTH_CAP),
+        ..default()
+    });
+    add_network_to_server_app(&mut app);
+    app.add_systems(FixedPostUpdate, sway_on_spots.after(PhysicsSet::Sync));
+    app.finish();
+    app.cleanup();
+    app
+}
+
+/// A client app with only the networking, connected like the game client does it.
+fn client_app(client_id: u64) -> App {
+    let mut app = App::new();
+    headless_plugins(&mut app);
+    app.add_plugins(ClientPlugins {
+        tick_duration: Duration::from_secs_f64(1.0 / FIXED_TIMESTEP_HZ),
+    });
+    app.finish();
+    app.cleanup();
+
+    let auth = Authentication::Manual {
+        server_addr: SERVER_ADDR,
+        client_id,
+        private_key: SHARED_SETTINGS.private_key,
+        protocol_id: SHARED_SETTINGS.protocol_id,
+    };
+    let netcode_client = NetcodeClient::new(auth, NetcodeConfig::default())
+        .expect("Failed to create netcode client");
+    let client = app
+        .world_mut()
+        .spawn((
+            Client::default(),
+            LocalAddr(SocketAddr::new(
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+                4000 + client_id as u16,
+            )),
+            PeerAddr(SERVER_ADDR),
+            Link::new(None),
+            ReplicationReceiver::default(),
+            PredictionManager::default(),
+            InterpolationManager::default(),
+            netcode_client,
+            UdpIo::default(),
+        ))
+        .id();
+    app.world_mut().trigger_targets(Connect, client);
+    app
+}
+
+fn update_all(server: &mut App, clients: &mut [App]) {
+    server.update();
+    for client in clients.iter_mut() {
+        client.update();
+    }
+    std::thread::sleep(Duration::from_millis(1));
+}
+
+/// Updates every app for `duration`, calling `sample` after each round.
+fn run_for(
+    duration: Duration,
+    server: &mut App,
+    clients: &mut [App],
+    mut sample: impl FnMut(&mut App, &mut [App]),
+) {
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        update_all(server, clients);
+        sample(server, clients);
+    }
+}
+
+/// Player entities by client id.
+fn players<F: bevy::ecs::query::QueryFilter>(app: &mut App) -> Vec<(u64, Entity, Vec3)> {
+    let mut players: Vec<_> = app
+        .world_mut()
+        .query_filtered::<(Entity, &PlayerId, &Position), F>()
+        .iter(app.world())
+        .map(|(entity, player_id, position)| (player_id.0.to_bits(), entity, position.0))
+        .collect();
+    players.sort_by_key(|(id, _, _)| *id);
+    players
+}
+
+/// Replication priority of every group the server sends to the first client, by group entity.
+fn first_client_priorities(server: &mut App) -> Vec<(Entity, f32)> {
+    let sender = server
+        .world_mut()
+        .query_filtered::<(&RemoteId, &ReplicationSender), With<ClientOf>>()
+        .iter(server.world())
+        .find(|(remote_id, _)| remote_id.0 == PeerId::Netcode(FIRST_CLIENT_ID))
+        .map(|(_, sender)| sender)
+        .expect("The first client should be connected");
+    sender
+        .group_channels
+        .iter()
+        .filter_map(|(group_id, channel)| {
+            Some((
+                Entity::try_from_bits(group_id.0).ok()?,
+                channel.base_priority,
+            ))
+        })
+        .collect()
+}
+
+/// With the bandwidth capped, a client's own hits come first, then its own player, the players
+/// close to it, the players further away and everything else. The players it receives more often
+/// are the ones that come first, and the last ones still get through.
+#[test]
+fn test_priorities_under_bandwidth_cap() {
+    let mut server = server_app();
+    let hit_priority = server
+        .world()
+        .resource::<ChannelRegistry>()
+        .settings(TypeId::of::<HitChannel>().into())
+        .expect("The hit channel should be registered")
+        .priority;
+    assert!(
+        hit_priority > OWN_PLAYER_PRIORITY,
+        "The client's own hits should go before its own player"
+    );
+
+    let mut clients: Vec<App> = (0..PLAYER_SPOTS.len() as u64)
+        .map(|index| client_app(FIRST_CLIENT_ID + index))
+        .collect();
+
+    let start = Instant::now();
+    while players::<()>(&mut server).len() < PLAYER_SPOTS.len() {
+        assert!(
+            start.elapsed() < CONNECT_TIMEOUT,
+            "Only {} of {} players spawned",
+            players::<()>(&mut server).len(),
+            PLAYER_SPOTS.len()
+        );
+        update_all(&mut server, &mut clients);
+    }
+    run_for(WARMUP, &mut server, &mut clients, |_, _| {});
+
+    let server_players = players::<()>(&mut server);
+    let priorities = first_client_priorities(&mut server);
+    let priority_of = |entity: Entity| {
+        priorities
+            .iter()
+            .find(|(group, _)| *group == entity)
+            .map(|(_, priority)| *priority)
+            .expect("Every player should be replicated to the first client")
+    };
+    let [own, near, far] = [0, 1, 2].map(|index| priority_of(server_players[index].1));
+    assert_eq!(own, OWN_PLAYER_PRIORITY);
+    assert!(
+        own > near && near > far,
+        "Own player {own}, near player {near} and far player {far} should come in that order"
+    );
+    let players_entities: Vec<Entity> = server_players.iter().map(|(_, e, _)| *e).collect();
+    for (entity, priority) in &priorities {
+        if !players_entities.contains(entity) {
+            assert!(
+                *priority <= PROP_PRIORITY && *priority < far,
+                "{entity:?} has priority {priority}, above the far player's {far}"
+            );
+        }
+    }
+
+    // Count the updates the first client gets for each player
+    let mut last_positions: Vec<Option<Vec3>> = vec![None; PLAYER_SPOTS.len()];
+    let mut updates = vec![0u32; PLAYER_SPOTS.len()];
+    run_for(MEASURE, &mut server, &mut clients, |_, clients| {
+        for (id, _, position) in players::<With<Confirmed>>(&mut clients[0]) {
+            let player = (id - FIRST_CLIENT_ID) as usize;
+            if last_positions[player].is_some_and(|last| last != position) {
+                updates[player] += 1;
+            }
+            last_positions[player] = Some(position);
+        }
+    });
+    println!("🚦 Updates received for the own, near and far players: {updates:?}");
+    assert!(
+        updates[0] > updates[1] && updates[1] > updates[2],
+        "Players should be updated more often the higher their priority, got {updates:?}"
+    );
+    assert!(
+        updates[2] > 0,
+        "The far player should be updated less often, not never"
+    );
+}
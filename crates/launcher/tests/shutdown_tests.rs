@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use server::app::ServerConfig;
+use server::shutdown::{
+    EXIT_CODE_FORCED, RequestShutdown, ShutdownPhase, ShutdownPlugin, ShutdownSignal, ShutdownState,
+};
+
+const COUNTDOWN: Duration = Duration::from_secs(1);
+const STEP: Duration = Duration::from_millis(50);
+
+fn shutdown_app() -> (App, ShutdownSignal) {
+    let signal = ShutdownSignal::default();
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins);
+    app.insert_resource(ServerConfig {
+        shutdown_countdown: COUNTDOWN,
+        ..default()
+    });
+    app.insert_resource(signal.clone());
+    app.add_plugins(ShutdownPlugin);
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(STEP));
+    app.finish();
+    app.cleanup();
+    app.update();
+    (app, signal)
+}
+
+fn phase(app: &App) -> Option<ShutdownPhase> {
+    app.world()
+        .get_resource::<ShutdownState>()
+        .map(|state| state.phase)
+}
+
+/// Updates the app until it exits or `duration` went by, returns the phases it went through
+fn run_for(app: &mut App, duration: Duration) -> Vec<ShutdownPhase> {
+    let mut phases: Vec<ShutdownPhase> = Vec::new();
+    let steps = duration.as_millis() / STEP.as_millis();
+    for _ in 0..steps {
+        app.update();
+        if let Some(phase) = phase(app).filter(|phase| phases.last() != Some(phase)) {
+            phases.push(phase);
+        }
+        if app.should_exit().is_some() {
+            break;
+        }
+    }
+    phases
+}
+
+/// A signal counts the clients down, flushes, disconnects them and exits cleanly
+#[test]
+fn test_signal_shuts_down_gracefully() {
+    let (mut app, signal) = shutdown_app();
+    assert_eq!(phase(&app), None);
+
+    signal.raise();
+    app.update();
+    assert_eq!(phase(&app), Some(ShutdownPhase::Countdown));
+
+    // Nothing happens before the countdown runs out
+    run_for(&mut app, COUNTDOWN / 2);
+    assert_eq!(phase(&app), Some(ShutdownPhase::Countdown));
+    assert_eq!(app.should_exit(), None);
+
+    let phases = run_for(&mut app, COUNTDOWN * 2);
+    assert_eq!(
+        phases,
+        [
+            ShutdownPhase::Countdown,
+            ShutdownPhase::Flushing,
+            ShutdownPhase::Disconnecting
+        ]
+    );
+    assert_eq!(app.should_exit(), Some(AppExit::Success));
+}
+
+/// A signal received during the shutdown exits right away with the forced exit code
+#[test]
+fn test_second_signal_forces_exit() {
+    let (mut app, signal) = shutdown_app();
+    signal.raise();
+    app.update();
+    assert_eq!(phase(&app), Some(ShutdownPhase::Countdown));
+
+    signal.raise();
+    app.update();
+    assert_eq!(
+        app.should_exit(),
+        Some(AppExit::from_code(EXIT_CODE_FORCED))
+    );
+}
+
+/// Signals arriving together before the server noticed them start a single graceful shutdown
+#[test]
+fn test_simultaneous_signals_keep_the_countdown() {
+    let (mut app, signal) = shutdown_app();
+    signal.raise();
+    signal.raise();
+    app.update();
+    assert_eq!(phase(&app), Some(ShutdownPhase::Countdown));
+
+    run_for(&mut app, COUNTDOWN / 2);
+    assert_eq!(app.should_exit(), None);
+    assert_eq!(phase(&app), Some(ShutdownPhase::Countdown));
+
+    run_for(&mut app, COUNTDOWN * 2);
+    assert_eq!(app.should_exit(), Some(AppExit::Success));
+}
+
+/// A shutdown asked by an admin counts down like a signal
+#[test]
+fn test_requested_shutdown() {
+    let (mut app, _) = shutdown_app();
+    app.world_mut().trigger(RequestShutdown {
+        reason: "Maintenance".to_string(),
+        countdown: COUNTDOWN,
+    });
+    app.update();
+    assert_eq!(phase(&app), Some(ShutdownPhase::Countdown));
+
+    run_for(&mut app, COUNTDOWN * 2);
+    assert_eq!(app.should_exit(), Some(AppExit::Success));
+}
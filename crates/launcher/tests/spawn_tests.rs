@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use avian3d::prelude::*;
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use server::spawn::{SpawnPicker, SpawnPlugin};
+use shared::enemy::{Enemy, EnemyState};
+use shared::protocol::Team;
+use shared::scene::{FloorMarker, SpawnPoint, WallMarker};
+
+/// A floor with two points for team 0, one for team 1 and a neutral one.
+fn level_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        AssetPlugin::default(),
+        bevy::scene::ScenePlugin,
+        bevy::render::mesh::MeshPlugin,
+        PhysicsPlugins::default(),
+        SpawnPlugin,
+    ));
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+        20,
+    )));
+    app.finish();
+    app.cleanup();
+
+    app.world_mut().spawn((
+        FloorMarker,
+        Position(Vec3::new(0.0, -0.5, 0.0)),
+        RigidBody::Static,
+        Collider::cuboid(20.0, 1.0, 20.0),
+    ));
+    for (team, x) in [
+        (Some(0), -6.0),
+        (Some(0), -3.0),
+        (Some(1), 6.0),
+        (None, 0.0),
+    ] {
+        app.world_mut()
+            .spawn((SpawnPoint { team }, Position(Vec3::new(x, 0.0, 0.0))));
+    }
+    update(&mut app);
+    app
+}
+
+fn update(app: &mut App) {
+    for _ in 0..5 {
+        app.update();
+    }
+}
+
+fn pick(app: &mut App, team: u8) -> Vec3 {
+    app.world_mut()
+        .run_system_once(move |mut picker: SpawnPicker| picker.pick(Team(team), None))
+        .unwrap()
+}
+
+/// Test that players get their team's free points first, then neutral ones
+#[test]
+fn test_spawn_prefers_free_team_points() {
+    let mut app = level_app();
+    // A crate sits on the first team 0 point
+    app.world_mut().spawn((
+        Position(Vec3::new(-6.0, 0.5, 0.0)),
+        RigidBody::Static,
+        Collider::cuboid(1.0, 1.0, 1.0),
+    ));
+    update(&mut app);
+
+    assert_eq!(pick(&mut app, 0).x, -3.0);
+    // The free team point was just used, the neutral one is next
+    assert_eq!(pick(&mut app, 0).x, 0.0);
+    assert_eq!(pick(&mut app, 1).x, 6.0);
+}
+
+/// Test that points in sight of enemies are skipped, unless a wall hides them
+#[test]
+fn test_spawn_avoids_enemy_sight() {
+    // A sleeping enemy only sees both team points, right next to it
+    let mut app = level_app();
+    app.world_mut().spawn((
+        Enemy,
+        EnemyState::Sleeping,
+        Position(Vec3::new(-4.5, 1.0, 0.0)),
+    ));
+    update(&mut app);
+    assert_eq!(pick(&mut app, 0).x, 0.0);
+
+    // An awake enemy behind a wall sees nothing, the point farthest from it wins
+    let mut app = level_app();
+    app.world_mut().spawn((
+        Enemy,
+        EnemyState::Chasing,
+        Position(Vec3::new(-8.0, 1.0, 0.0)),
+    ));
+    app.world_mut().spawn((
+        WallMarker,
+        Position(Vec3::new(-7.0, 1.5, 0.0)),
+        RigidBody::Static,
+        Collider::cuboid(0.5, 3.0, 10.0),
+    ));
+    update(&mut app);
+    assert_eq!(pick(&mut app, 0).x, -3.0);
+}
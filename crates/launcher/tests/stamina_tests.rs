@@ -0,0 +1,154 @@
+use avian3d::prelude::*;
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+use shared::input::{
+    JUMP_STAMINA_COST, PlayerAction, SPRINT_STAMINA_PER_SEC, STAMINA_MAX, STAMINA_RECOVERED,
+    STAMINA_REGEN_PER_SEC, Stamina, Stance, shared_player_movement, update_player_stance,
+    update_stamina,
+};
+
+const TICK_SECS: f32 = 1.0 / 64.0;
+
+fn stamina(current: f32, exhausted: bool) -> Stamina {
+    Stamina { current, exhausted }
+}
+
+/// Runs the stamina of a player in `stance` for `secs`, a tick at a time.
+fn run(mut stamina: Stamina, stance: Stance, secs: f32) -> Stamina {
+    for _ in 0..(secs / TICK_SECS).round() as usize {
+        stamina = update_stamina(stamina, stance, TICK_SECS);
+    }
+    stamina
+}
+
+fn actions(pressed: &[PlayerAction], movement: Vec2) -> ActionState<PlayerAction> {
+    let mut action_state = ActionState::<PlayerAction>::default();
+    for action in pressed {
+        action_state.press(action);
+    }
+    action_state.set_axis_pair(&PlayerAction::Move, movement);
+    action_state
+}
+
+/// Test that sprinting drains stamina and anything else regenerates it, within its bounds
+#[test]
+fn test_sprint_drains_and_rest_regenerates() {
+    let sprinted = update_stamina(Stamina::default(), Stance::Sprinting, 1.0);
+    assert_eq!(
+        sprinted,
+        stamina(STAMINA_MAX - SPRINT_STAMINA_PER_SEC, false)
+    );
+
+    for stance in [Stance::Standing, Stance::Crouching] {
+        let rested = update_stamina(stamina(10.0, false), stance, 1.0);
+        assert_eq!(rested, stamina(10.0 + STAMINA_REGEN_PER_SEC, false));
+    }
+    assert_eq!(
+        update_stamina(stamina(STAMINA_MAX - 1.0, false), Stance::Standing, 1.0),
+        Stamina::default()
+    );
+}
+
+/// Test that a jump costs stamina, and that it can't be made without enough of it
+#[test]
+fn test_jump_costs_stamina() {
+    let jump = |before: Stamina| {
+        let mut world = World::new();
+        let player = world
+            .spawn((before, Rotation::default(), LinearVelocity::ZERO))
+            .id();
+        world
+            .run_system_once(
+                move |mut query: Query<(&mut Stamina, &mut Rotation, &mut LinearVelocity)>| {
+                    let (stamina, mut rotation, mut velocity) = query.single_mut().unwrap();
+                    shared_player_movement(
+                        &actions(&[PlayerAction::Jump], Vec2::ZERO),
+                        Stance::Standing,
+                        true,
+                        stamina,
+                        &mut rotation,
+                        &mut velocity,
+                        0.0,
+                    );
+                },
+            )
+            .unwrap();
+        let jumped = world.get::<LinearVelocity>(player).unwrap().y > 0.0;
+        (jumped, *world.get::<Stamina>(player).unwrap())
+    };
+
+    assert_eq!(
+        jump(Stamina::default()),
+        (true, stamina(STAMINA_MAX - JUMP_STAMINA_COST, false))
+    );
+    let short = stamina(JUMP_STAMINA_COST - 1.0, false);
+    assert_eq!(jump(short), (false, short));
+    // Enough for a jump, but not caught up yet
+    let exhausted = stamina(STAMINA_RECOVERED - 1.0, true);
+    assert!(exhausted.current >= JUMP_STAMINA_COST);
+    assert_eq!(jump(exhausted), (false, exhausted));
+}
+
+/// Test that sprinting until empty exhausts the player, and that it stays exhausted until it has
+/// regenerated up to `STAMINA_RECOVERED`
+#[test]
+fn test_exhaustion_until_recovered() {
+    let emptied = run(
+        Stamina::default(),
+        Stance::Sprinting,
+        STAMINA_MAX / SPRINT_STAMINA_PER_SEC,
+    );
+    assert_eq!(emptied, stamina(0.0, true));
+    // Still sprinting at zero doesn't go below it
+    assert_eq!(
+        update_stamina(emptied, Stance::Sprinting, 1.0),
+        stamina(0.0, true)
+    );
+
+    let almost = run(
+        emptied,
+        Stance::Standing,
+        STAMINA_RECOVERED / STAMINA_REGEN_PER_SEC - 0.1,
+    );
+    assert!(almost.current < STAMINA_RECOVERED);
+    assert!(almost.exhausted, "Recovered early at {}", almost.current);
+
+    let recovered = run(almost, Stance::Standing, 0.2);
+    assert!(recovered.current >= STAMINA_RECOVERED);
+    assert!(
+        !recovered.exhausted,
+        "Still exhausted at {}",
+        recovered.current
+    );
+}
+
+/// Test that an exhausted player pressing sprint walks instead
+#[test]
+fn test_exhaustion_forces_walk() {
+    let stance_with = |stamina: Stamina| {
+        let mut world = World::new();
+        world.init_resource::<SpatialQueryPipeline>();
+        world.spawn((Stance::Standing, Position::default()));
+        world
+            .run_system_once(
+                move |mut query: Query<(Entity, &mut Stance, &mut Position)>,
+                      spatial_query: Res<SpatialQueryPipeline>| {
+                    let (entity, stance, position) = query.single_mut().unwrap();
+                    update_player_stance(
+                        &actions(&[PlayerAction::Sprint], Vec2::Y),
+                        entity,
+                        stance,
+                        position,
+                        &stamina,
+                        &spatial_query,
+                    )
+                },
+            )
+            .unwrap()
+    };
+
+    assert_eq!(stance_with(Stamina::default()), Stance::Sprinting);
+    assert_eq!(stance_with(stamina(STAMINA_MAX, true)), Stance::Standing);
+    assert_eq!(stance_with(stamina(0.0, true)), Stance::Standing);
+}
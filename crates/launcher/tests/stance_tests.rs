@@ -0,0 +1,244 @@
+use std::time::Duration;
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use client::app::LocalPlayerId;
+use client::input::ClientInputPlugin;
+use leafwing_input_manager::prelude::ActionState;
+use lightyear::prelude::{Controlled, PeerId, Predicted};
+use server::gameplay::server_player_movement;
+use shared::FIXED_TIMESTEP_HZ;
+use shared::input::{
+    CROUCH_CAPSULE_HEIGHT, PLAYER_CAPSULE_HEIGHT, PLAYER_CAPSULE_RADIUS, PlayerAction, Stamina,
+    Stance, apply_stance_collider,
+};
+use shared::protocol::PlayerId;
+
+/// Capsule center of a player standing on the ground at y = 0.
+const STANDING_Y: f32 = PLAYER_CAPSULE_HEIGHT / 2.0 + PLAYER_CAPSULE_RADIUS;
+const CROUCHING_Y: f32 = CROUCH_CAPSULE_HEIGHT / 2.0 + PLAYER_CAPSULE_RADIUS;
+/// A slab above the crouched player's head but below the standing one's, ahead of the player.
+const CEILING_CENTER: Vec3 = Vec3::new(0.0, 2.0, -6.0);
+const CEILING_SIZE: Vec3 = Vec3::new(4.0, 0.4, 4.0);
+const TIMEOUT_TICKS: usize = 1000;
+
+fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        AssetPlugin::default(),
+        bevy::scene::ScenePlugin,
+        bevy::render::mesh::MeshPlugin,
+        PhysicsPlugins::default(),
+    ));
+    app.insert_resource(Time::<Fixed>::from_hz(FIXED_TIMESTEP_HZ));
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        1.0 / FIXED_TIMESTEP_HZ,
+    )));
+    app
+}
+
+/// The player is kinematic, so it only goes up and down with its stance and walks straight
+/// through the ceiling's edge rather than bumping into it.
+fn spawn_scene(app: &mut App) -> Entity {
+    app.world_mut().spawn((
+        Name::new("Ceiling"),
+        RigidBody::Static,
+        Collider::cuboid(CEILING_SIZE.x, CEILING_SIZE.y, CEILING_SIZE.z),
+        Position(CEILING_CENTER),
+    ));
+    let player = app
+        .world_mut()
+        .spawn((
+            Name::new("Player"),
+            PlayerId(PeerId::Netcode(1)),
+            Stance::default(),
+            Stamina::default(),
+            RigidBody::Kinematic,
+            Stance::default().collider(),
+            Position(Vec3::new(0.0, STANDING_Y, 0.0)),
+            Rotation::default(),
+            LinearVelocity::ZERO,
+            ActionState::<PlayerAction>::default(),
+        ))
+        .id();
+    app.finish();
+    app.cleanup();
+    app.update();
+    player
+}
+
+fn server_app() -> (App, Entity) {
+    let mut app = headless_app();
+    app.add_systems(
+        FixedUpdate,
+        (server_player_movement, apply_stance_collider).chain(),
+    );
+    let player = spawn_scene(&mut app);
+    (app, player)
+}
+
+/// Predicts a player of another client, so the client doesn't swap in its own physics body.
+fn client_app() -> (App, Entity) {
+    let mut app = headless_app();
+    app.init_asset::<StandardMaterial>();
+    app.insert_resource(LocalPlayerId(2));
+    app.add_plugins(ClientInputPlugin);
+    let player = spawn_scene(&mut app);
+    app.world_mut().entity_mut(player).insert((
+        Predicted {
+            confirmed_entity: None,
+        },
+        Controlled,
+    ));
+    (app, player)
+}
+
+/// What happened to the player on one tick.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Step {
+    stance: Stance,
+    position: Vec3,
+}
+
+struct Run {
+    app: App,
+    player: Entity,
+    steps: Vec<Step>,
+}
+
+impl Run {
+    fn new((app, player): (App, Entity)) -> Self {
+        Self {
+            app,
+            player,
+            steps: Vec::new(),
+        }
+    }
+
+    fn input(&mut self, crouch: bool, forward: bool) {
+        let mut action_state = self
+            .app
+            .world_mut()
+            .get_mut::<ActionState<PlayerAction>>(self.player)
+            .unwrap();
+        if crouch {
+            action_state.press(&PlayerAction::Crouch);
+        } else {
+            action_state.release(&PlayerAction::Crouch);
+        }
+        let axis = if forward { Vec2::Y } else { Vec2::ZERO };
+        action_state.set_axis_pair(&PlayerAction::Move, axis);
+    }
+
+    fn tick(&mut self) -> Step {
+        self.app.update();
+        let world = self.app.world();
+        let step = Step {
+            stance: *world.get::<Stance>(self.player).unwrap(),
+            position: world.get::<Position>(self.player).unwrap().0,
+        };
+        self.steps.push(step);
+        step
+    }
+
+    /// Ticks with the given inputs until `done`, returning the step it stopped at.
+    fn until(&mut self, crouch: bool, forward: bool, done: impl Fn(Step) -> bool) -> Step {
+        self.input(crouch, forward);
+        for _ in 0..TIMEOUT_TICKS {
+            let step = self.tick();
+            if done(step) {
+                return step;
+            }
+        }
+        panic!("Timed out at {:?}", self.steps.last());
+    }
+}
+
+fn under_ceiling(position: Vec3) -> bool {
+    (position.z - CEILING_CENTER.z).abs() < CEILING_SIZE.z / 2.0
+}
+
+/// Crouches, walks under the ceiling, lets go of crouch there, then walks out the other side.
+fn crouch_under_ceiling(run: &mut Run) {
+    let crouched = run.until(true, false, |step| step.stance == Stance::Crouching);
+    assert!(
+        (crouched.position.y - CROUCHING_Y).abs() < 1e-4,
+        "Crouching should lower the capsule center to keep the feet down, at {}",
+        crouched.position.y
+    );
+
+    let under = run.until(true, true, |step| step.position.z <= CEILING_CENTER.z);
+    assert_eq!(under.stance, Stance::Crouching);
+
+    // Letting go of crouch under the ceiling keeps the player down
+    run.input(false, false);
+    for _ in 0..FIXED_TIMESTEP_HZ as usize {
+        let step = run.tick();
+        assert_eq!(
+            step.stance,
+            Stance::Crouching,
+            "The player shouldn't stand up under the ceiling"
+        );
+        assert!((step.position.y - CROUCHING_Y).abs() < 1e-4);
+    }
+
+    // Walking on without crouch, the player stands up once its head is clear of the ceiling
+    let stood = run.until(false, true, |step| step.stance != Stance::Crouching);
+    assert_eq!(stood.stance, Stance::Standing);
+    assert!(
+        !under_ceiling(stood.position),
+        "Stood up under the ceiling at {}",
+        stood.position
+    );
+    assert!(
+        (stood.position.y - STANDING_Y).abs() < 1e-4,
+        "Standing up should raise the capsule center back, at {}",
+        stood.position.y
+    );
+    let collider = run.app.world().get::<Collider>(run.player).unwrap();
+    assert_eq!(
+        collider
+            .shape()
+            .as_capsule()
+            .map(|capsule| capsule.half_height()),
+        Stance::Standing
+            .collider()
+            .shape()
+            .as_capsule()
+            .map(|capsule| capsule.half_height()),
+        "The collider should follow the stance"
+    );
+}
+
+/// Test that a crouched player stays down until nothing is above its head, with its capsule
+/// center lowered and raised back around it
+#[test]
+fn test_crouched_player_stays_down_under_ceiling() {
+    let mut run = Run::new(server_app());
+    crouch_under_ceiling(&mut run);
+}
+
+/// Test that the client predicts the same stances and positions as the server runs, tick by tick
+#[test]
+fn test_client_predicts_server_stance() {
+    let mut server = Run::new(server_app());
+    let mut client = Run::new(client_app());
+    crouch_under_ceiling(&mut server);
+    crouch_under_ceiling(&mut client);
+    assert_eq!(server.steps.len(), client.steps.len());
+    for (tick, (server, client)) in server.steps.iter().zip(&client.steps).enumerate() {
+        assert_eq!(
+            server.stance, client.stance,
+            "Stances differ at tick {tick}"
+        );
+        assert!(
+            server.position.distance(client.position) < 1e-4,
+            "Positions differ at tick {tick}: {} and {}",
+            server.position,
+            client.position
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use avian3d::prelude::{LinearVelocity, PhysicsSet, Position};
+use bevy::prelude::*;
+use lightyear::prelude::client::{ClientPlugins, NetcodeClient, NetcodeConfig};
+use lightyear::prelude::{
+    Authentication, Client, Connect, InterpolationManager, Link, LocalAddr, MessageReceiver,
+    MessageSender, PeerAddr, PeerId, PredictionManager, ReplicationReceiver, UdpIo,
+};
+use server::app::{ServerConfig, add_network_to_server_app};
+use server::noise::{NoiseEvent, NoiseSource};
+use shared::protocol::PlayerId;
+use shared::voice::{VoiceChannel, VoiceFrame, VoiceLevel, VoicePacket};
+use shared::{FIXED_TIMESTEP_HZ, SERVER_ADDR, SHARED_SETTINGS, SharedPlugin};
+
+/// Client ids, and local ports from 4000 up, of the test clients.
+const FIRST_CLIENT_ID: u64 = 300;
+/// Where each player stands: the speaker, a listener within whispering range of it and one only
+/// in range of its normal voice.
+const PLAYER_SPOTS: [Vec3; 3] = [
+    Vec3::new(-8.0, 1.0, -8.0),
+    Vec3::new(-5.0, 1.0, -8.0),
+    Vec3::new(4.0, 1.0, -8.0),
+];
+const SPEAKER: usize = 0;
+const NEAR: usize = 1;
+const FAR: usize = 2;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Time for the players to reach their spots on the server.
+const WARMUP: Duration = Duration::from_secs(1);
+/// Time for a frame to be relayed, lost frames are not sent again.
+const RELAY: Duration = Duration::from_secs(1);
+
+/// Noises made on the server.
+#[derive(Resource, Default)]
+struct Noises(Vec<NoiseEvent>);
+
+fn record_noises(mut events: EventReader<NoiseEvent>, mut noises: ResMut<Noises>) {
+    noises.0.extend(events.read().copied());
+}
+
+/// Voice packets a client received. Unread messages are dropped at the end of each update.
+#[derive(Resource, Default)]
+struct Heard(Vec<VoicePacket>);
+
+fn record_voice(
+    mut receivers: Query<&mut MessageReceiver<VoicePacket>, With<Client>>,
+    mut heard: ResMut<Heard>,
+) {
+    for mut receiver in receivers.iter_mut() {
+        heard.0.extend(receiver.receive());
+    }
+}
+
+/// Keeps every player on its spot. Runs after the physics step so the level geometry doesn't get
+/// in the way.
+fn hold_spots(mut players: Query<(&PlayerId, &mut Position, &mut LinearVelocity)>) {
+    for (player_id, mut position, mut velocity) in players.iter_mut() {
+        let player = (player_id.0.to_bits() - FIRST_CLIENT_ID) as usize;
+        position.0 = PLAYER_SPOTS[player];
+        *velocity = LinearVelocity::ZERO;
+    }
+}
+
+fn headless_plugins(app: &mut App) {
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        AssetPlugin::default(),
+        bevy::scene::ScenePlugin,
+        bevy::render::mesh::MeshPlugin,
+        SharedPlugin,
+    ));
+}
+
+fn server_app() -> App {
+    let mut app = App::new();
+    headless_plugins(&mut app);
+    app.insert_resource(ServerConfig::default());
+    add_network_to_server_app(&mut app);
+    app.init_resource::<Noises>();
+    app.add_systems(Update, record_noises);
+    app.add_systems(FixedPostUpdate, hold_spots.after(PhysicsSet::Sync));
+    app.finish();
+    app.cleanup();
+    app
+}
+
+/// A client app with only the networking, connected like the game client does it.
+fn client_app(client_id: u64) -> App {
+    let mut app = App::new();
+    headless_plugins(&mut app);
+    app.add_plugins(ClientPlugins {
+        tick_duration: Duration::from_secs_f64(1.0 / FIXED_TIMESTEP_HZ),
+    });
+    app.init_resource::<Heard>();
+    app.add_systems(Update, record_voice);
+    app.finish();
+    app.cleanup();
+
+    let auth = Authentication::Manual {
+        server_addr: SERVER_ADDR,
+        client_id,
+        private_key: SHARED_SETTINGS.private_key,
+        protocol_id: SHARED_SETTINGS.protocol_id,
+    };
+    let netcode_client = NetcodeClient::new(auth, NetcodeConfig::default())
+        .expect("Failed to create netcode client");
+    let client = app
+        .world_mut()
+        .spawn((
+            Client::default(),
+            LocalAddr(SocketAddr::new(
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+                4000 + client_id as u16,
+            )),
+            PeerAddr(SERVER_ADDR),
+            Link::new(None),
+            ReplicationReceiver::default(),
+            PredictionManager::default(),
+            InterpolationManager::default(),
+            netcode_client,
+            UdpIo::default(),
+        ))
+        .id();
+    app.world_mut().trigger_targets(Connect, client);
+    app
+}
+
+fn update_all(server: &mut App, clients: &mut [App]) {
+    server.update();
+    for client in clients.iter_mut() {
+        client.update();
+    }
+    std::thread::sleep(Duration::from_millis(1));
+}
+
+fn player_count(server: &mut App) -> usize {
+    server
+        .world_mut()
+        .query::<&PlayerId>()
+        .iter(server.world())
+        .count()
+}
+
+fn send_frame(client: &mut App, level: VoiceLevel) {
+    client
+        .world_mut()
+        .query_filtered::<&mut MessageSender<VoiceFrame>, With<Client>>()
+        .single_mut(client.world_mut())
+        .expect("Client should send voice frames")
+        .send::<VoiceChannel>(VoiceFrame {
+            level,
+            data: vec![0xAB; 40],
+        });
+}
+
+/// Relays one frame of the speaker at `level`, returning the voice packets every client received.
+fn relay(server: &mut App, clients: &mut [App], level: VoiceLevel) -> Vec<Vec<VoicePacket>> {
+    send_frame(&mut clients[SPEAKER], level);
+    let start = Instant::now();
+    while start.elapsed() < RELAY {
+        update_all(server, clients);
+    }
+    clients
+        .iter_mut()
+        .map(|client| std::mem::take(&mut client.world_mut().resource_mut::<Heard>().0))
+        .collect()
+}
+
+/// The server relays a voice frame to the players within range of its level, never back to the
+/// speaker, and lets enemies hear it as a voice noise
+#[test]
+fn test_voice_reaches_players_in_range() {
+    assert!(PLAYER_SPOTS[SPEAKER].distance(PLAYER_SPOTS[NEAR]) < VoiceLevel::Whisper.range());
+    assert!(PLAYER_SPOTS[SPEAKER].distance(PLAYER_SPOTS[FAR]) > VoiceLevel::Whisper.range());
+    assert!(PLAYER_SPOTS[SPEAKER].distance(PLAYER_SPOTS[FAR]) < VoiceLevel::Normal.range());
+
+    let mut server = server_app();
+    let mut clients: Vec<App> = (0..PLAYER_SPOTS.len() as u64)
+        .map(|index| client_app(FIRST_CLIENT_ID + index))
+        .collect();
+    let start = Instant::now();
+    while player_count(&mut server) < PLAYER_SPOTS.len() {
+        assert!(
+            start.elapsed() < CONNECT_TIMEOUT,
+            "Only {} of {} players spawned",
+            player_count(&mut server),
+            PLAYER_SPOTS.len()
+        );
+        update_all(&mut server, &mut clients);
+    }
+    let start = Instant::now();
+    while start.elapsed() < WARMUP {
+        update_all(&mut server, &mut clients);
+    }
+    server.world_mut().resource_mut::<Noises>().0.clear();
+    for client in clients.iter_mut() {
+        client.world_mut().resource_mut::<Heard>().0.clear();
+    }
+
+    let speaker = PeerId::Netcode(FIRST_CLIENT_ID + SPEAKER as u64);
+    let whispered = relay(&mut server, &mut clients, VoiceLevel::Whisper);
+    assert!(
+        whispered[SPEAKER].is_empty(),
+        "The speaker shouldn't hear itself"
+    );
+    assert_eq!(
+        whispered[NEAR].len(),
+        1,
+        "The near player should hear the whisper"
+    );
+    assert!(
+        whispered[FAR].is_empty(),
+        "The far player shouldn't hear the whisper"
+    );
+    let packet = &whispered[NEAR][0];
+    assert_eq!(packet.speaker, speaker);
+    assert_eq!(packet.level, VoiceLevel::Whisper);
+    assert_eq!(packet.data, vec![0xAB; 40]);
+
+    let spoken = relay(&mut server, &mut clients, VoiceLevel::Normal);
+    assert!(
+        spoken[SPEAKER].is_empty(),
+        "The speaker shouldn't hear itself"
+    );
+    assert_eq!(
+        spoken[NEAR].len(),
+        1,
+        "The near player should hear the voice"
+    );
+    assert_eq!(spoken[FAR].len(), 1, "The far player should hear the voice");
+
+    let noises = &server.world().resource::<Noises>().0;
+    let voices: Vec<&NoiseEvent> = noises
+        .iter()
+        .filter(|noise| noise.source == NoiseSource::Voice)
+        .collect();
+    assert_eq!(voices.len(), 2, "Each frame should make a voice noise");
+    assert!(voices[0].position.distance(PLAYER_SPOTS[SPEAKER]) < 0.1);
+    assert_eq!(voices[0].loudness, VoiceLevel::Whisper.loudness());
+    assert_eq!(voices[1].loudness, VoiceLevel::Normal.loudness());
+}
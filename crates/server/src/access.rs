@@ -0,0 +1,302 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::app::ServerConfig;
+
+pub struct AccessControlPlugin;
+
+impl Plugin for AccessControlPlugin {
+    fn build(&self, app: &mut App) {
+        let config = app.world().resource::<ServerConfig>().clone();
+        let mut access_list = match &config.access_list_path {
+            Some(path) => AccessList::load(path.clone()),
+            None => AccessList::default(),
+        };
+        if config.whitelist_only {
+            access_list.set_whitelist_only(true);
+        }
+        access_list.prune_expired(unix_now());
+        app.insert_resource(access_list);
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BanEntry {
+    pub client_id: u64,
+    /// Address the client was connected from when it was banned, if it was connected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<IpAddr>,
+    pub reason: String,
+    /// Unix time in seconds at which the ban was issued.
+    pub banned_at: u64,
+    /// Unix time in seconds after which the ban is lifted, `None` for a permanent ban.
+    pub expires_at: Option<u64>,
+}
+
+impl BanEntry {
+    pub fn is_active(&self, now: u64) -> bool {
+        self.expires_at.is_none_or(|expires_at| now < expires_at)
+    }
+}
+
+/// On-disk layout of the access list.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct AccessListData {
+    /// Only clients in `allowed` may join when set.
+    pub whitelist_only: bool,
+    pub allowed: BTreeSet<u64>,
+    pub bans: Vec<BanEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AccessDenied {
+    Banned {
+        reason: String,
+        /// Time left before the ban is lifted, `None` for a permanent ban.
+        remaining: Option<Duration>,
+    },
+    NotWhitelisted,
+}
+
+impl fmt::Display for AccessDenied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccessDenied::Banned {
+                reason,
+                remaining: Some(remaining),
+            } => write!(f, "banned for {}: {reason}", format_duration(*remaining)),
+            AccessDenied::Banned {
+                reason,
+                remaining: None,
+            } => write!(f, "banned permanently: {reason}"),
+            AccessDenied::NotWhitelisted => write!(f, "not on the whitelist"),
+        }
+    }
+}
+
+/// Bans and whitelist keyed by the client id of the netcode connect token. Clients pick that id
+/// themselves, so a banned player could come back with another one: bans of connected clients
+/// also cover the address they connected from, see [`ban_address`].
+///
+/// Every change is written back to `path` so bans survive a restart.
+#[derive(Resource, Debug, Default)]
+pub struct AccessList {
+    path: Option<PathBuf>,
+    data: AccessListData,
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+impl AccessList {
+    pub fn load(path: PathBuf) -> Self {
+        let data = match std::fs::read_to_string(&path) {
+            Ok(text) => match ron::from_str(&text) {
+                Ok(data) => {
+                    info!("🔐 Loaded access list from {}", path.display());
+                    data
+                }
+                Err(e) => {
+                    error!(
+                        "❌ Failed to parse access list {}: {e}, starting empty",
+                        path.display()
+                    );
+                    AccessListData::default()
+                }
+            },
+            Err(_) => {
+                info!(
+                    "🔐 No access list at {}, it will be created on first change",
+                    path.display()
+                );
+                AccessListData::default()
+            }
+        };
+        Self {
+            path: Some(path),
+            data,
+        }
+    }
+
+    pub fn data(&self) -> &AccessListData {
+        &self.data
+    }
+
+    pub fn check(
+        &self,
+        client_id: u64,
+        address: Option<IpAddr>,
+        now: u64,
+    ) -> Result<(), AccessDenied> {
+        if let Some(ban) = self.data.bans.iter().find(|ban| {
+            let same_address = ban.address.is_some() && ban.address == address;
+            (ban.client_id == client_id || same_address) && ban.is_active(now)
+        }) {
+            return Err(AccessDenied::Banned {
+                reason: ban.reason.clone(),
+                remaining: ban
+                    .expires_at
+                    .map(|expires_at| Duration::from_secs(expires_at - now)),
+            });
+        }
+        if self.data.whitelist_only && !self.data.allowed.contains(&client_id) {
+            return Err(AccessDenied::NotWhitelisted);
+        }
+        Ok(())
+    }
+
+    /// Bans `client_id`, and `address` too when given. Fails, leaving the access list untouched,
+    /// when the ban would end past the last representable time.
+    pub fn ban(
+        &mut self,
+        client_id: u64,
+        address: Option<IpAddr>,
+        reason: String,
+        duration: Option<Duration>,
+        now: u64,
+    ) -> Result<(), String> {
+        let expires_at = match duration {
+            Some(duration) => Some(
+                now.checked_add(duration.as_secs())
+                    .ok_or_else(|| "ban duration is too long".to_string())?,
+            ),
+            None => None,
+        };
+        self.data.bans.retain(|ban| ban.client_id != client_id);
+        self.data.bans.push(BanEntry {
+            client_id,
+            address,
+            reason,
+            banned_at: now,
+            expires_at,
+        });
+        self.save();
+        Ok(())
+    }
+
+    /// Returns `false` if the client was not banned.
+    pub fn unban(&mut self, client_id: u64) -> bool {
+        let before = self.data.bans.len();
+        self.data.bans.retain(|ban| ban.client_id != client_id);
+        let removed = self.data.bans.len() != before;
+        if removed {
+            self.save();
+        }
+        removed
+    }
+
+    pub fn allow(&mut self, client_id: u64) {
+        if self.data.allowed.insert(client_id) {
+            self.save();
+        }
+    }
+
+    pub fn disallow(&mut self, client_id: u64) {
+        if self.data.allowed.remove(&client_id) {
+            self.save();
+        }
+    }
+
+    pub fn set_whitelist_only(&mut self, whitelist_only: bool) {
+        if self.data.whitelist_only != whitelist_only {
+            self.data.whitelist_only = whitelist_only;
+            self.save();
+        }
+    }
+
+    /// Drops bans that have run out. Returns how many were removed.
+    pub fn prune_expired(&mut self, now: u64) -> usize {
+        let before = self.data.bans.len();
+        self.data.bans.retain(|ban| ban.is_active(now));
+        let removed = before - self.data.bans.len();
+        if removed > 0 {
+            self.save();
+        }
+        removed
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let text = match ron::ser::to_string_pretty(&self.data, ron::ser::PrettyConfig::default()) {
+            Ok(text) => text,
+            Err(e) => {
+                error!("❌ Failed to serialize access list: {e}");
+                return;
+            }
+        };
+        // Write next to the target and rename so a crash never leaves a truncated file
+        let tmp_path = path.with_extension("tmp");
+        if let Err(e) =
+            std::fs::write(&tmp_path, text).and_then(|_| std::fs::rename(&tmp_path, path))
+        {
+            error!("❌ Failed to write access list {}: {e}", path.display());
+        }
+    }
+}
+
+/// Address to ban along with the client id of a client connected from `peer`. Local clients all
+/// share the loopback address, banning it would lock every one of them out.
+pub fn ban_address(peer: SocketAddr) -> Option<IpAddr> {
+    Some(peer.ip()).filter(|ip| !ip.is_loopback() && !ip.is_unspecified())
+}
+
+/// Parses durations such as `90s`, `30m`, `12h` or `7d`.
+///
+/// Returns `Ok(None)` when `text` is not a duration, and an error when it is one too long to
+/// count in seconds.
+pub fn parse_duration(text: &str) -> Result<Option<Duration>, String> {
+    let Some(split) = text
+        .find(|c: char| !c.is_ascii_digit())
+        .filter(|split| *split > 0)
+    else {
+        return Ok(None);
+    };
+    let (amount, unit) = text.split_at(split);
+    let unit_seconds: u64 = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 60 * 60 * 24,
+        _ => return Ok(None),
+    };
+    amount
+        .parse::<u64>()
+        .ok()
+        .and_then(|amount| amount.checked_mul(unit_seconds))
+        .map(|seconds| Some(Duration::from_secs(seconds)))
+        .ok_or_else(|| format!("duration '{text}' is too long"))
+}
+
+/// Formats a duration with its two largest units, such as `2d 5h`, `12m 30s` or `45s`.
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let units = [
+        (seconds / (60 * 60 * 24), "d"),
+        (seconds / (60 * 60) % 24, "h"),
+        (seconds / 60 % 60, "m"),
+        (seconds % 60, "s"),
+    ];
+    let first = units
+        .iter()
+        .position(|(amount, _)| *amount > 0)
+        .unwrap_or(units.len() - 1);
+    units[first..]
+        .iter()
+        .take(2)
+        .filter(|(amount, _)| *amount > 0 || first == units.len() - 1)
+        .map(|(amount, unit)| format!("{amount}{unit}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
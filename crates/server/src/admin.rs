@@ -0,0 +1,470 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::sync::Mutex;
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use lightyear::prelude::server::*;
+use lightyear::prelude::*;
+use shared::protocol::PlayerId;
+
+use crate::access::{AccessList, ban_address, format_duration, parse_duration, unix_now};
+use crate::app::ServerConfig;
+use crate::gameplay::{LevelSeed, ResetLevel, RestartMatch};
+use crate::network::KickClient;
+use crate::shutdown::RequestShutdown;
+
+/// How long the console and RCON threads wait for the game loop to answer a command.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub struct AdminPlugin;
+
+impl Plugin for AdminPlugin {
+    fn build(&self, app: &mut App) {
+        let (sender, receiver) = channel();
+        let config = app.world().resource::<ServerConfig>().clone();
+
+        spawn_stdin_console(sender.clone());
+        if let Some(addr) = config.rcon_addr {
+            spawn_rcon_listener(addr, config.rcon_password.unwrap_or_default(), sender);
+        }
+
+        app.insert_resource(AdminInbox(Mutex::new(receiver)));
+        app.add_systems(Update, process_admin_commands);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdminCommand {
+    Help,
+    Players,
+    Kick {
+        client_id: u64,
+    },
+    Ban {
+        client_id: u64,
+        duration: Option<Duration>,
+        reason: String,
+    },
+    Unban {
+        client_id: u64,
+    },
+    Bans,
+    Allow {
+        client_id: u64,
+    },
+    Disallow {
+        client_id: u64,
+    },
+    Whitelist {
+        enabled: bool,
+    },
+    /// Rebuilds the level and restarts the director with `seed`. The layout doesn't depend on the
+    /// seed yet, only the director's decisions do.
+    Map {
+        seed: u64,
+    },
+    TickRate {
+        hz: f64,
+    },
+    Restart,
+    Entities,
+    Shutdown {
+        countdown: Option<Duration>,
+    },
+}
+
+impl AdminCommand {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut words = line.split_whitespace();
+        let Some(name) = words.next() else {
+            return Err("empty command".to_string());
+        };
+
+        match name.to_lowercase().as_str() {
+            "help" => Ok(Self::Help),
+            "players" | "status" => Ok(Self::Players),
+            "kick" => Ok(Self::Kick {
+                client_id: parse_arg(words.next(), "client id")?,
+            }),
+            "ban" => {
+                let client_id = parse_arg(words.next(), "client id")?;
+                let mut rest = words.peekable();
+                let duration = match rest.peek() {
+                    Some(word) => parse_duration(word)?,
+                    None => None,
+                };
+                if duration.is_some() {
+                    rest.next();
+                }
+                let reason = rest.collect::<Vec<_>>().join(" ");
+                Ok(Self::Ban {
+                    client_id,
+                    duration,
+                    reason: if reason.is_empty() {
+                        "banned by admin".to_string()
+                    } else {
+                        reason
+                    },
+                })
+            }
+            "unban" => Ok(Self::Unban {
+                client_id: parse_arg(words.next(), "client id")?,
+            }),
+            "bans" => Ok(Self::Bans),
+            "allow" => Ok(Self::Allow {
+                client_id: parse_arg(words.next(), "client id")?,
+            }),
+            "disallow" => Ok(Self::Disallow {
+                client_id: parse_arg(words.next(), "client id")?,
+            }),
+            "whitelist" => match words.next() {
+                Some("on") => Ok(Self::Whitelist { enabled: true }),
+                Some("off") => Ok(Self::Whitelist { enabled: false }),
+                _ => Err("usage: whitelist on|off".to_string()),
+            },
+            "map" | "seed" => Ok(Self::Map {
+                seed: parse_arg(words.next(), "seed")?,
+            }),
+            "tickrate" => {
+                let hz: f64 = parse_arg(words.next(), "tick rate")?;
+                if hz <= 0.0 {
+                    return Err("tick rate must be positive".to_string());
+                }
+                Ok(Self::TickRate { hz })
+            }
+            "restart" => Ok(Self::Restart),
+            "entities" => Ok(Self::Entities),
+            "shutdown" => Ok(Self::Shutdown {
+                countdown: match words.next() {
+                    Some(word) => Some(Duration::from_secs(parse_arg(Some(word), "countdown")?)),
+                    None => None,
+                },
+            }),
+            other => Err(format!("unknown command '{other}', try 'help'")),
+        }
+    }
+}
+
+fn parse_arg<T: std::str::FromStr>(word: Option<&str>, what: &str) -> Result<T, String> {
+    let word = word.ok_or_else(|| format!("missing {what}"))?;
+    word.parse().map_err(|_| format!("invalid {what} '{word}'"))
+}
+
+pub struct AdminRequest {
+    pub command: AdminCommand,
+    pub reply: Sender<String>,
+}
+
+/// Commands coming from the stdin console and RCON connections, drained once per frame.
+#[derive(Resource)]
+pub struct AdminInbox(Mutex<Receiver<AdminRequest>>);
+
+/// Sends a command line to the game loop and waits for its answer.
+fn dispatch_line(sender: &Sender<AdminRequest>, line: &str) -> String {
+    let command = match AdminCommand::parse(line) {
+        Ok(command) => command,
+        Err(e) => return format!("error: {e}"),
+    };
+    let (reply, answer) = channel();
+    if sender.send(AdminRequest { command, reply }).is_err() {
+        return "error: server is shutting down".to_string();
+    }
+    answer
+        .recv_timeout(REPLY_TIMEOUT)
+        .unwrap_or_else(|_| "error: no answer from the game loop".to_string())
+}
+
+fn spawn_stdin_console(sender: Sender<AdminRequest>) {
+    thread::spawn(move || {
+        let stdin = std::io::stdin();
+        for line in stdin.lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            println!("{}", dispatch_line(&sender, &line));
+        }
+    });
+}
+
+fn spawn_rcon_listener(addr: SocketAddr, password: String, sender: Sender<AdminRequest>) {
+    if password.is_empty() {
+        warn!("RCON requested on {addr} without a password, refusing to start it");
+        return;
+    }
+
+    let listener = match TcpListener::bind(addr) {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("❌ Failed to bind RCON listener on {addr}: {e}");
+            return;
+        }
+    };
+    info!("🛠️ RCON listening on {addr}");
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let sender = sender.clone();
+            let password = password.clone();
+            thread::spawn(move || {
+                let peer = stream.peer_addr().ok();
+                if let Err(e) = handle_rcon_session(stream, &password, &sender) {
+                    debug!("RCON session {peer:?} ended: {e}");
+                }
+            });
+        }
+    });
+}
+
+/// Line-based session: the first line must be `auth <password>`, every following line is a command.
+fn handle_rcon_session(
+    stream: TcpStream,
+    password: &str,
+    sender: &Sender<AdminRequest>,
+) -> std::io::Result<()> {
+    let peer = stream.peer_addr()?;
+    let mut writer = stream.try_clone()?;
+    let mut lines = BufReader::new(stream).lines();
+
+    let authenticated = match lines.next() {
+        Some(Ok(line)) => line.strip_prefix("auth ").map(str::trim) == Some(password),
+        _ => false,
+    };
+    if !authenticated {
+        warn!("⛔ RCON authentication failed from {peer}");
+        writeln!(writer, "error: authentication failed")?;
+        return Ok(());
+    }
+    info!("🛠️ RCON session opened from {peer}");
+    writeln!(writer, "ok")?;
+
+    for line in lines {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line == "quit" {
+            break;
+        }
+        info!("🛠️ RCON {peer}: {line}");
+        writeln!(writer, "{}", dispatch_line(sender, line))?;
+    }
+    Ok(())
+}
+
+fn process_admin_commands(world: &mut World) {
+    let requests: Vec<AdminRequest> = {
+        let inbox = world.resource::<AdminInbox>();
+        let Ok(receiver) = inbox.0.lock() else {
+            return;
+        };
+        receiver.try_iter().collect()
+    };
+
+    for request in requests {
+        let answer = execute_admin_command(world, request.command);
+        let _ = request.reply.send(answer);
+    }
+}
+
+fn execute_admin_command(world: &mut World, command: AdminCommand) -> String {
+    match command {
+        AdminCommand::Help => "commands: players, kick <id>, ban <id> [30m|12h|7d] [reason] \
+             (ids are picked by clients, a connected client is banned by address too), \
+             unban <id>, bans, allow <id>, disallow <id>, whitelist on|off, \
+             map <seed> (resets the level, the seed only affects the director), tickrate <hz>, \
+             restart, entities, shutdown [seconds]"
+            .to_string(),
+        AdminCommand::Players => list_players(world),
+        AdminCommand::Kick { client_id } => match find_client(world, client_id) {
+            Some(client) => {
+                world.trigger(KickClient {
+                    client,
+                    reason: "Kicked by an admin".to_string(),
+                });
+                format!("kicked {client_id}")
+            }
+            None => format!("error: no connected client {client_id}"),
+        },
+        AdminCommand::Ban {
+            client_id,
+            duration,
+            reason,
+        } => {
+            let client = find_client(world, client_id);
+            let address = client_address(world, client);
+            if let Err(e) = world.resource_mut::<AccessList>().ban(
+                client_id,
+                address,
+                reason.clone(),
+                duration,
+                unix_now(),
+            ) {
+                return format!("error: {e}");
+            }
+            if let Some(client) = client {
+                world.trigger(KickClient {
+                    client,
+                    reason: format!("Banned: {reason}"),
+                });
+            }
+            let length = duration.map_or("permanently".to_string(), |d| {
+                format!("for {}", format_duration(d))
+            });
+            info!("⛔ Banned client {client_id} {length}: {reason}");
+            format!("banned {client_id} {length}: {reason}")
+        }
+        AdminCommand::Unban { client_id } => {
+            if world.resource_mut::<AccessList>().unban(client_id) {
+                format!("unbanned {client_id}")
+            } else {
+                format!("error: {client_id} is not banned")
+            }
+        }
+        AdminCommand::Bans => list_bans(world.resource::<AccessList>()),
+        AdminCommand::Allow { client_id } => {
+            world.resource_mut::<AccessList>().allow(client_id);
+            format!("{client_id} added to the whitelist")
+        }
+        AdminCommand::Disallow { client_id } => {
+            world.resource_mut::<AccessList>().disallow(client_id);
+            let client = find_client(world, client_id);
+            let address = client_address(world, client);
+            let access = world
+                .resource::<AccessList>()
+                .check(client_id, address, unix_now());
+            if let (Err(denied), Some(client)) = (access, client) {
+                world.trigger(KickClient {
+                    client,
+                    reason: format!("Access denied: {denied}"),
+                });
+            }
+            format!("{client_id} removed from the whitelist")
+        }
+        AdminCommand::Whitelist { enabled } => {
+            world
+                .resource_mut::<AccessList>()
+                .set_whitelist_only(enabled);
+            format!("whitelist-only {}", if enabled { "on" } else { "off" })
+        }
+        AdminCommand::Map { seed } => {
+            world.trigger(ResetLevel { seed });
+            format!("level reset with seed {seed}, which only affects the director")
+        }
+        AdminCommand::TickRate { hz } => {
+            world.resource_mut::<Time<Fixed>>().set_timestep_hz(hz);
+            warn!("Fixed tick rate changed to {hz} Hz, clients keep their own tick duration");
+            format!("tick rate set to {hz} Hz")
+        }
+        AdminCommand::Restart => {
+            world.trigger(RestartMatch);
+            "match restarted".to_string()
+        }
+        AdminCommand::Entities => count_entities(world),
+        AdminCommand::Shutdown { countdown } => {
+            let countdown =
+                countdown.unwrap_or(world.resource::<ServerConfig>().shutdown_countdown);
+            world.trigger(RequestShutdown {
+                reason: "Server is shutting down for maintenance".to_string(),
+                countdown,
+            });
+            format!("shutting down in {}s", countdown.as_secs())
+        }
+    }
+}
+
+fn find_client(world: &mut World, client_id: u64) -> Option<Entity> {
+    world
+        .query_filtered::<(Entity, &RemoteId), With<ClientOf>>()
+        .iter(world)
+        .find(|(_, remote_id)| remote_id.to_bits() == client_id)
+        .map(|(entity, _)| entity)
+}
+
+/// Address a connected client can be banned by, see [`ban_address`].
+fn client_address(world: &World, client: Option<Entity>) -> Option<IpAddr> {
+    let peer_addr = world.get::<PeerAddr>(client?)?;
+    ban_address(peer_addr.0)
+}
+
+fn list_players(world: &mut World) -> String {
+    let names: HashMap<u64, String> = world
+        .query::<(&PlayerId, &Name)>()
+        .iter(world)
+        .map(|(player_id, name)| (player_id.0.to_bits(), name.to_string()))
+        .collect();
+
+    let mut lines = Vec::new();
+    for (remote_id, link) in world
+        .query_filtered::<(&RemoteId, &Link), (With<ClientOf>, With<Connected>)>()
+        .iter(world)
+    {
+        let client_id = remote_id.to_bits();
+        let name = names
+            .get(&client_id)
+            .map(String::as_str)
+            .unwrap_or("<no player>");
+        lines.push(format!(
+            "{name} id={client_id} remote={:?} ping={}ms",
+            remote_id.0,
+            link.stats.rtt.as_millis()
+        ));
+    }
+
+    if lines.is_empty() {
+        "no players connected".to_string()
+    } else {
+        lines.join("\n")
+    }
+}
+
+fn list_bans(access_list: &AccessList) -> String {
+    let now = unix_now();
+    let lines: Vec<String> = access_list
+        .data()
+        .bans
+        .iter()
+        .filter(|ban| ban.is_active(now))
+        .map(|ban| {
+            let banned = match ban.address {
+                Some(address) => format!("{} ({address})", ban.client_id),
+                None => ban.client_id.to_string(),
+            };
+            match ban.expires_at {
+                Some(expires_at) => format!(
+                    "{banned} expires in {}: {}",
+                    format_duration(Duration::from_secs(expires_at.saturating_sub(now))),
+                    ban.reason
+                ),
+                None => format!("{banned} permanent: {}", ban.reason),
+            }
+        })
+        .collect();
+
+    if lines.is_empty() {
+        "no active bans".to_string()
+    } else {
+        lines.join("\n")
+    }
+}
+
+fn count_entities(world: &mut World) -> String {
+    let total = world.entities().len();
+    let players = world.query::<&PlayerId>().iter(world).count();
+    let clients = world.query::<&ClientOf>().iter(world).count();
+    let replicated = world.query::<&Replicate>().iter(world).count();
+    let seed = world.resource::<LevelSeed>().0;
+    format!(
+        "entities={total} players={players} clients={clients} replicated={replicated} seed={seed}"
+    )
+}
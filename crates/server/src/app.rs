@@ -1,15 +1,36 @@
+use bevy::log::LogPlugin;
 use bevy::prelude::*;
 
 use bevy::window::{Window, WindowPlugin};
 
 use lightyear::prelude::server::*;
-// use lightyear::prelude::{DeltaManager, Server};
 
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::time::Duration;
 
+use crate::access::AccessControlPlugin;
+use crate::admin::AdminPlugin;
+use crate::chat::ChatPlugin;
+use crate::director::DirectorPlugin;
+use crate::enemy::EnemyPlugin;
 use crate::gameplay::ServerGameplayPlugin;
+use crate::hits::HitPlugin;
+use crate::interaction::InteractionPlugin;
+use crate::logging::init_json_logging;
+use crate::match_state::MatchStatePlugin;
+use crate::metrics::MetricsPlugin;
+use crate::navigation::NavigationPlugin;
 use crate::network::NetworkPlugin;
+use crate::noise::NoisePlugin;
+use crate::objective::ObjectivePlugin;
+use crate::priority::ReplicationPriorityPlugin;
+use crate::relevancy::RelevancyPlugin;
 use crate::render::RenderPlugin;
+use crate::shutdown::ShutdownPlugin;
+use crate::sound::SoundPlugin;
+use crate::spawn::SpawnPlugin;
+use crate::voice::VoicePlugin;
 use shared::SharedPlugin;
 
 use bevy::prelude::{App, default};
@@ -20,12 +41,45 @@ pub enum ServerMode {
     Headless,
 }
 
-pub fn add_basics_to_server_app(app: &mut App, headless: bool) -> &mut App {
+/// Operator-facing settings for the dedicated server, usually filled from the CLI.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct ServerConfig {
+    /// Address of the TCP RCON listener. `None` keeps RCON disabled.
+    pub rcon_addr: Option<SocketAddr>,
+    /// Password a RCON connection has to send before any command is accepted.
+    pub rcon_password: Option<String>,
+    /// File holding bans and the whitelist. `None` keeps them in memory only.
+    pub access_list_path: Option<PathBuf>,
+    /// Only let whitelisted clients in, whatever the access list file says.
+    pub whitelist_only: bool,
+    /// Delay between the shutdown announcement and the disconnection of every client.
+    pub shutdown_countdown: Duration,
+    /// Address of the HTTP `/metrics` endpoint. `None` keeps it disabled.
+    pub metrics_addr: Option<SocketAddr>,
+    /// Bytes per second sent to each client, split by replication priority. `None` sends
+    /// everything as soon as it changes.
+    pub bandwidth_cap: Option<u32>,
+    /// RON file with the [`DirectorTunables`](crate::director::DirectorTunables). `None` uses the
+    /// defaults.
+    pub director_path: Option<PathBuf>,
+    /// RON file with the [`MissionDefinition`](crate::objective::MissionDefinition) played
+    /// every round. `None` plays a mission without objectives.
+    pub mission_path: Option<PathBuf>,
+}
+
+/// `json_logs` prints logs as JSON lines instead of the default human-readable format.
+pub fn add_basics_to_server_app(app: &mut App, headless: bool, json_logs: bool) -> &mut App {
+    let mut plugins = DefaultPlugins.build();
+    if json_logs {
+        init_json_logging();
+        plugins = plugins.disable::<LogPlugin>();
+    }
+
     if headless {
-        app.add_plugins(DefaultPlugins);
+        app.add_plugins(plugins);
     } else {
         app.add_plugins((
-            DefaultPlugins.set(WindowPlugin {
+            plugins.set(WindowPlugin {
                 primary_window: Some(Window {
                     title: "Yolo Game - Server".to_string(),
                     resolution: (400., 200.).into(),
@@ -46,18 +100,33 @@ pub fn add_network_to_server_app(app: &mut App) -> &mut App {
         // tick_duration: Duration::from_secs_f64(1.0 / shared::FIXED_TIMESTEP_HZ),
         tick_duration: Duration::from_secs_f64(1.0 / shared::FIXED_TIMESTEP_HZ),
     });
-    app.add_plugins((NetworkPlugin, ServerGameplayPlugin));
-
-    // Delta compression
-    // let server = app
-    //     .world_mut()
-    //     .query_filtered::<Entity, With<Server>>()
-    //     .single(app.world_mut())
-    //     .unwrap();
-
-    // // set some input-delay since we are predicting all entities
-    // app.world_mut()
-    //     .entity_mut(server)
-    //     .insert(DeltaManager::default());
+    if !app.world().contains_resource::<ServerConfig>() {
+        app.insert_resource(ServerConfig::default());
+    }
+    app.add_plugins((
+        AccessControlPlugin,
+        NetworkPlugin,
+        ServerGameplayPlugin,
+        AdminPlugin,
+        ShutdownPlugin,
+        MetricsPlugin,
+        RelevancyPlugin,
+        ReplicationPriorityPlugin,
+        ChatPlugin,
+        NoisePlugin,
+        VoicePlugin,
+        SoundPlugin,
+        HitPlugin,
+        // The round and what happens in it
+        (
+            MatchStatePlugin,
+            InteractionPlugin,
+            ObjectivePlugin,
+            SpawnPlugin,
+            NavigationPlugin,
+            EnemyPlugin,
+            DirectorPlugin,
+        ),
+    ));
     app
 }
@@ -0,0 +1,152 @@
+use std::collections::VecDeque;
+
+use avian3d::prelude::Position;
+use bevy::prelude::*;
+use lightyear::prelude::server::ClientOf;
+use lightyear::prelude::*;
+use shared::chat::{
+    ChatChannel, ChatLine, ChatScope, ChatSend, MAX_CHAT_LENGTH, PROXIMITY_CHAT_RANGE,
+};
+use shared::protocol::{PlayerId, Team};
+
+use crate::logging::client_span;
+
+/// Messages a client can send in a row before being rate limited.
+const CHAT_BURST: f32 = 5.0;
+/// Messages per second given back to each client, up to [`CHAT_BURST`].
+const CHAT_REFILL_PER_SEC: f32 = 1.0;
+/// Lines kept to replay to players joining later.
+const CHAT_HISTORY_LEN: usize = 50;
+
+type ChatClient<'a> = (
+    Entity,
+    &'a RemoteId,
+    &'a mut MessageReceiver<ChatSend>,
+    &'a mut ChatRateLimit,
+);
+
+/// Relays chat between players according to the [`ChatScope`] of each message. All and team
+/// messages are kept in a short history replayed to players when they spawn.
+pub struct ChatPlugin;
+
+impl Plugin for ChatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChatHistory>();
+        app.add_observer(add_chat_rate_limit);
+        app.add_systems(Update, (send_chat_history, relay_chat).chain());
+    }
+}
+
+/// Token bucket limiting how fast a client can send messages.
+#[derive(Component)]
+struct ChatRateLimit {
+    tokens: f32,
+}
+
+impl Default for ChatRateLimit {
+    fn default() -> Self {
+        Self { tokens: CHAT_BURST }
+    }
+}
+
+/// Last chat lines, with the team they were restricted to.
+#[derive(Resource, Default)]
+struct ChatHistory(VecDeque<(Option<Team>, ChatLine)>);
+
+fn add_chat_rate_limit(trigger: Trigger<OnAdd, ClientOf>, mut commands: Commands) {
+    commands
+        .entity(trigger.target())
+        .insert(ChatRateLimit::default());
+}
+
+fn relay_chat(
+    time: Res<Time>,
+    timeline: Single<&LocalTimeline, With<Server>>,
+    mut clients: Query<ChatClient, With<ClientOf>>,
+    mut senders: Query<&mut MessageSender<ChatLine>, With<ClientOf>>,
+    players: Query<(&Name, &Team, &Position, &ControlledBy), With<PlayerId>>,
+    mut history: ResMut<ChatHistory>,
+) {
+    for (client, remote_id, mut receiver, mut rate_limit) in clients.iter_mut() {
+        rate_limit.tokens =
+            (rate_limit.tokens + time.delta_secs() * CHAT_REFILL_PER_SEC).min(CHAT_BURST);
+
+        for message in receiver.receive() {
+            let _span = client_span(remote_id.to_bits(), client, timeline.tick()).entered();
+            let Some((name, team, position, _)) = players
+                .iter()
+                .find(|(_, _, _, controlled_by)| controlled_by.owner == client)
+            else {
+                continue;
+            };
+            let text: String = message.text.trim().chars().take(MAX_CHAT_LENGTH).collect();
+            if text.is_empty() {
+                continue;
+            }
+            if rate_limit.tokens < 1.0 {
+                info!(event = "chat_rate_limited", "🔇 Dropping chat from {name}");
+                continue;
+            }
+            rate_limit.tokens -= 1.0;
+            info!(
+                event = "chat_message",
+                scope = ?message.scope,
+                "💬 [{}] {name}: {text}",
+                message.scope.label()
+            );
+
+            let line = ChatLine {
+                sender: name.to_string(),
+                scope: message.scope,
+                text,
+                history: false,
+            };
+            for (_, other_team, other_position, controlled_by) in players.iter() {
+                let receives = match message.scope {
+                    ChatScope::All => true,
+                    ChatScope::Team => other_team == team,
+                    ChatScope::Proximity => {
+                        position.0.distance(other_position.0) <= PROXIMITY_CHAT_RANGE
+                    }
+                };
+                if !receives {
+                    continue;
+                }
+                if let Ok(mut sender) = senders.get_mut(controlled_by.owner) {
+                    sender.send::<ChatChannel>(line.clone());
+                }
+            }
+
+            let restricted_to = match message.scope {
+                ChatScope::All => None,
+                ChatScope::Team => Some(*team),
+                // Only makes sense to the players who were around
+                ChatScope::Proximity => continue,
+            };
+            if history.0.len() == CHAT_HISTORY_LEN {
+                history.0.pop_front();
+            }
+            history.0.push_back((restricted_to, line));
+        }
+    }
+}
+
+fn send_chat_history(
+    players: Query<(&Team, &ControlledBy), Added<PlayerId>>,
+    mut senders: Query<&mut MessageSender<ChatLine>, With<ClientOf>>,
+    history: Res<ChatHistory>,
+) {
+    for (team, controlled_by) in players.iter() {
+        let Ok(mut sender) = senders.get_mut(controlled_by.owner) else {
+            continue;
+        };
+        for (restricted_to, line) in history.0.iter() {
+            if restricted_to.is_none_or(|restricted_to| restricted_to == *team) {
+                sender.send::<ChatChannel>(ChatLine {
+                    history: true,
+                    ..line.clone()
+                });
+            }
+        }
+    }
+}
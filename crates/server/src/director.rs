@@ -0,0 +1,501 @@
+use std::path::Path;
+
+use avian3d::prelude::*;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use lightyear::connection::server::Started;
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use shared::enemy::{Enemy, EnemyState};
+use shared::protocol::PlayerId;
+
+use crate::app::ServerConfig;
+use crate::enemy::spawn_enemy;
+use crate::gameplay::{LevelSeed, ResetLevel};
+use crate::match_state::round_in_progress;
+use crate::navigation::{LevelGeometryFilter, NavGrid};
+
+/// Highest intensity a player can reach.
+pub const MAX_INTENSITY: f32 = 100.0;
+/// Health of an unharmed player, [`PlayerDamaged`] amounts are taken out of it.
+pub const PLAYER_MAX_HEALTH: f32 = 100.0;
+/// Height above the floor enemies are spawned at, they fall from there.
+const SPAWN_HEIGHT: f32 = 2.0;
+/// Height of the eyes of a player above its capsule center.
+const PLAYER_EYE_HEIGHT: f32 = 0.5;
+
+/// Paces the enemy spawns from how intense the game is for the players. Each player has a
+/// [`PlayerIntensity`] that rises with damage taken, awake enemies nearby and a low
+/// [`PlayerCondition`], and slowly falls while nothing happens. The [`Director`] cycles through
+/// build-up, peak and relax phases from the intensity of the most stressed player and spawns
+/// enemies on the navigation grid, out of sight of every player. Its tunables are read from
+/// [`ServerConfig::director_path`], the defaults are used without a config.
+pub struct DirectorPlugin;
+
+impl Plugin for DirectorPlugin {
+    fn build(&self, app: &mut App) {
+        let director_path = app
+            .world()
+            .get_resource::<ServerConfig>()
+            .and_then(|config| config.director_path.clone());
+        let tunables = match &director_path {
+            Some(path) => DirectorTunables::load(path),
+            None => DirectorTunables::default(),
+        };
+        app.insert_resource(tunables);
+        app.add_event::<PlayerDamaged>();
+        app.add_observer(start_director);
+        app.add_observer(restart_director);
+        app.add_observer(add_player_intensity);
+        app.add_event::<DirectorSpawn>();
+        app.add_systems(
+            FixedUpdate,
+            (
+                damage_players,
+                update_intensity,
+                run_director.run_if(round_in_progress),
+                spawn_director_enemies,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Director settings, loaded from a RON file. Missing fields keep their default value.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct DirectorTunables {
+    /// Intensity added per point of damage taken.
+    pub damage_intensity: f32,
+    /// Intensity added each second per awake enemy within `nearby_enemy_radius`.
+    pub nearby_enemy_intensity: f32,
+    pub nearby_enemy_radius: f32,
+    /// Health fraction under which a player gains `low_health_intensity` each second.
+    pub low_health: f32,
+    pub low_health_intensity: f32,
+    /// Ammo fraction under which a player gains `low_ammo_intensity` each second.
+    pub low_ammo: f32,
+    pub low_ammo_intensity: f32,
+    /// Intensity lost each second while nothing is happening to a player.
+    pub intensity_decay: f32,
+    /// Team intensity turning the build-up into a peak.
+    pub peak_intensity: f32,
+    /// Longest build-up in seconds, a wave starts the peak once it is over.
+    pub max_build_up: f32,
+    pub peak_duration: f32,
+    /// Shortest relax in seconds. It lasts until the team intensity falls under
+    /// `relax_intensity`.
+    pub relax_duration: f32,
+    pub relax_intensity: f32,
+    /// Seconds between two wandering enemies during the build-up, picked in this range.
+    pub wanderer_interval: (f32, f32),
+    /// Enemies in the wave starting a peak, picked in this range.
+    pub wave_size: (u32, u32),
+    /// The director never spawns more enemies than this.
+    pub max_enemies: u32,
+    /// Range of distances to the closest player enemies are spawned at.
+    pub spawn_distance: (f32, f32),
+}
+
+impl Default for DirectorTunables {
+    fn default() -> Self {
+        Self {
+            damage_intensity: 2.0,
+            nearby_enemy_intensity: 5.0,
+            nearby_enemy_radius: 10.0,
+            low_health: 0.3,
+            low_health_intensity: 3.0,
+            low_ammo: 0.2,
+            low_ammo_intensity: 2.0,
+            intensity_decay: 5.0,
+            peak_intensity: 70.0,
+            max_build_up: 90.0,
+            peak_duration: 15.0,
+            relax_duration: 30.0,
+            relax_intensity: 20.0,
+            wanderer_interval: (10.0, 20.0),
+            wave_size: (3, 6),
+            max_enemies: 12,
+            spawn_distance: (12.0, 30.0),
+        }
+    }
+}
+
+impl DirectorTunables {
+    pub fn load(path: &Path) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(text) => match ron::from_str::<Self>(&text) {
+                Ok(tunables) => match tunables.check_ranges() {
+                    Ok(()) => {
+                        info!("🎬 Loaded director tunables from {}", path.display());
+                        tunables
+                    }
+                    Err(e) => {
+                        error!(
+                            "❌ Invalid director tunables {}: {e}, using defaults",
+                            path.display()
+                        );
+                        Self::default()
+                    }
+                },
+                Err(e) => {
+                    error!(
+                        "❌ Failed to parse director tunables {}: {e}, using defaults",
+                        path.display()
+                    );
+                    Self::default()
+                }
+            },
+            Err(_) => {
+                info!(
+                    "🎬 No director tunables at {}, using defaults",
+                    path.display()
+                );
+                Self::default()
+            }
+        }
+    }
+
+    /// Picking in a range whose minimum is above its maximum panics.
+    fn check_ranges(&self) -> Result<(), String> {
+        for (name, (min, max)) in [
+            ("wanderer_interval", self.wanderer_interval),
+            ("spawn_distance", self.spawn_distance),
+        ] {
+            if !min.is_finite() || !max.is_finite() || min > max {
+                return Err(format!("{name} ({min}, {max}) is not a range"));
+            }
+        }
+        let (min, max) = self.wave_size;
+        if min > max {
+            return Err(format!("wave_size ({min}, {max}) is not a range"));
+        }
+        Ok(())
+    }
+}
+
+/// Damage dealt to a player, taken out of its health and one of the inputs of its intensity.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerDamaged {
+    pub player: Entity,
+    pub amount: f32,
+}
+
+/// How stressed a player is, from 0 to [`MAX_INTENSITY`].
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+pub struct PlayerIntensity(pub f32);
+
+/// Health and ammo of a player as fractions of their maximum. Damage lowers the health until the
+/// level resets, nothing happens at zero yet. The ammo stays full until players can run out of it.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct PlayerCondition {
+    pub health: f32,
+    pub ammo: f32,
+}
+
+impl Default for PlayerCondition {
+    fn default() -> Self {
+        Self {
+            health: 1.0,
+            ammo: 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DirectorPhase {
+    /// Occasional wandering enemies while the tension rises.
+    BuildUp,
+    /// Starts with a wave and lets the players fight it for a while.
+    Peak,
+    /// No spawns until the players calm down.
+    Relax,
+}
+
+/// Enemies the director wants spawned.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DirectorSpawn {
+    Wanderer,
+    Wave(u32),
+}
+
+/// Pacing state machine. It only depends on its inputs and its seed, so the same seed and the
+/// same inputs always give the same decisions.
+#[derive(Resource)]
+pub struct Director {
+    tunables: DirectorTunables,
+    phase: DirectorPhase,
+    /// Seconds spent in the current phase.
+    phase_time: f32,
+    /// Seconds before the next wandering enemy.
+    next_wanderer: f32,
+    rng: StdRng,
+}
+
+impl Director {
+    pub fn new(tunables: DirectorTunables, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (min, max) = tunables.wanderer_interval;
+        let next_wanderer = rng.random_range(min..=max);
+        Self {
+            tunables,
+            phase: DirectorPhase::BuildUp,
+            phase_time: 0.0,
+            next_wanderer,
+            rng,
+        }
+    }
+
+    pub fn phase(&self) -> DirectorPhase {
+        self.phase
+    }
+
+    pub fn tunables(&self) -> &DirectorTunables {
+        &self.tunables
+    }
+
+    fn enter(&mut self, phase: DirectorPhase) {
+        self.phase = phase;
+        self.phase_time = 0.0;
+    }
+
+    /// Advances the director by `delta_secs` and returns what to spawn now, if anything.
+    pub fn update(
+        &mut self,
+        team_intensity: f32,
+        enemies_alive: u32,
+        delta_secs: f32,
+    ) -> Option<DirectorSpawn> {
+        self.phase_time += delta_secs;
+        let room = self.tunables.max_enemies.saturating_sub(enemies_alive);
+        match self.phase {
+            DirectorPhase::BuildUp => {
+                if team_intensity >= self.tunables.peak_intensity
+                    || self.phase_time >= self.tunables.max_build_up
+                {
+                    self.enter(DirectorPhase::Peak);
+                    let (min, max) = self.tunables.wave_size;
+                    let size = self.rng.random_range(min..=max).min(room);
+                    return (size > 0).then_some(DirectorSpawn::Wave(size));
+                }
+                self.next_wanderer -= delta_secs;
+                if self.next_wanderer <= 0.0 {
+                    let (min, max) = self.tunables.wanderer_interval;
+                    self.next_wanderer = self.rng.random_range(min..=max);
+                    return (room > 0).then_some(DirectorSpawn::Wanderer);
+                }
+            }
+            DirectorPhase::Peak => {
+                if self.phase_time >= self.tunables.peak_duration {
+                    self.enter(DirectorPhase::Relax);
+                }
+            }
+            DirectorPhase::Relax => {
+                if self.phase_time >= self.tunables.relax_duration
+                    && team_intensity < self.tunables.relax_intensity
+                {
+                    self.enter(DirectorPhase::BuildUp);
+                }
+            }
+        }
+        None
+    }
+
+    /// One of `candidates`, picked at random.
+    pub fn pick(&mut self, candidates: &[Vec3]) -> Option<Vec3> {
+        candidates.choose(&mut self.rng).copied()
+    }
+}
+
+fn start_director(
+    _trigger: Trigger<OnAdd, Started>,
+    tunables: Res<DirectorTunables>,
+    seed: Res<LevelSeed>,
+    mut commands: Commands,
+) {
+    commands.insert_resource(Director::new(tunables.clone(), seed.0));
+}
+
+/// A new level starts a new build-up, seeded like the level, with every player healed.
+fn restart_director(
+    trigger: Trigger<ResetLevel>,
+    tunables: Res<DirectorTunables>,
+    mut players: Query<&mut PlayerCondition>,
+    mut commands: Commands,
+) {
+    commands.insert_resource(Director::new(tunables.clone(), trigger.seed));
+    for mut condition in players.iter_mut() {
+        condition.health = 1.0;
+    }
+}
+
+fn add_player_intensity(trigger: Trigger<OnAdd, PlayerId>, mut commands: Commands) {
+    commands
+        .entity(trigger.target())
+        .insert((PlayerIntensity::default(), PlayerCondition::default()));
+}
+
+fn damage_players(
+    mut damaged: EventReader<PlayerDamaged>,
+    mut players: Query<&mut PlayerCondition>,
+) {
+    for event in damaged.read() {
+        if let Ok(mut condition) = players.get_mut(event.player) {
+            condition.health = (condition.health - event.amount / PLAYER_MAX_HEALTH).max(0.0);
+        }
+    }
+}
+
+fn update_intensity(
+    time: Res<Time>,
+    tunables: Res<DirectorTunables>,
+    mut damaged: EventReader<PlayerDamaged>,
+    mut players: Query<(Entity, &Position, &PlayerCondition, &mut PlayerIntensity)>,
+    enemies: Query<(&Position, &EnemyState), With<Enemy>>,
+) {
+    let delta_secs = time.delta_secs();
+    let mut damage: HashMap<Entity, f32> = HashMap::default();
+    for event in damaged.read() {
+        *damage.entry(event.player).or_default() += event.amount;
+    }
+
+    for (player, position, condition, mut intensity) in players.iter_mut() {
+        let nearby = enemies
+            .iter()
+            .filter(|(enemy_position, state)| {
+                **state != EnemyState::Sleeping
+                    && enemy_position.0.distance(position.0) <= tunables.nearby_enemy_radius
+            })
+            .count();
+        let mut gain = damage.get(&player).copied().unwrap_or_default() * tunables.damage_intensity
+            + nearby as f32 * tunables.nearby_enemy_intensity * delta_secs;
+        if condition.health < tunables.low_health {
+            gain += tunables.low_health_intensity * delta_secs;
+        }
+        if condition.ammo < tunables.low_ammo {
+            gain += tunables.low_ammo_intensity * delta_secs;
+        }
+        let value = if gain > 0.0 {
+            intensity.0 + gain
+        } else {
+            intensity.0 - tunables.intensity_decay * delta_secs
+        };
+        intensity.set_if_neq(PlayerIntensity(value.clamp(0.0, MAX_INTENSITY)));
+    }
+}
+
+fn run_director(
+    time: Res<Time>,
+    director: Option<ResMut<Director>>,
+    players: Query<&PlayerIntensity>,
+    enemies: Query<(), With<Enemy>>,
+    mut spawns: EventWriter<DirectorSpawn>,
+) {
+    let Some(mut director) = director else {
+        return;
+    };
+    // Nobody to scare
+    if players.is_empty() {
+        return;
+    }
+
+    let team_intensity = players
+        .iter()
+        .map(|intensity| intensity.0)
+        .fold(0.0, f32::max);
+    let phase = director.phase();
+    let spawn = director.update(
+        team_intensity,
+        enemies.iter().len() as u32,
+        time.delta_secs(),
+    );
+    if director.phase() != phase {
+        info!(
+            event = "director_phase",
+            phase = ?director.phase(),
+            intensity = team_intensity,
+            "🎬 Director: {phase:?} -> {:?}",
+            director.phase()
+        );
+    }
+    if let Some(spawn) = spawn {
+        spawns.write(spawn);
+    }
+}
+
+/// Spawns the enemies asked by the director on walkable cells at the right distance that no
+/// player can see. They head to the closest player, their hearing and sight do the rest.
+fn spawn_director_enemies(
+    mut spawns: EventReader<DirectorSpawn>,
+    director: Option<ResMut<Director>>,
+    players: Query<&Position, With<PlayerId>>,
+    level: Query<(), LevelGeometryFilter>,
+    grid: Res<NavGrid>,
+    spatial_query: SpatialQuery,
+    mut commands: Commands,
+) {
+    let count: u32 = spawns
+        .read()
+        .map(|spawn| match spawn {
+            DirectorSpawn::Wanderer => 1,
+            DirectorSpawn::Wave(size) => *size,
+        })
+        .sum();
+    let Some(mut director) = director.filter(|_| count > 0) else {
+        return;
+    };
+
+    let (min_distance, max_distance) = director.tunables().spawn_distance;
+    let is_level = |entity: Entity| level.contains(entity);
+    let candidates: Vec<Vec3> = grid
+        .walkable_positions()
+        .map(|floor| floor + Vec3::Y * SPAWN_HEIGHT)
+        .filter(|spawn| {
+            let closest = players
+                .iter()
+                .map(|position| position.0.distance(*spawn))
+                .fold(f32::MAX, f32::min);
+            (min_distance..=max_distance).contains(&closest)
+        })
+        .filter(|spawn| {
+            players.iter().all(|position| {
+                let eye = position.0 + Vec3::Y * PLAYER_EYE_HEIGHT;
+                let Ok(direction) = Dir3::new(*spawn - eye) else {
+                    return false;
+                };
+                spatial_query
+                    .cast_ray_predicate(
+                        eye,
+                        direction,
+                        eye.distance(*spawn),
+                        true,
+                        &SpatialQueryFilter::default(),
+                        &is_level,
+                    )
+                    .is_some()
+            })
+        })
+        .collect();
+
+    for _ in 0..count {
+        let Some(spawn) = director.pick(&candidates) else {
+            debug!("🎬 Director found no hidden spawn position");
+            return;
+        };
+        let Some(target) = players
+            .iter()
+            .map(|position| position.0)
+            .min_by(|a, b| a.distance(spawn).total_cmp(&b.distance(spawn)))
+        else {
+            return;
+        };
+        let enemy = spawn_enemy(&mut commands, spawn, Some(target));
+        info!(
+            event = "director_spawn",
+            enemy = ?enemy,
+            "🎬 Director spawned enemy {enemy:?} at {spawn}"
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use lightyear::connection::server::Started;
+use lightyear::prelude::*;
+use shared::enemy::{ENEMY_CHASE_SPEED, ENEMY_WALK_SPEED, Enemy, EnemyState};
+use shared::input::{Stance, steer_character};
+use shared::protocol::PlayerId;
+use shared::scene::PlayerPhysicsBundle;
+
+use crate::director::PlayerDamaged;
+use crate::gameplay::ResetLevel;
+use crate::navigation::{NavAgent, NavGrid};
+use crate::noise::{Hearing, NoiseEvent, NoiseListener};
+use crate::relevancy::InRoom;
+
+/// Where enemies are spawned when the server starts: by the middle of the north and south walls,
+/// between the sides the teams spawn on and out of the closet.
+const ENEMY_SPAWNS: [Vec3; 2] = [Vec3::new(0.0, 2.0, -7.0), Vec3::new(0.0, 2.0, 7.0)];
+/// How far an awake enemy sees, in meters.
+const SIGHT_RANGE: f32 = 15.0;
+/// How far a sleeping enemy notices a player right in front of it.
+const SLEEPING_SIGHT_RANGE: f32 = 3.0;
+/// Cosine of half the field of view, 120 degrees wide.
+const SIGHT_COS_HALF_ANGLE: f32 = 0.5;
+/// Height of the eyes above the capsule center.
+pub(crate) const EYE_HEIGHT: f32 = 0.5;
+/// Weakest noise that wakes a sleeping enemy up.
+const WAKE_LOUDNESS: f32 = 0.1;
+/// Gap between the capsules of an enemy and its target within which it hits.
+const ATTACK_RANGE: f32 = 0.5;
+const ATTACK_COOLDOWN: f32 = 1.0;
+const ATTACK_DAMAGE: f32 = 10.0;
+/// Distance at which an enemy considers it reached its destination.
+const ARRIVAL_DISTANCE: f32 = 1.0;
+/// Seconds spent searching before falling asleep again.
+const SEARCH_DURATION: f32 = 8.0;
+/// Radius of the area searched around where the target was lost.
+const SEARCH_RADIUS: f32 = 4.0;
+
+/// Server-authoritative enemies. Each fixed tick they hear the [`NoiseEvent`]s of the tick, look
+/// for players in their field of view and then move through their [`EnemyState`]s with the shared
+/// character controller, following paths on the [`NavGrid`].
+pub struct EnemyPlugin;
+
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(spawn_enemies_on_server_start);
+        app.add_observer(reset_enemies);
+        app.add_systems(
+            FixedUpdate,
+            (hear_noises, see_players, move_enemies).chain(),
+        );
+    }
+}
+
+/// Server-only memory of an enemy.
+#[derive(Component, Default)]
+struct EnemyBrain {
+    /// Player in sight, if any.
+    target: Option<Entity>,
+    /// Where the enemy is heading: a noise, the target or where it was last seen.
+    destination: Vec3,
+    /// Center of the area being searched.
+    search_origin: Vec3,
+    /// Seconds left searching.
+    search_time: f32,
+    /// Seconds before the next attack.
+    attack_cooldown: f32,
+}
+
+type EnemySenses<'a> = (
+    Entity,
+    &'a Position,
+    &'a Rotation,
+    &'a mut EnemyState,
+    &'a mut EnemyBrain,
+);
+
+type EnemyBody<'a> = (
+    Entity,
+    &'a Position,
+    &'a mut Rotation,
+    &'a mut LinearVelocity,
+    &'a mut EnemyState,
+    &'a mut EnemyBrain,
+    &'a mut NavAgent,
+);
+
+fn spawn_enemies_on_server_start(_trigger: Trigger<OnAdd, Started>, mut commands: Commands) {
+    for position in ENEMY_SPAWNS {
+        spawn_enemy(&mut commands, position, None);
+    }
+}
+
+/// Enemies of the previous level go away with it.
+fn reset_enemies(
+    _trigger: Trigger<ResetLevel>,
+    enemies: Query<Entity, With<Enemy>>,
+    mut commands: Commands,
+) {
+    for enemy in enemies.iter() {
+        commands.entity(enemy).despawn();
+    }
+    for position in ENEMY_SPAWNS {
+        spawn_enemy(&mut commands, position, None);
+    }
+}
+
+/// Spawns an enemy at `position`, replicated to every client in range. It sleeps, or walks to
+/// `investigate` when given.
+pub fn spawn_enemy(commands: &mut Commands, position: Vec3, investigate: Option<Vec3>) -> Entity {
+    let (state, destination) = match investigate {
+        Some(destination) => (EnemyState::Investigating, destination),
+        None => (EnemyState::Sleeping, position),
+    };
+    let enemy = commands
+        .spawn((
+            // Replicated
+            Name::new("Enemy"),
+            Enemy,
+            state,
+            Position(position),
+            Rotation::default(),
+            LinearVelocity::default(),
+            Replicate::to_clients(NetworkTarget::All),
+            InterpolationTarget::to_clients(NetworkTarget::All),
+            InRoom::default(),
+            NetworkVisibility::default(),
+            // Should not be replicated
+            EnemyBrain {
+                destination,
+                ..default()
+            },
+            NoiseListener::default(),
+            NavAgent::default(),
+            PlayerPhysicsBundle::default(),
+        ))
+        .id();
+    info!(event = "enemy_spawned", enemy = ?enemy, "👹 Spawned enemy {enemy:?} at {position}");
+    enemy
+}
+
+/// How far an enemy sees, sleeping ones only notice what is right in front of them.
+pub(crate) fn sight_range(state: EnemyState) -> f32 {
+    if state == EnemyState::Sleeping {
+        SLEEPING_SIGHT_RANGE
+    } else {
+        SIGHT_RANGE
+    }
+}
+
+fn set_state(enemy: Entity, state: &mut Mut<EnemyState>, next: EnemyState) {
+    if **state != next {
+        debug!("👹 Enemy {enemy:?}: {:?} -> {next:?}", **state);
+        **state = next;
+    }
+}
+
+fn hear_noises(
+    mut noises: EventReader<NoiseEvent>,
+    hearing: Hearing,
+    mut enemies: Query<(&mut EnemyState, &mut EnemyBrain)>,
+) {
+    for noise in noises.read() {
+        for heard in hearing.heard_by(noise) {
+            let Ok((mut state, mut brain)) = enemies.get_mut(heard.listener) else {
+                continue;
+            };
+            match *state {
+                EnemyState::Chasing | EnemyState::Attacking => {}
+                EnemyState::Sleeping if heard.loudness < WAKE_LOUDNESS => {}
+                _ => {
+                    set_state(heard.listener, &mut state, EnemyState::Investigating);
+                    brain.destination = noise.position;
+                }
+            }
+        }
+    }
+}
+
+fn see_players(
+    spatial_query: SpatialQuery,
+    mut enemies: Query<EnemySenses>,
+    players: Query<(Entity, &Position), With<PlayerId>>,
+) {
+    for (enemy, position, rotation, mut state, mut brain) in enemies.iter_mut() {
+        let eye = position.0 + Vec3::Y * EYE_HEIGHT;
+        let forward = rotation.0 * Vec3::NEG_Z;
+        let range = sight_range(*state);
+        let filter = SpatialQueryFilter::from_excluded_entities([enemy]);
+
+        let seen = players
+            .iter()
+            .filter(|(player, player_position)| {
+                let to_player = player_position.0 - eye;
+                let distance = to_player.length();
+                let Ok(direction) = Dir3::new(to_player) else {
+                    return true;
+                };
+                distance <= range
+                    && forward.dot(*direction) >= SIGHT_COS_HALF_ANGLE
+                    && spatial_query
+                        .cast_ray(eye, direction, distance, true, &filter)
+                        .is_some_and(|hit| hit.entity == *player)
+            })
+            .min_by(|(_, a), (_, b)| {
+                a.0.distance_squared(eye)
+                    .total_cmp(&b.0.distance_squared(eye))
+            });
+
+        match seen {
+            Some((player, player_position)) => {
+                if brain.target.is_none() {
+                    info!(
+                        event = "enemy_alerted",
+                        enemy = ?enemy,
+                        player = ?player,
+                        "👹 Enemy {enemy:?} spotted player {player:?}"
+                    );
+                }
+                brain.target = Some(player);
+                brain.destination = player_position.0;
+                if *state != EnemyState::Attacking {
+                    set_state(enemy, &mut state, EnemyState::Chasing);
+                }
+            }
+            None => {
+                // Keeps chasing to where the target was last seen
+                brain.target = None;
+                if *state == EnemyState::Attacking {
+                    set_state(enemy, &mut state, EnemyState::Chasing);
+                }
+            }
+        }
+    }
+}
+
+fn move_enemies(
+    time: Res<Time>,
+    grid: Res<NavGrid>,
+    mut enemies: Query<EnemyBody>,
+    mut damage: EventWriter<PlayerDamaged>,
+) {
+    let delta_secs = time.delta_secs();
+    // Distance between capsule centers, which can't get closer than both capsules touching
+    let capsule_radius = Stance::Standing
+        .collider()
+        .aabb(Vec3::ZERO, Quat::IDENTITY)
+        .max
+        .x;
+    let reach = 2.0 * capsule_radius + ATTACK_RANGE;
+    for (enemy, position, mut rotation, mut velocity, mut state, mut brain, mut agent) in
+        enemies.iter_mut()
+    {
+        let to_destination = (brain.destination - position.0).with_y(0.0);
+        let arrived = to_destination.length() < ARRIVAL_DISTANCE;
+        brain.attack_cooldown = (brain.attack_cooldown - delta_secs).max(0.0);
+
+        match *state {
+            EnemyState::Sleeping => {
+                steer_character(&mut rotation, &mut velocity, Vec3::ZERO, 0.0);
+            }
+            EnemyState::Investigating | EnemyState::Chasing if arrived => {
+                if brain.target.is_some() && to_destination.length() < reach {
+                    set_state(enemy, &mut state, EnemyState::Attacking);
+                } else if brain.target.is_none() {
+                    brain.search_origin = position.0;
+                    brain.search_time = SEARCH_DURATION;
+                    set_state(enemy, &mut state, EnemyState::Searching);
+                }
+                steer_character(&mut rotation, &mut velocity, Vec3::ZERO, 0.0);
+            }
+            EnemyState::Investigating => {
+                let direction = agent.direction_to(&grid, position.0, brain.destination);
+                steer_character(&mut rotation, &mut velocity, direction, ENEMY_WALK_SPEED);
+            }
+            EnemyState::Chasing => {
+                if brain.target.is_some() && to_destination.length() < reach {
+                    set_state(enemy, &mut state, EnemyState::Attacking);
+                }
+                let direction = agent.direction_to(&grid, position.0, brain.destination);
+                steer_character(&mut rotation, &mut velocity, direction, ENEMY_CHASE_SPEED);
+            }
+            EnemyState::Attacking => {
+                if to_destination.length() > reach * 1.2 {
+                    set_state(enemy, &mut state, EnemyState::Chasing);
+                } else if let Some(player) = brain.target
+                    && brain.attack_cooldown <= 0.0
+                {
+                    brain.attack_cooldown = ATTACK_COOLDOWN;
+                    damage.write(PlayerDamaged {
+                        player,
+                        amount: ATTACK_DAMAGE,
+                    });
+                    info!(
+                        event = "enemy_attack",
+                        enemy = ?enemy,
+                        player = ?player,
+                        "👹 Enemy {enemy:?} attacks player {player:?}"
+                    );
+                }
+                // Faces its target without moving
+                steer_character(&mut rotation, &mut velocity, to_destination, 0.0);
+            }
+            EnemyState::Searching => {
+                brain.search_time -= delta_secs;
+                if brain.search_time <= 0.0 {
+                    set_state(enemy, &mut state, EnemyState::Sleeping);
+                } else if arrived {
+                    // Spirals around the search origin
+                    let angle = brain.search_time * 2.4;
+                    let radius = SEARCH_RADIUS * (brain.search_time / SEARCH_DURATION);
+                    brain.destination =
+                        brain.search_origin + Vec3::new(angle.cos(), 0.0, angle.sin()) * radius;
+                }
+                let direction = agent.direction_to(&grid, position.0, brain.destination);
+                steer_character(&mut rotation, &mut velocity, direction, ENEMY_WALK_SPEED);
+            }
+        }
+    }
+}
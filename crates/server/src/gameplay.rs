@@ -6,20 +6,73 @@ use lightyear::connection::server::Started;
 
 use lightyear::prelude::server::*;
 use lightyear::prelude::*;
-use shared::input::{PLAYER_CAPSULE_HEIGHT, PlayerAction, shared_player_movement};
-use shared::protocol::{PlayerColor, PlayerId};
+use shared::game_state::MatchState;
+use shared::input::{
+    PlayerAction, Stamina, Stance, apply_stance_collider, is_grounded, shared_player_movement,
+    update_player_stance,
+};
+use shared::interaction::{
+    Door, Interactable, InteractableKind, add_interactable_physics, apply_door_state,
+};
+use shared::protocol::{PlayerColor, PlayerId, TEAM_COUNT, Team};
 use shared::scene::*;
 
+use crate::access::{AccessList, ban_address, unix_now};
+use crate::logging::client_span;
+use crate::network::KickClient;
+use crate::relevancy::{InRoom, LevelLayout, LevelRoom, RoomId};
+use crate::shutdown::ShutdownState;
+use crate::spawn::SpawnPicker;
+
 pub struct ServerGameplayPlugin;
 
+/// Seed of the current level. Changing it through [`ResetLevel`] rebuilds the level, with the same
+/// layout for every seed for now, the seed only drives the [`crate::director`].
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LevelSeed(pub u64);
+
+/// Despawns the current level geometry and spawns it again, then restarts the director with the
+/// given seed.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ResetLevel {
+    pub seed: u64,
+}
+
+/// Puts every connected player back on its spawn position with no velocity.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct RestartMatch;
+
+/// A player pulled the trigger this tick.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ShotFired {
+    pub shooter: Entity,
+}
+
+/// Whether the trigger was held on the previous tick. A shot is fired once per press.
+#[derive(Component, Default)]
+pub struct TriggerHeld(bool);
+
 impl Plugin for ServerGameplayPlugin {
     fn build(&self, app: &mut App) {
+        app.init_resource::<LevelSeed>();
+        app.add_event::<ShotFired>();
         app.add_observer(setup_scene_on_server_start);
+        app.add_observer(reset_level);
+        app.add_observer(restart_match);
         app.add_observer(handle_connected);
-        app.add_systems(FixedUpdate, server_player_movement);
+        app.add_systems(
+            FixedUpdate,
+            (server_player_movement, apply_stance_collider).chain(),
+        );
+        app.add_systems(FixedUpdate, fire_weapons);
         app.add_systems(FixedUpdate, debug_player_position);
+        app.add_systems(
+            FixedPostUpdate,
+            apply_door_state.before(PhysicsSet::Prepare),
+        );
         app.add_observer(add_floor_physics);
         app.add_observer(add_wall_physics);
+        app.add_observer(add_interactable_physics);
     }
 }
 
@@ -40,20 +93,49 @@ fn debug_player_position(
 
 fn handle_connected(
     trigger: Trigger<OnAdd, Connected>,
-    query: Query<&RemoteId, With<ClientOf>>,
+    query: Query<(&RemoteId, Option<&PeerAddr>), With<ClientOf>>,
+    access_list: Res<AccessList>,
+    shutdown: Option<Res<ShutdownState>>,
+    timeline: Single<&LocalTimeline, With<Server>>,
+    mut spawn_picker: SpawnPicker,
     mut commands: Commands,
 ) {
-    let Ok(client_id) = query.get(trigger.target()) else {
+    let Ok((client_id, peer_addr)) = query.get(trigger.target()) else {
         return;
     };
+    let _span = client_span(client_id.to_bits(), trigger.target(), timeline.tick()).entered();
     let peer_id = client_id.0;
-    info!("Client connected with client-id {client_id:?}. Spawning player entity.");
+    if shutdown.is_some() {
+        info!(
+            event = "client_rejected",
+            reason = "server is shutting down",
+            "⛔ Rejecting client {client_id:?}: server is shutting down"
+        );
+        commands.trigger(KickClient {
+            client: trigger.target(),
+            reason: "Server is shutting down".to_string(),
+        });
+        return;
+    }
+    let address = peer_addr.and_then(|peer_addr| ban_address(peer_addr.0));
+    if let Err(denied) = access_list.check(client_id.to_bits(), address, unix_now()) {
+        info!(
+            event = "client_rejected",
+            reason = %denied,
+            "⛔ Rejecting client {client_id:?}: {denied}"
+        );
+        commands.trigger(KickClient {
+            client: trigger.target(),
+            reason: format!("Access denied: {denied}"),
+        });
+        return;
+    }
+    info!(
+        event = "client_connected",
+        "Client connected with client-id {client_id:?}. Spawning player entity."
+    );
 
     let color = color_from_id(client_id.to_bits());
-    let angle: f32 = client_id.to_bits() as f32 * 6.28 / 4.0; // Distribute around circle
-    let x = 5.0 * angle.cos();
-    let z = 5.0 * angle.sin();
-    let y = PLAYER_CAPSULE_HEIGHT + 10.0;
 
     info!(
         "🎯 Setting up prediction target for client_id: {:?} (peer_id: {})",
@@ -65,15 +147,19 @@ fn handle_connected(
         client_id.to_bits()
     );
 
+    let team = Team((client_id.to_bits() % TEAM_COUNT as u64) as u8);
+    let spawn_position = spawn_picker.pick(team, None);
     let player = commands
         .spawn((
             // Replicated
             Name::new(format!("Player_{}", client_id.to_bits())),
             PlayerId(peer_id),
             LinearVelocity::default(),
-            Position(Vec3::new(x, y, z)),
+            Position(spawn_position),
             Rotation::default(),
+            (Stance::default(), Stamina::default()),
             PlayerColor(color),
+            team,
             // Lightyear config
             ControlledBy {
                 owner: trigger.target(),
@@ -82,32 +168,71 @@ fn handle_connected(
             Replicate::to_clients(NetworkTarget::All),
             PredictionTarget::to_clients(NetworkTarget::Single(peer_id)),
             InterpolationTarget::to_clients(NetworkTarget::AllExceptSingle(peer_id)),
+            // Only its owner needs it, to predict its movement
+            ComponentReplicationOverrides::<Stamina>::default()
+                .disable_all()
+                .enable_for(trigger.target()),
+            (
+                InRoom::default(),
+                NetworkVisibility::default(),
+            ),
             // Should not be replicated
-            PlayerPhysicsBundle::default(),
+            (PlayerPhysicsBundle::default(), TriggerHeld::default()),
         ))
         .id();
 
-    info!("Created player entity {player:?} for client {client_id:?}");
+    info!(
+        event = "player_spawned",
+        player = ?player,
+        "Created player entity {player:?} for client {client_id:?}"
+    );
     info!(
         "🔍 ControlledBy owner set to client entity: {:?}",
         trigger.target()
     );
 }
 
+/// Compares the trigger with the previous tick rather than using `just_pressed`, which follows
+/// frames and can be seen on several ticks or none.
+pub(crate) fn fire_weapons(
+    mut players: Query<(Entity, &ActionState<PlayerAction>, &mut TriggerHeld)>,
+    mut shots: EventWriter<ShotFired>,
+) {
+    for (shooter, action_state, mut held) in players.iter_mut() {
+        let pressed = action_state.pressed(&PlayerAction::Shoot);
+        if pressed && !held.0 {
+            shots.write(ShotFired { shooter });
+        }
+        held.0 = pressed;
+    }
+}
+
 pub fn server_player_movement(
     mut player_query: Query<
         (
             Entity,
             &mut Rotation,
             &mut LinearVelocity,
+            &mut Stance,
+            &mut Position,
+            &mut Stamina,
             &ActionState<PlayerAction>,
         ),
         // Based on lightyear examples - avoid applying movement to predicted/confirmed entities
         // to prevent conflicts in host-server mode
         (With<PlayerId>, Without<Predicted>, Without<Confirmed>),
     >,
+    spatial_query: Res<SpatialQueryPipeline>,
+    time: Res<Time>,
+    match_state: Option<Single<&MatchState>>,
 ) {
-    for (entity, mut rotation, mut velocity, action_state) in player_query.iter_mut() {
+    // Locked players keep falling and catching their breath, as if nothing was pressed
+    let neutral = ActionState::<PlayerAction>::default();
+    let allows_input = MatchState::allows_input(match_state.as_deref().copied());
+    for (entity, mut rotation, mut velocity, stance, position, stamina, action_state) in
+        player_query.iter_mut()
+    {
+        let action_state = if allows_input { action_state } else { &neutral };
         let axis_pair = action_state.axis_pair(&PlayerAction::Move);
         if axis_pair != Vec2::ZERO || !action_state.get_pressed().is_empty() {
             debug!(
@@ -118,12 +243,94 @@ pub fn server_player_movement(
             );
         }
 
-        shared_player_movement(action_state, &mut rotation, &mut velocity);
+        let grounded = is_grounded(entity, position.0, *stance, &spatial_query);
+        let stance = update_player_stance(
+            action_state,
+            entity,
+            stance,
+            position,
+            &stamina,
+            &spatial_query,
+        );
+        shared_player_movement(
+            action_state,
+            stance,
+            grounded,
+            stamina,
+            &mut rotation,
+            &mut velocity,
+            time.delta_secs(),
+        );
     }
 }
 
-fn setup_scene_on_server_start(_trigger: Trigger<OnAdd, Started>, mut commands: Commands) {
+fn setup_scene_on_server_start(
+    _trigger: Trigger<OnAdd, Started>,
+    seed: Res<LevelSeed>,
+    mut commands: Commands,
+) {
     info!("Setting up scene on server (after server started)");
+    spawn_level(&mut commands, seed.0);
+}
+
+type LevelEntityFilter = Or<(With<FloorMarker>, With<WallMarker>, With<SpawnPoint>)>;
+
+fn reset_level(
+    trigger: Trigger<ResetLevel>,
+    level_query: Query<Entity, LevelEntityFilter>,
+    mut seed: ResMut<LevelSeed>,
+    mut commands: Commands,
+) {
+    info!("🔄 Resetting level with seed {}", trigger.seed);
+    for entity in level_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    seed.0 = trigger.seed;
+    spawn_level(&mut commands, seed.0);
+}
+
+fn restart_match(
+    _trigger: Trigger<RestartMatch>,
+    mut player_query: Query<
+        (
+            Entity,
+            &Team,
+            &mut Position,
+            &mut LinearVelocity,
+            &mut Rotation,
+        ),
+        With<PlayerId>,
+    >,
+    mut spawn_picker: SpawnPicker,
+) {
+    for (player, team, mut position, mut velocity, mut rotation) in player_query.iter_mut() {
+        position.0 = spawn_picker.pick(*team, Some(player));
+        velocity.0 = Vec3::ZERO;
+        *rotation = Rotation::default();
+    }
+    info!("🔁 Match restarted");
+}
+
+fn spawn_level(commands: &mut Commands, seed: u64) {
+    debug!("Spawning level for seed {seed}");
+    // One room with a closet in its south west corner, generated levels will have more
+    let (main_room, closet) = (RoomId(0), RoomId(1));
+    commands.insert_resource(LevelLayout {
+        rooms: vec![
+            LevelRoom {
+                id: main_room,
+                bounds: Rect::from_center_size(Vec2::ZERO, Vec2::splat(ROOM_SIZE)),
+                adjacent: vec![closet],
+            },
+            LevelRoom {
+                id: closet,
+                bounds: Rect::new(-ROOM_SIZE / 2.0, -ROOM_SIZE / 2.0, -6.5, -6.5),
+                adjacent: vec![main_room],
+            },
+        ],
+    });
+
+    // Level geometry goes to every client, it is never hidden by relevancy
 
     commands.spawn((
         Name::new("Floor"),
@@ -131,6 +338,8 @@ fn setup_scene_on_server_start(_trigger: Trigger<OnAdd, Started>, mut commands:
         Position(Vec3::new(0.0, -FLOOR_THICKNESS / 2.0, 0.0)),
         Rotation::default(),
         Replicate::to_clients(NetworkTarget::All),
+        ComponentReplicationOverrides::<Position>::default().replicate_once_all(),
+        ComponentReplicationOverrides::<Rotation>::default().replicate_once_all(),
     ));
 
     let wall_positions = [
@@ -175,8 +384,69 @@ fn setup_scene_on_server_start(_trigger: Trigger<OnAdd, Started>, mut commands:
             Position(position),
             Rotation::default(),
             Replicate::to_clients(NetworkTarget::All),
+            ComponentReplicationOverrides::<Position>::default().replicate_once_all(),
+            ComponentReplicationOverrides::<Rotation>::default().replicate_once_all(),
+        ));
+    }
+
+    // A closet in the south west corner, closed by a door, holds the extraction
+    let closet_walls = [
+        (
+            Vec3::new(-6.5, WALL_HEIGHT / 2.0, -8.25),
+            Vec3::new(WALL_THICKNESS, WALL_HEIGHT, 3.5),
+            "Closet Wall East",
+        ),
+        (
+            Vec3::new(-9.5, WALL_HEIGHT / 2.0, -6.5),
+            Vec3::new(1.0, WALL_HEIGHT, WALL_THICKNESS),
+            "Closet Wall North West",
+        ),
+        (
+            Vec3::new(-7.0, WALL_HEIGHT / 2.0, -6.5),
+            Vec3::new(1.0, WALL_HEIGHT, WALL_THICKNESS),
+            "Closet Wall North East",
+        ),
+    ];
+    for (position, size, name) in closet_walls {
+        commands.spawn((
+            Name::new(name),
+            WallMarker,
+            WallSize(size),
+            Position(position),
+            Rotation::default(),
+            Replicate::to_clients(NetworkTarget::All),
+            ComponentReplicationOverrides::<Position>::default().replicate_once_all(),
+            ComponentReplicationOverrides::<Rotation>::default().replicate_once_all(),
         ));
     }
+    commands.spawn((
+        Name::new("Closet Door"),
+        WallMarker,
+        WallSize(Vec3::new(1.5, WALL_HEIGHT, WALL_THICKNESS)),
+        Door::default(),
+        Interactable {
+            kind: InteractableKind::Door,
+            hold_secs: 0.0,
+            locked: false,
+        },
+        Position(Vec3::new(-8.25, WALL_HEIGHT / 2.0, -6.5)),
+        Rotation::default(),
+        Replicate::to_clients(NetworkTarget::All),
+        ComponentReplicationOverrides::<Position>::default().replicate_once_all(),
+        ComponentReplicationOverrides::<Rotation>::default().replicate_once_all(),
+    ));
+
+    // Each team starts on its own side, the middle of the room is left to enemies
+    for team in 0..TEAM_COUNT {
+        let x = if team == 0 { -6.0 } else { 6.0 };
+        for z in [-5.25, -1.75, 1.75, 5.25] {
+            commands.spawn((
+                Name::new(format!("Spawn Point {team}")),
+                SpawnPoint { team: Some(team) },
+                Position(Vec3::new(x, 0.0, z)),
+            ));
+        }
+    }
 
     info!("Scene setup complete");
 }
@@ -0,0 +1,59 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use lightyear::prelude::server::ClientOf;
+use lightyear::prelude::*;
+use shared::enemy::Enemy;
+use shared::input::Stance;
+use shared::protocol::{Hit, HitChannel, PlayerId};
+
+use crate::director::PlayerDamaged;
+use crate::gameplay::ShotFired;
+
+/// Furthest a shot can hit something, in meters.
+pub const WEAPON_RANGE: f32 = 50.0;
+
+/// What a shot can hit.
+type HitTargetFilter = Or<(With<Enemy>, With<PlayerId>)>;
+
+/// Tells each client about the hits of its own player on [`HitChannel`], which goes before
+/// replication updates when the bandwidth is capped.
+pub struct HitPlugin;
+
+impl Plugin for HitPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, send_hits);
+    }
+}
+
+fn send_hits(
+    mut shots: EventReader<ShotFired>,
+    mut damaged: EventReader<PlayerDamaged>,
+    players: Query<(&Position, &Rotation, &Stance, &ControlledBy), With<PlayerId>>,
+    targets: Query<(), HitTargetFilter>,
+    spatial_query: SpatialQuery,
+    mut senders: Query<&mut MessageSender<Hit>, With<ClientOf>>,
+) {
+    let dealt = shots.read().filter_map(|shot| {
+        let (position, rotation, stance, owner) = players.get(shot.shooter).ok()?;
+        // Players only replicate their yaw, shots fly level
+        let hit = spatial_query.cast_ray(
+            position.0 + Vec3::Y * stance.eye_height(),
+            Dir3::new(rotation.0 * Vec3::NEG_Z).ok()?,
+            WEAPON_RANGE,
+            true,
+            &SpatialQueryFilter::from_excluded_entities([shot.shooter]),
+        )?;
+        targets
+            .contains(hit.entity)
+            .then_some((owner.owner, Hit::Dealt))
+    });
+    let taken = damaged.read().filter_map(|damage| {
+        let (_, _, _, owner) = players.get(damage.player).ok()?;
+        Some((owner.owner, Hit::Taken))
+    });
+    for (client, hit) in dealt.chain(taken) {
+        if let Ok(mut sender) = senders.get_mut(client) {
+            sender.send::<HitChannel>(hit);
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+use lightyear::prelude::server::ClientOf;
+use lightyear::prelude::*;
+use shared::enemy::Enemy;
+use shared::game_state::MatchState;
+use shared::input::{PlayerAction, Stance};
+use shared::interaction::{
+    Door, INTERACT_MIN_FACING, INTERACT_RANGE, InteractTarget, Interactable, InteractableKind,
+};
+use shared::protocol::PlayerId;
+
+use crate::noise::{NoiseEvent, NoiseSource};
+
+/// Loudness of a door opening or closing.
+const DOOR_LOUDNESS: f32 = 0.5;
+
+/// Server side of interactions. Clients tell which [`Interactable`] they look at, the server
+/// checks the player can reach it, counts how long [`PlayerAction::Interact`] is held and
+/// triggers [`Interacted`] once the interactable's hold time is reached. Doors are handled here,
+/// terminals and pickups by the objectives they belong to.
+pub struct InteractionPlugin;
+
+/// Triggered when a player finishes using an interactable.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Interacted {
+    pub player: Entity,
+    pub target: Entity,
+    pub kind: InteractableKind,
+}
+
+/// What a player is using and for how long it has held the action.
+#[derive(Component, Debug, Default)]
+struct InteractionHold {
+    target: Option<Entity>,
+    held: f32,
+    /// Set once the interaction triggered, until the action is released.
+    done: bool,
+}
+
+type InteractingPlayer = (
+    Entity,
+    &'static Position,
+    &'static Rotation,
+    &'static Stance,
+    &'static ActionState<PlayerAction>,
+    &'static mut InteractionHold,
+);
+
+/// Characters that keep a door from closing on them.
+type DoorBlockerFilter = Or<(With<PlayerId>, With<Enemy>)>;
+
+impl Plugin for InteractionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(add_interaction_hold);
+        app.add_observer(toggle_doors);
+        app.add_systems(Update, receive_targets);
+        app.add_systems(FixedUpdate, hold_interactions);
+    }
+}
+
+fn add_interaction_hold(trigger: Trigger<OnAdd, PlayerId>, mut commands: Commands) {
+    commands
+        .entity(trigger.target())
+        .insert(InteractionHold::default());
+}
+
+fn receive_targets(
+    mut clients: Query<(Entity, &mut MessageReceiver<InteractTarget>), With<ClientOf>>,
+    mut players: Query<(&ControlledBy, &mut InteractionHold), With<PlayerId>>,
+) {
+    for (client, mut receiver) in clients.iter_mut() {
+        for message in receiver.receive() {
+            let Some((_, mut hold)) = players
+                .iter_mut()
+                .find(|(controlled_by, _)| controlled_by.owner == client)
+            else {
+                continue;
+            };
+            if hold.target != message.target {
+                *hold = InteractionHold {
+                    target: message.target,
+                    ..default()
+                };
+            }
+        }
+    }
+}
+
+/// Whether a player with its eyes at `eye`, facing `forward`, can use `target`: it has to be in
+/// range, in front of the player and nothing else can be in the way. Open doors have no collider
+/// to hit, the ray only has to get to their center.
+pub fn can_reach(
+    eye: Vec3,
+    forward: Vec3,
+    target: Entity,
+    aabb: &ColliderAabb,
+    spatial_query: &SpatialQuery,
+    filter: &SpatialQueryFilter,
+) -> bool {
+    let closest = eye.clamp(aabb.min, aabb.max);
+    if eye.distance(closest) > INTERACT_RANGE {
+        return false;
+    }
+    // Players only replicate their yaw, looking up or down is not checked
+    let to_target = aabb.center() - eye;
+    let facing = to_target.xz().normalize_or_zero();
+    if facing != Vec2::ZERO && forward.xz().normalize_or_zero().dot(facing) < INTERACT_MIN_FACING {
+        return false;
+    }
+    let Ok(direction) = Dir3::new(to_target) else {
+        return true;
+    };
+    spatial_query
+        .cast_ray(eye, direction, to_target.length(), true, filter)
+        .is_none_or(|hit| hit.entity == target)
+}
+
+fn hold_interactions(
+    time: Res<Time>,
+    mut players: Query<InteractingPlayer, With<PlayerId>>,
+    interactables: Query<(&Interactable, &ColliderAabb)>,
+    spatial_query: SpatialQuery,
+    match_state: Option<Single<&MatchState>>,
+    mut commands: Commands,
+) {
+    let allows_input = MatchState::allows_input(match_state.as_deref().copied());
+    for (player, position, rotation, stance, action_state, mut hold) in players.iter_mut() {
+        let Some(target) = hold.target else {
+            continue;
+        };
+        let eye = position.0 + Vec3::Y * stance.eye_height();
+        let filter = SpatialQueryFilter::from_excluded_entities([player]);
+        let usable = interactables
+            .get(target)
+            .ok()
+            .filter(|(interactable, aabb)| {
+                !interactable.locked
+                    && can_reach(
+                        eye,
+                        rotation.0 * Vec3::NEG_Z,
+                        target,
+                        aabb,
+                        &spatial_query,
+                        &filter,
+                    )
+            });
+
+        match usable {
+            Some((interactable, _))
+                if allows_input && action_state.pressed(&PlayerAction::Interact) =>
+            {
+                hold.held += time.delta_secs();
+                if !hold.done && hold.held >= interactable.hold_secs {
+                    hold.done = true;
+                    info!(
+                        event = "interacted",
+                        player = ?player,
+                        target = ?target,
+                        kind = ?interactable.kind,
+                        "🖐️ Player {player:?} used {:?} {target:?}",
+                        interactable.kind
+                    );
+                    commands.trigger(Interacted {
+                        player,
+                        target,
+                        kind: interactable.kind,
+                    });
+                }
+            }
+            _ => {
+                hold.held = 0.0;
+                hold.done = false;
+            }
+        }
+    }
+}
+
+/// Doors open and close when used, unless someone stands in the doorway.
+fn toggle_doors(
+    trigger: Trigger<Interacted>,
+    mut doors: Query<(&mut Door, &Position, &Rotation, &Collider)>,
+    blockers: Query<(), DoorBlockerFilter>,
+    spatial_query: SpatialQuery,
+    mut noises: EventWriter<NoiseEvent>,
+) {
+    let Ok((mut door, position, rotation, collider)) = doors.get_mut(trigger.target) else {
+        return;
+    };
+    if door.open
+        && spatial_query
+            .shape_intersections(
+                collider,
+                position.0,
+                rotation.0,
+                &SpatialQueryFilter::default(),
+            )
+            .into_iter()
+            .any(|entity| blockers.contains(entity))
+    {
+        return;
+    }
+
+    door.open = !door.open;
+    info!(
+        event = "door_toggled",
+        door = ?trigger.target,
+        open = door.open,
+        "🚪 Door {:?} {}",
+        trigger.target,
+        if door.open { "opened" } else { "closed" }
+    );
+    noises.write(NoiseEvent {
+        position: position.0,
+        loudness: DOOR_LOUDNESS,
+        source: NoiseSource::Door,
+        emitter: trigger.target,
+    });
+}
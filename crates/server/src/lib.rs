@@ -1,4 +1,23 @@
+pub mod access;
+mod admin;
 pub mod app;
-mod gameplay;
+mod chat;
+pub mod director;
+pub mod enemy;
+pub mod gameplay;
+mod hits;
+pub mod interaction;
+mod logging;
+pub mod match_state;
+mod metrics;
+pub mod navigation;
 mod network;
+pub mod noise;
+pub mod objective;
+pub mod priority;
+mod relevancy;
 mod render;
+pub mod shutdown;
+mod sound;
+pub mod spawn;
+mod voice;
@@ -0,0 +1,120 @@
+use bevy::log::tracing::field::{Field, Visit};
+use bevy::log::tracing::{self, Span};
+use bevy::log::{DEFAULT_FILTER, Level};
+use bevy::prelude::*;
+use lightyear::prelude::*;
+use serde_json::{Map, Value};
+use tracing_subscriber::fmt::format::{JsonFields, Writer};
+use tracing_subscriber::fmt::time::{FormatTime, SystemTime};
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, fmt};
+
+/// Installs a subscriber printing one JSON object per line on stderr, used instead of the
+/// `LogPlugin` output. `RUST_LOG` is honored like with the default logger.
+///
+/// Event fields such as `event` and the fields of the enclosing spans (`client_id`, `entity`,
+/// `tick`) all sit at the top level.
+pub fn init_json_logging() {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(format!("{},{DEFAULT_FILTER}", Level::INFO)));
+    let json = fmt::layer()
+        .fmt_fields(JsonFields::new())
+        .event_format(FlatJsonFormat)
+        .with_writer(std::io::stderr);
+
+    if let Err(e) = tracing_subscriber::registry()
+        .with(filter)
+        .with(json)
+        .try_init()
+    {
+        eprintln!("Failed to install the JSON logger: {e}");
+    }
+}
+
+/// Span wrapping everything logged about one client, so logs can be filtered by player.
+pub fn client_span(client_id: u64, entity: Entity, tick: Tick) -> Span {
+    info_span!("client", client_id, entity = ?entity, tick = tick.0)
+}
+
+/// Writes an event and the fields of its spans as a single flat JSON object. The built-in JSON
+/// format nests span fields under `span`.
+struct FlatJsonFormat;
+
+impl<S, N> FormatEvent<S, N> for FlatJsonFormat
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &tracing::Event<'_>,
+    ) -> std::fmt::Result {
+        let mut timestamp = String::new();
+        SystemTime.format_time(&mut Writer::new(&mut timestamp))?;
+        let metadata = event.metadata();
+        let mut object = Map::new();
+        object.insert("timestamp".into(), timestamp.into());
+        object.insert("level".into(), metadata.level().as_str().into());
+        object.insert("target".into(), metadata.target().into());
+
+        // Inner spans override the fields of outer ones, and the event overrides them all
+        for span in ctx
+            .event_scope()
+            .into_iter()
+            .flat_map(|scope| scope.from_root())
+        {
+            let extensions = span.extensions();
+            let fields = extensions
+                .get::<FormattedFields<N>>()
+                .and_then(|fields| serde_json::from_str::<Map<String, Value>>(fields).ok());
+            if let Some(fields) = fields {
+                object.extend(fields);
+            }
+        }
+        event.record(&mut JsonVisitor(&mut object));
+
+        writeln!(writer, "{}", Value::Object(object))
+    }
+}
+
+/// Adds the fields of an event to a JSON object, skipping the `log.*` metadata of events coming
+/// from the `log` crate.
+struct JsonVisitor<'a>(&'a mut Map<String, Value>);
+
+impl JsonVisitor<'_> {
+    fn insert(&mut self, field: &Field, value: impl Into<Value>) {
+        if !field.name().starts_with("log.") {
+            self.0.insert(field.name().to_string(), value.into());
+        }
+    }
+}
+
+impl Visit for JsonVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, value);
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value);
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value);
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value);
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, value);
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.insert(field, format!("{value:?}"));
+    }
+}
@@ -0,0 +1,190 @@
+use bevy::prelude::*;
+use lightyear::connection::server::Started;
+use lightyear::prelude::*;
+use shared::game_state::{MatchPhase, MatchState};
+use shared::protocol::PlayerId;
+
+use crate::gameplay::{LevelSeed, ResetLevel, RestartMatch};
+
+/// Seconds of warmup, counted once at least one player is in.
+const WARMUP_DURATION: f32 = 30.0;
+/// Seconds the team has to get to its extraction before the round is lost.
+const ROUND_DURATION: f32 = 600.0;
+/// Seconds the team holds out before being extracted.
+const EXTRACTION_DURATION: f32 = 60.0;
+/// Seconds the victory or defeat stays on screen.
+const RESULT_DURATION: f32 = 8.0;
+const INTERMISSION_DURATION: f32 = 10.0;
+
+/// Runs the rounds: warmup, in progress, extraction, victory or defeat, then an intermission
+/// during which the level is rebuilt with the next seed.
+pub struct MatchStatePlugin;
+
+/// Starts the extraction, the round is won once it runs out.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct StartExtraction;
+
+/// Ends the round right away.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct EndRound {
+    pub victory: bool,
+}
+
+/// Seconds left in the current phase, more precise than the replicated countdown.
+#[derive(Component, Debug)]
+struct PhaseTimer(f32);
+
+type MatchItem = (&'static mut MatchState, &'static mut PhaseTimer);
+
+impl Plugin for MatchStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelSeed>();
+        app.add_observer(spawn_match_on_server_start);
+        app.add_observer(start_extraction);
+        app.add_observer(end_round);
+        app.add_systems(FixedUpdate, advance_match);
+    }
+}
+
+/// Run condition for systems that only make sense while a round is played.
+pub(crate) fn round_in_progress(state: Option<Single<&MatchState>>) -> bool {
+    state
+        .is_some_and(|state| matches!(state.phase, MatchPhase::InProgress | MatchPhase::Extraction))
+}
+
+fn phase_duration(phase: MatchPhase) -> f32 {
+    match phase {
+        MatchPhase::Warmup => WARMUP_DURATION,
+        MatchPhase::InProgress => ROUND_DURATION,
+        MatchPhase::Extraction => EXTRACTION_DURATION,
+        MatchPhase::Victory | MatchPhase::Defeat => RESULT_DURATION,
+        MatchPhase::Intermission => INTERMISSION_DURATION,
+    }
+}
+
+fn spawn_match_on_server_start(_trigger: Trigger<OnAdd, Started>, mut commands: Commands) {
+    commands.spawn((
+        Name::new("Match"),
+        MatchState {
+            phase: MatchPhase::Warmup,
+            round: 1,
+            seconds_left: WARMUP_DURATION as u32,
+        },
+        PhaseTimer(WARMUP_DURATION),
+        Replicate::to_clients(NetworkTarget::All),
+    ));
+}
+
+fn advance_match(
+    time: Res<Time>,
+    mut match_query: Query<MatchItem>,
+    players: Query<(), With<PlayerId>>,
+    seed: Res<LevelSeed>,
+    mut commands: Commands,
+) {
+    let Ok((mut state, mut timer)) = match_query.single_mut() else {
+        return;
+    };
+
+    let empty = players.is_empty();
+    if empty && state.phase == MatchPhase::Warmup {
+        timer.0 = WARMUP_DURATION;
+    } else {
+        timer.0 -= time.delta_secs();
+    }
+
+    let next = match state.phase {
+        // Nobody left to finish the round
+        MatchPhase::InProgress | MatchPhase::Extraction if empty => MatchPhase::Intermission,
+        _ if timer.0 > 0.0 => {
+            let seconds_left = timer.0.ceil() as u32;
+            state.set_if_neq(MatchState {
+                seconds_left,
+                ..*state
+            });
+            return;
+        }
+        MatchPhase::Warmup => MatchPhase::InProgress,
+        MatchPhase::InProgress => MatchPhase::Defeat,
+        MatchPhase::Extraction => MatchPhase::Victory,
+        MatchPhase::Victory | MatchPhase::Defeat => MatchPhase::Intermission,
+        MatchPhase::Intermission => MatchPhase::Warmup,
+    };
+    enter_phase(&mut state, &mut timer, next, seed.0, &mut commands);
+}
+
+fn start_extraction(
+    _trigger: Trigger<StartExtraction>,
+    mut match_query: Query<MatchItem>,
+    seed: Res<LevelSeed>,
+    mut commands: Commands,
+) {
+    let Ok((mut state, mut timer)) = match_query.single_mut() else {
+        return;
+    };
+    if state.phase == MatchPhase::InProgress {
+        enter_phase(
+            &mut state,
+            &mut timer,
+            MatchPhase::Extraction,
+            seed.0,
+            &mut commands,
+        );
+    }
+}
+
+fn end_round(
+    trigger: Trigger<EndRound>,
+    mut match_query: Query<MatchItem>,
+    seed: Res<LevelSeed>,
+    mut commands: Commands,
+) {
+    let Ok((mut state, mut timer)) = match_query.single_mut() else {
+        return;
+    };
+    if matches!(state.phase, MatchPhase::InProgress | MatchPhase::Extraction) {
+        let result = if trigger.victory {
+            MatchPhase::Victory
+        } else {
+            MatchPhase::Defeat
+        };
+        enter_phase(&mut state, &mut timer, result, seed.0, &mut commands);
+    }
+}
+
+fn enter_phase(
+    state: &mut MatchState,
+    timer: &mut PhaseTimer,
+    phase: MatchPhase,
+    seed: u64,
+    commands: &mut Commands,
+) {
+    let round = if phase == MatchPhase::Warmup {
+        state.round + 1
+    } else {
+        state.round
+    };
+    match phase {
+        // Players start the round, and the warmup of the next one, on their spawn points
+        MatchPhase::Warmup | MatchPhase::InProgress => commands.trigger(RestartMatch),
+        MatchPhase::Intermission => commands.trigger(ResetLevel {
+            seed: seed.wrapping_add(1),
+        }),
+        _ => {}
+    }
+
+    let duration = phase_duration(phase);
+    timer.0 = duration;
+    *state = MatchState {
+        phase,
+        round,
+        seconds_left: duration.ceil() as u32,
+    };
+    info!(
+        event = "match_phase",
+        round,
+        phase = ?phase,
+        "🏁 Round {round}: {}",
+        phase.label()
+    );
+}
@@ -0,0 +1,290 @@
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bevy::time::common_conditions::on_timer;
+use lightyear::prelude::server::*;
+use lightyear::prelude::*;
+use shared::network_stats::NetworkStats;
+
+use crate::app::ServerConfig;
+
+/// Upper bounds of the tick duration histogram buckets, in seconds. 15.625ms is one 64 Hz tick.
+const TICK_BUCKETS: [f64; 8] = [
+    0.0005, 0.001, 0.002, 0.004, 0.008, 0.015625, 0.03125, 0.0625,
+];
+
+/// How often the page served on `/metrics` is rebuilt.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Reads one per-client value out of the link of a client.
+type ClientValue = fn(&Link, &NetworkStats) -> f64;
+
+type ConnectedClients = (With<ClientOf>, With<Connected>);
+
+pub struct MetricsPlugin;
+
+impl Plugin for MetricsPlugin {
+    fn build(&self, app: &mut App) {
+        let Some(addr) = app.world().resource::<ServerConfig>().metrics_addr else {
+            return;
+        };
+
+        let page = MetricsPage::default();
+        spawn_metrics_listener(addr, page.0.clone());
+
+        app.insert_resource(page);
+        app.init_resource::<TickMetrics>();
+        app.add_systems(FixedFirst, start_tick);
+        app.add_systems(FixedLast, finish_tick);
+        app.add_systems(Last, render_metrics.run_if(on_timer(REFRESH_INTERVAL)));
+    }
+}
+
+/// Latest rendering of the metrics, shared with the HTTP thread.
+#[derive(Resource, Default)]
+struct MetricsPage(Arc<Mutex<String>>);
+
+#[derive(Resource)]
+struct TickMetrics {
+    tick_started: Option<Instant>,
+    /// Non-cumulative count of ticks per bucket, the last slot holds the ticks above every bound.
+    buckets: [u64; TICK_BUCKETS.len() + 1],
+    duration_sum: f64,
+    ticks: u64,
+    /// Ticks and wall clock time since the last refresh, to see if the simulation keeps up.
+    window_ticks: u64,
+    window_started: Instant,
+    ticks_behind: u64,
+}
+
+impl Default for TickMetrics {
+    fn default() -> Self {
+        Self {
+            tick_started: None,
+            buckets: [0; TICK_BUCKETS.len() + 1],
+            duration_sum: 0.0,
+            ticks: 0,
+            window_ticks: 0,
+            window_started: Instant::now(),
+            ticks_behind: 0,
+        }
+    }
+}
+
+fn start_tick(mut metrics: ResMut<TickMetrics>) {
+    metrics.tick_started = Some(Instant::now());
+}
+
+fn finish_tick(mut metrics: ResMut<TickMetrics>) {
+    let Some(started) = metrics.tick_started.take() else {
+        return;
+    };
+    let duration = started.elapsed().as_secs_f64();
+    let bucket = TICK_BUCKETS
+        .iter()
+        .position(|bound| duration <= *bound)
+        .unwrap_or(TICK_BUCKETS.len());
+    metrics.buckets[bucket] += 1;
+    metrics.duration_sum += duration;
+    metrics.ticks += 1;
+    metrics.window_ticks += 1;
+}
+
+fn render_metrics(
+    page: Res<MetricsPage>,
+    mut metrics: ResMut<TickMetrics>,
+    fixed_time: Res<Time<Fixed>>,
+    clients: Query<(&RemoteId, &Link, &NetworkStats), ConnectedClients>,
+    all_stats: Query<&NetworkStats>,
+    replicated: Query<(), With<Replicate>>,
+) {
+    // Bevy drops fixed ticks when a frame takes too long, so compare with the wall clock
+    let expected_ticks =
+        metrics.window_started.elapsed().as_secs_f64() / fixed_time.timestep().as_secs_f64();
+    metrics.ticks_behind = (expected_ticks.floor() as u64).saturating_sub(metrics.window_ticks);
+    metrics.window_ticks = 0;
+    metrics.window_started = Instant::now();
+
+    let mut out = String::new();
+
+    header(
+        &mut out,
+        "yolo_tick_duration_seconds",
+        "histogram",
+        "Time spent running one fixed simulation tick.",
+    );
+    let mut cumulative = 0;
+    for (bound, count) in TICK_BUCKETS.iter().zip(metrics.buckets) {
+        cumulative += count;
+        let _ = writeln!(
+            out,
+            "yolo_tick_duration_seconds_bucket{{le=\"{bound}\"}} {cumulative}"
+        );
+    }
+    let _ = writeln!(
+        out,
+        "yolo_tick_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+        metrics.ticks
+    );
+    let _ = writeln!(
+        out,
+        "yolo_tick_duration_seconds_sum {}",
+        metrics.duration_sum
+    );
+    let _ = writeln!(out, "yolo_tick_duration_seconds_count {}", metrics.ticks);
+
+    gauge(
+        &mut out,
+        "yolo_ticks_behind",
+        "Fixed ticks the simulation fell behind the wall clock during the last second.",
+        metrics.ticks_behind,
+    );
+    gauge(
+        &mut out,
+        "yolo_connected_clients",
+        "Clients with an established connection.",
+        clients.iter().count(),
+    );
+    gauge(
+        &mut out,
+        "yolo_replicated_entities",
+        "Entities replicated to clients.",
+        replicated.iter().count(),
+    );
+    header(
+        &mut out,
+        "yolo_rollbacks_total",
+        "counter",
+        "Prediction rollbacks, always 0 on a dedicated server since it does not predict.",
+    );
+    let rollbacks: u64 = all_stats.iter().map(|stats| stats.rollbacks).sum();
+    let _ = writeln!(out, "yolo_rollbacks_total {rollbacks}");
+
+    let per_client: [(&str, &str, &str, ClientValue); 7] = [
+        (
+            "yolo_client_rtt_seconds",
+            "gauge",
+            "Round trip time to the client.",
+            |link, _| link.stats.rtt.as_secs_f64(),
+        ),
+        (
+            "yolo_client_jitter_seconds",
+            "gauge",
+            "Round trip time jitter of the client.",
+            |link, _| link.stats.jitter.as_secs_f64(),
+        ),
+        (
+            "yolo_client_packet_loss_ratio",
+            "gauge",
+            "Share of messages on acked channels lost on the way to the client.",
+            |_, stats| stats.packet_loss as f64,
+        ),
+        (
+            "yolo_client_sent_bytes_per_second",
+            "gauge",
+            "Bytes sent to the client during the last second.",
+            |_, stats| stats.bytes_sent_per_sec as f64,
+        ),
+        (
+            "yolo_client_received_bytes_per_second",
+            "gauge",
+            "Bytes received from the client during the last second.",
+            |_, stats| stats.bytes_received_per_sec as f64,
+        ),
+        (
+            "yolo_client_sent_bytes_total",
+            "counter",
+            "Bytes sent to the client since it connected.",
+            |_, stats| stats.bytes_sent as f64,
+        ),
+        (
+            "yolo_client_received_bytes_total",
+            "counter",
+            "Bytes received from the client since it connected.",
+            |_, stats| stats.bytes_received as f64,
+        ),
+    ];
+    for (name, kind, help, value) in per_client {
+        header(&mut out, name, kind, help);
+        for (remote_id, link, stats) in clients.iter() {
+            let _ = writeln!(
+                out,
+                "{name}{{client_id=\"{}\"}} {}",
+                remote_id.to_bits(),
+                value(link, stats)
+            );
+        }
+    }
+
+    if let Ok(mut page) = page.0.lock() {
+        *page = out;
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: impl std::fmt::Display) {
+    header(out, name, "gauge", help);
+    let _ = writeln!(out, "{name} {value}");
+}
+
+fn spawn_metrics_listener(addr: SocketAddr, page: Arc<Mutex<String>>) {
+    let listener = match TcpListener::bind(addr) {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("❌ Failed to bind metrics endpoint on {addr}: {e}");
+            return;
+        }
+    };
+    info!("📈 Metrics available on http://{addr}/metrics");
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            if let Err(e) = serve_metrics(stream, &page) {
+                debug!("Metrics request failed: {e}");
+            }
+        }
+    });
+}
+
+/// Answers a single HTTP/1.x request and closes the connection.
+fn serve_metrics(stream: TcpStream, page: &Mutex<String>) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the headers, nothing in them changes the answer
+    let mut header_line = String::new();
+    while reader.read_line(&mut header_line)? > 2 {
+        header_line.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => (
+            "200 OK",
+            "text/plain; version=0.0.4",
+            page.lock().map(|page| page.clone()).unwrap_or_default(),
+        ),
+        _ => ("404 Not Found", "text/plain", "not found\n".to_string()),
+    };
+    write!(
+        writer,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    writer.flush()
+}
@@ -0,0 +1,463 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use avian3d::prelude::*;
+use bevy::platform::collections::{HashMap, HashSet};
+use bevy::prelude::*;
+use shared::input::Stance;
+use shared::interaction::Door;
+use shared::scene::{FloorMarker, WallMarker};
+
+/// Width of a navigation cell, in meters.
+pub const NAV_CELL_SIZE: f32 = 0.5;
+/// Highest step between two neighbouring cells a character can walk up.
+const MAX_STEP_HEIGHT: f32 = 0.5;
+/// Distance at which an agent moves on to the next waypoint.
+const WAYPOINT_REACHED: f32 = 0.5;
+/// Distance the destination of an agent can move before its path is planned again.
+const REPLAN_DISTANCE: f32 = 1.0;
+
+/// Walkable grid built from the static level colliders and A* pathfinding over it. Every column
+/// of the grid can hold several walkable surfaces, one per floor, so stairwells and stacked rooms
+/// are connected wherever the step between two cells is low enough. [`NavLink`]s connect places
+/// the grid can't. The grid is rebuilt whenever level geometry or a link is added or removed. When
+/// a [`Door`] opens or closes, only the columns around it are sampled again.
+pub struct NavigationPlugin;
+
+impl Plugin for NavigationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NavGrid>();
+        // Runs once new colliders are in the spatial query pipeline
+        app.add_systems(
+            FixedPostUpdate,
+            (detect_level_changes, rebuild_nav_grid)
+                .chain()
+                .after(PhysicsSet::StepSimulation),
+        );
+    }
+}
+
+/// Connects the walkable cells closest to `from` and `to` in both directions.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct NavLink {
+    pub from: Vec3,
+    pub to: Vec3,
+}
+
+/// A walkable spot at the center of a cell, on top of the floor.
+#[derive(Clone, Debug)]
+struct NavNode {
+    position: Vec3,
+    neighbors: Vec<usize>,
+}
+
+#[derive(Resource, Default)]
+pub struct NavGrid {
+    nodes: Vec<NavNode>,
+    /// Nodes of each grid column, from the lowest to the highest.
+    columns: HashMap<IVec2, Vec<usize>>,
+    /// Set when the level changed since the grid was built.
+    stale: bool,
+    /// Columns around doors that opened or closed since the grid was built.
+    dirty_columns: HashSet<IVec2>,
+}
+
+pub(crate) type LevelGeometryFilter = Or<(With<FloorMarker>, With<WallMarker>)>;
+type ChangedGeometryFilter = (LevelGeometryFilter, Added<Collider>);
+
+impl NavGrid {
+    fn column(position: Vec3) -> IVec2 {
+        (position.xz() / NAV_CELL_SIZE).floor().as_ivec2()
+    }
+
+    fn column_center(column: IVec2) -> Vec2 {
+        (column.as_vec2() + 0.5) * NAV_CELL_SIZE
+    }
+
+    /// Floor point at the center of every walkable cell.
+    pub fn walkable_positions(&self) -> impl Iterator<Item = Vec3> + '_ {
+        self.nodes.iter().map(|node| node.position)
+    }
+
+    /// Every connection between two nodes, once.
+    pub fn edges(&self) -> impl Iterator<Item = (Vec3, Vec3)> + '_ {
+        self.nodes
+            .iter()
+            .enumerate()
+            .flat_map(move |(index, node)| {
+                node.neighbors
+                    .iter()
+                    .filter(move |neighbor| **neighbor > index)
+                    .map(move |neighbor| (node.position, self.nodes[*neighbor].position))
+            })
+    }
+
+    /// Node of the column of `position` at the closest height.
+    fn node_in_column(&self, column: IVec2, height: f32) -> Option<usize> {
+        self.columns.get(&column)?.iter().copied().min_by(|a, b| {
+            let a = (self.nodes[*a].position.y - height).abs();
+            let b = (self.nodes[*b].position.y - height).abs();
+            a.total_cmp(&b)
+        })
+    }
+
+    /// Node closest to `position`, looking a couple of cells around it when it stands off the
+    /// grid, for example right against a wall.
+    fn nearest_node(&self, position: Vec3) -> Option<usize> {
+        let center = Self::column(position);
+        (0..=2)
+            .flat_map(|radius| {
+                let nodes: Vec<usize> = (-radius..=radius)
+                    .flat_map(|x| (-radius..=radius).map(move |y| center + IVec2::new(x, y)))
+                    .filter_map(|column| self.node_in_column(column, position.y))
+                    .collect();
+                nodes.into_iter().min_by(|a, b| {
+                    let a = self.nodes[*a].position.distance_squared(position);
+                    let b = self.nodes[*b].position.distance_squared(position);
+                    a.total_cmp(&b)
+                })
+            })
+            .next()
+    }
+
+    /// Shortest walkable path from `from` to the cell of `to`, as a list of waypoints. Waypoints
+    /// that can be skipped by walking straight are removed.
+    pub fn find_path(&self, from: Vec3, to: Vec3) -> Option<Vec<Vec3>> {
+        let start = self.nearest_node(from)?;
+        let goal = self.nearest_node(to)?;
+        let nodes = self.a_star(start, goal)?;
+        let path: Vec<Vec3> = nodes
+            .into_iter()
+            .map(|node| self.nodes[node].position)
+            .collect();
+        Some(self.smooth(from, path))
+    }
+
+    fn a_star(&self, start: usize, goal: usize) -> Option<Vec<usize>> {
+        let goal_position = self.nodes[goal].position;
+        let mut open = BinaryHeap::from([OpenNode {
+            node: start,
+            estimate: 0.0,
+        }]);
+        let mut came_from: HashMap<usize, usize> = HashMap::default();
+        let mut cost: HashMap<usize, f32> = HashMap::from_iter([(start, 0.0)]);
+
+        while let Some(OpenNode { node, .. }) = open.pop() {
+            if node == goal {
+                let mut path = vec![goal];
+                let mut current = goal;
+                while let Some(previous) = came_from.get(&current) {
+                    current = *previous;
+                    path.push(current);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            let position = self.nodes[node].position;
+            for neighbor in &self.nodes[node].neighbors {
+                let neighbor_position = self.nodes[*neighbor].position;
+                let new_cost = cost[&node] + position.distance(neighbor_position);
+                if cost.get(neighbor).is_none_or(|old| new_cost < *old) {
+                    cost.insert(*neighbor, new_cost);
+                    came_from.insert(*neighbor, node);
+                    open.push(OpenNode {
+                        node: *neighbor,
+                        estimate: new_cost + neighbor_position.distance(goal_position),
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// String pulling: from each waypoint, jumps to the farthest one reachable in a straight line.
+    fn smooth(&self, from: Vec3, path: Vec<Vec3>) -> Vec<Vec3> {
+        let mut smoothed = Vec::new();
+        let mut current = from;
+        let mut index = 0;
+        while index < path.len() {
+            let farthest = (index..path.len())
+                .rev()
+                .find(|next| self.can_walk_straight(current, path[*next]))
+                .unwrap_or(index);
+            current = path[farthest];
+            smoothed.push(current);
+            index = farthest + 1;
+        }
+        smoothed
+    }
+
+    /// Whether every cell on the segment is walkable and the floor never steps too high.
+    fn can_walk_straight(&self, from: Vec3, to: Vec3) -> bool {
+        let steps = (from.xz().distance(to.xz()) / (NAV_CELL_SIZE * 0.5)).ceil() as usize;
+        let mut height = self
+            .nearest_node(from)
+            .map_or(from.y, |node| self.nodes[node].position.y);
+        for step in 1..=steps {
+            let point = from.lerp(to, step as f32 / steps as f32);
+            let Some(node) = self.node_in_column(Self::column(point), height) else {
+                return false;
+            };
+            let node_height = self.nodes[node].position.y;
+            if (node_height - height).abs() > MAX_STEP_HEIGHT {
+                return false;
+            }
+            height = node_height;
+        }
+        true
+    }
+}
+
+#[derive(PartialEq)]
+struct OpenNode {
+    node: usize,
+    estimate: f32,
+}
+
+impl Eq for OpenNode {}
+
+impl Ord for OpenNode {
+    /// Reversed so the [`BinaryHeap`] pops the lowest estimate first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Follows paths on the [`NavGrid`] to reach a destination.
+#[derive(Component, Default)]
+pub struct NavAgent {
+    path: Vec<Vec3>,
+    destination: Option<Vec3>,
+}
+
+impl NavAgent {
+    /// Waypoints left to walk through.
+    pub fn path(&self) -> &[Vec3] {
+        &self.path
+    }
+
+    /// Horizontal direction to walk in from `position` to reach `destination`, planning a new
+    /// path when the destination moved. Heads straight to it when there is no grid or no path.
+    pub fn direction_to(&mut self, grid: &NavGrid, position: Vec3, destination: Vec3) -> Vec3 {
+        let moved = self
+            .destination
+            .is_none_or(|planned| planned.distance(destination) > REPLAN_DISTANCE);
+        if moved {
+            self.destination = Some(destination);
+            self.path = grid.find_path(position, destination).unwrap_or_default();
+        }
+        while self
+            .path
+            .first()
+            .is_some_and(|waypoint| waypoint.xz().distance(position.xz()) < WAYPOINT_REACHED)
+        {
+            self.path.remove(0);
+        }
+        let target = self.path.first().copied().unwrap_or(destination);
+        (target - position).with_y(0.0)
+    }
+}
+
+fn detect_level_changes(
+    added_geometry: Query<(), ChangedGeometryFilter>,
+    added_links: Query<(), Added<NavLink>>,
+    changed_doors: Query<&ColliderAabb, Changed<Door>>,
+    mut removed_floors: RemovedComponents<FloorMarker>,
+    mut removed_walls: RemovedComponents<WallMarker>,
+    mut removed_links: RemovedComponents<NavLink>,
+    mut grid: ResMut<NavGrid>,
+) {
+    let removed =
+        removed_floors.read().count() + removed_walls.read().count() + removed_links.read().count();
+    if !added_geometry.is_empty() || !added_links.is_empty() || removed > 0 {
+        grid.stale = true;
+    }
+
+    // Cells next to the door are blocked by it too, as far as a standing character reaches
+    let reach = Stance::Standing
+        .collider()
+        .aabb(Vec3::ZERO, Quat::IDENTITY)
+        .max
+        .x
+        + NAV_CELL_SIZE;
+    for aabb in changed_doors.iter() {
+        let min = NavGrid::column(aabb.min - Vec3::splat(reach));
+        let max = NavGrid::column(aabb.max + Vec3::splat(reach));
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                grid.dirty_columns.insert(IVec2::new(x, y));
+            }
+        }
+    }
+}
+
+/// Samples every column over the level bounds from the top down, or only the dirty columns when
+/// nothing but doors changed. Upward faces of floors are walkable when a standing player capsule
+/// fits above them without touching the level.
+fn rebuild_nav_grid(
+    geometry: Query<(Entity, &ColliderAabb, Has<FloorMarker>), LevelGeometryFilter>,
+    links: Query<&NavLink>,
+    spatial_query: SpatialQuery,
+    mut grid: ResMut<NavGrid>,
+) {
+    if !grid.stale && grid.dirty_columns.is_empty() {
+        return;
+    }
+
+    let mut nav_grid = NavGrid::default();
+    let Some((min, max)) = geometry
+        .iter()
+        .map(|(_, aabb, _)| (aabb.min, aabb.max))
+        .reduce(|(min, max), (aabb_min, aabb_max)| (min.min(aabb_min), max.max(aabb_max)))
+    else {
+        *grid = nav_grid;
+        return;
+    };
+
+    let is_level = |entity: Entity| geometry.contains(entity);
+    let is_floor = |entity: Entity| geometry.get(entity).is_ok_and(|(_, _, floor)| floor);
+    // Same collider as the characters walking on the grid
+    let standing = Stance::Standing.collider();
+    let standing_center = -standing.aabb(Vec3::ZERO, Quat::IDENTITY).min.y + 0.05;
+    let top = max.y + 1.0;
+
+    // Walkable floor heights of a column, from the lowest to the highest
+    let sample_column = |column: IVec2| -> Vec<f32> {
+        let center = NavGrid::column_center(column);
+        let origin = Vec3::new(center.x, top, center.y);
+
+        let mut surfaces = Vec::new();
+        spatial_query.ray_hits_callback(
+            origin,
+            Dir3::NEG_Y,
+            top - min.y + 1.0,
+            true,
+            &SpatialQueryFilter::default(),
+            |hit| {
+                if is_floor(hit.entity) && hit.normal.y > 0.7 {
+                    surfaces.push(top - hit.distance);
+                }
+                true
+            },
+        );
+        surfaces.sort_by(f32::total_cmp);
+        surfaces.retain(|height| {
+            let floor = Vec3::new(center.x, *height, center.y);
+            !spatial_query
+                .shape_intersections(
+                    &standing,
+                    floor + Vec3::Y * standing_center,
+                    Quat::IDENTITY,
+                    &SpatialQueryFilter::default(),
+                )
+                .into_iter()
+                .any(is_level)
+        });
+        surfaces
+    };
+
+    let columns: Vec<(IVec2, Vec<f32>)> = if grid.stale {
+        let min_column = NavGrid::column(min);
+        let max_column = NavGrid::column(max);
+        (min_column.x..=max_column.x)
+            .flat_map(|x| (min_column.y..=max_column.y).map(move |y| IVec2::new(x, y)))
+            .map(|column| (column, sample_column(column)))
+            .collect()
+    } else {
+        // Clean columns keep their nodes, only the dirty ones are sampled again
+        let clean = grid
+            .columns
+            .iter()
+            .filter(|(column, _)| !grid.dirty_columns.contains(*column))
+            .map(|(column, nodes)| {
+                let heights = nodes
+                    .iter()
+                    .map(|node| grid.nodes[*node].position.y)
+                    .collect();
+                (*column, heights)
+            });
+        let dirty = grid
+            .dirty_columns
+            .iter()
+            .map(|column| (*column, sample_column(*column)));
+        clean.chain(dirty).collect()
+    };
+
+    for (column, heights) in columns {
+        let center = NavGrid::column_center(column);
+        for height in heights {
+            let index = nav_grid.nodes.len();
+            nav_grid.nodes.push(NavNode {
+                position: Vec3::new(center.x, height, center.y),
+                neighbors: Vec::new(),
+            });
+            nav_grid.columns.entry(column).or_default().push(index);
+        }
+    }
+
+    connect_neighbors(&mut nav_grid);
+    for link in links.iter() {
+        if let (Some(from), Some(to)) = (
+            nav_grid.nearest_node(link.from),
+            nav_grid.nearest_node(link.to),
+        ) {
+            nav_grid.nodes[from].neighbors.push(to);
+            nav_grid.nodes[to].neighbors.push(from);
+        }
+    }
+
+    if grid.stale {
+        info!(
+            "🧭 Navigation grid rebuilt with {} walkable cells",
+            nav_grid.nodes.len()
+        );
+    } else {
+        debug!(
+            "🧭 Navigation grid updated around doors, {} cells sampled again",
+            grid.dirty_columns.len()
+        );
+    }
+    *grid = nav_grid;
+}
+
+/// Links each node to the nodes of the 8 columns around it within a step. Diagonals need both
+/// sides to be walkable so paths don't cut wall corners.
+fn connect_neighbors(grid: &mut NavGrid) {
+    const OFFSETS: [IVec2; 8] = [
+        IVec2::new(1, 0),
+        IVec2::new(-1, 0),
+        IVec2::new(0, 1),
+        IVec2::new(0, -1),
+        IVec2::new(1, 1),
+        IVec2::new(1, -1),
+        IVec2::new(-1, 1),
+        IVec2::new(-1, -1),
+    ];
+    for node in 0..grid.nodes.len() {
+        let position = grid.nodes[node].position;
+        let column = NavGrid::column(position);
+        let reachable = |offset: IVec2| {
+            grid.node_in_column(column + offset, position.y)
+                .filter(|other| {
+                    (grid.nodes[*other].position.y - position.y).abs() <= MAX_STEP_HEIGHT
+                })
+        };
+        let neighbors: Vec<usize> = OFFSETS
+            .iter()
+            .filter(|offset| {
+                offset.x == 0
+                    || offset.y == 0
+                    || (reachable(IVec2::new(offset.x, 0)).is_some()
+                        && reachable(IVec2::new(0, offset.y)).is_some())
+            })
+            .filter_map(|offset| reachable(*offset))
+            .collect();
+        grid.nodes[node].neighbors = neighbors;
+    }
+}
@@ -1,11 +1,28 @@
-use bevy::prelude::{App, Commands, Name, OnAdd, Plugin, Res, Startup, Trigger, info};
-use lightyear::prelude::server::NetcodeConfig;
+use std::time::Duration;
+
+use avian3d::prelude::Position;
+use bevy::prelude::{
+    App, Changed, Commands, Component, Entity, Event, IntoScheduleConfigs, Name, OnAdd, OnRemove,
+    Plugin, PostUpdate, Query, Res, Single, Startup, Time, Timer, TimerMode, Trigger, Update, With,
+    info,
+};
+use lightyear::connection::client::Disconnecting;
 use lightyear::prelude::server::Start;
+use lightyear::prelude::server::{ClientOf, NetcodeConfig};
 use lightyear::prelude::*;
 use lightyear::{netcode::NetcodeServer, prelude::server::ServerUdpIo};
 use shared::NetTransport;
+use shared::link_conditioner::NetworkConditions;
+use shared::protocol::{Kicked, ServerNoticeChannel};
+use shared::quantization::{WireFormat, snap_position};
 use shared::{SEND_INTERVAL, SERVER_BIND_ADDR, SHARED_SETTINGS};
 
+use crate::app::ServerConfig;
+use crate::logging::client_span;
+
+/// Time a kicked client gets to disconnect by itself before the server drops its link.
+const KICK_GRACE: Duration = Duration::from_secs(1);
+
 pub struct NetworkPlugin;
 
 impl Plugin for NetworkPlugin {
@@ -13,9 +30,34 @@ impl Plugin for NetworkPlugin {
         app.insert_resource(NetTransport::Udp);
         app.add_systems(Startup, startup_server);
         app.add_observer(handle_new_client);
+        app.add_observer(handle_disconnected);
+        app.add_observer(kick_client);
+        app.add_systems(Update, drop_kicked_clients);
+        if app.world().get_resource::<WireFormat>() == Some(&WireFormat::QuantizedDelta) {
+            app.add_systems(
+                PostUpdate,
+                (
+                    snap_positions.before(ReplicationBufferSet::Buffer),
+                    drop_lost_delta_baselines
+                        .after(ReplicationBufferSet::BeforeBuffer)
+                        .before(ReplicationBufferSet::Buffer),
+                ),
+            );
+        }
     }
 }
 
+/// Removes a client from the server, telling it why first.
+#[derive(Event, Clone, Debug)]
+pub struct KickClient {
+    pub client: Entity,
+    pub reason: String,
+}
+
+/// Marks a client that was told it got kicked, counting down to the forced drop.
+#[derive(Component)]
+struct PendingKick(Timer);
+
 fn startup_server(mut commands: Commands, transport: Res<NetTransport>) {
     info!("Starting server with transport: {:?}", transport.as_ref());
     match transport.as_ref() {
@@ -33,7 +75,6 @@ fn startup_server(mut commands: Commands, transport: Res<NetTransport>) {
                     NetcodeServer::new(netcode_config),
                     LocalAddr(SERVER_BIND_ADDR),
                     ServerUdpIo::default(),
-                    // DeltaManager::default(), // Enable delta compression
                 ))
                 .id();
 
@@ -47,11 +88,113 @@ fn startup_server(mut commands: Commands, transport: Res<NetTransport>) {
     }
 }
 
-fn handle_new_client(trigger: Trigger<OnAdd, LinkOf>, mut commands: Commands) {
-    info!("🎉 New client connected: {:?}", trigger.target());
+fn handle_new_client(
+    trigger: Trigger<OnAdd, LinkOf>,
+    conditions: Res<NetworkConditions>,
+    config: Res<ServerConfig>,
+    mut links: Query<&mut Link>,
+    mut commands: Commands,
+) {
+    info!(
+        entity = ?trigger.target(),
+        event = "link_opened",
+        "🎉 New client connected: {:?}",
+        trigger.target()
+    );
+
+    if let Ok(mut link) = links.get_mut(trigger.target()) {
+        link.recv.conditioner = conditions.recv_conditioner();
+    }
 
     commands.entity(trigger.target()).insert((
-        ReplicationSender::new(SEND_INTERVAL, SendUpdatesMode::SinceLastAck, false),
+        ReplicationSender::new(
+            SEND_INTERVAL,
+            SendUpdatesMode::SinceLastAck,
+            config.bandwidth_cap.is_some(),
+        ),
+        // Delta compressed components are diffed against what this client acked
+        DeltaManager::default(),
         Name::from(format!("Client-{}", trigger.target())),
     ));
 }
+
+/// Keeps the replicated positions on the grid they are sent on, so the client adds up the exact
+/// same millimetre deltas the server computed.
+fn snap_positions(mut positions: Query<&mut Position, (Changed<Position>, With<Replicate>)>) {
+    for mut position in positions.iter_mut() {
+        position.0 = snap_position(position.0);
+    }
+}
+
+/// lightyear moves a client's delta baseline to the tick of every ack, even to a tick it already
+/// dropped from the [`DeltaManager`] when an older ack comes in after a newer one. Every later
+/// diff of that entity then fails and the client stops hearing about it. Forgetting those
+/// baselines makes the next update a full value instead.
+fn drop_lost_delta_baselines(mut links: Query<(&mut ReplicationSender, &DeltaManager)>) {
+    for (mut sender, delta_manager) in links.iter_mut() {
+        let lost = |entity, tick, kind| delta_manager.get(entity, tick, kind).is_none();
+        if sender.group_channels.values().any(|channel| {
+            channel
+                .delta_ack_ticks
+                .iter()
+                .any(|(&(entity, kind), &tick)| lost(entity, tick, kind))
+        }) {
+            for channel in sender.group_channels.values_mut() {
+                channel
+                    .delta_ack_ticks
+                    .retain(|&(entity, kind), &mut tick| !lost(entity, tick, kind));
+            }
+        }
+    }
+}
+
+fn handle_disconnected(
+    trigger: Trigger<OnRemove, Connected>,
+    query: Query<&RemoteId, With<ClientOf>>,
+    timeline: Single<&LocalTimeline, With<Server>>,
+) {
+    let Ok(remote_id) = query.get(trigger.target()) else {
+        return;
+    };
+    let _span = client_span(remote_id.to_bits(), trigger.target(), timeline.tick()).entered();
+    info!(
+        event = "client_disconnected",
+        "👋 Client {remote_id:?} disconnected"
+    );
+}
+
+fn kick_client(
+    trigger: Trigger<KickClient>,
+    mut senders: Query<(&mut MessageSender<Kicked>, &RemoteId)>,
+    timeline: Single<&LocalTimeline, With<Server>>,
+    mut commands: Commands,
+) {
+    let Ok((mut sender, remote_id)) = senders.get_mut(trigger.client) else {
+        return;
+    };
+    let _span = client_span(remote_id.to_bits(), trigger.client, timeline.tick()).entered();
+    info!(
+        event = "client_kicked",
+        reason = %trigger.reason,
+        "👢 Kicking client {remote_id:?}: {}",
+        trigger.reason
+    );
+    sender.send::<ServerNoticeChannel>(Kicked {
+        reason: trigger.reason.clone(),
+    });
+    commands
+        .entity(trigger.client)
+        .insert(PendingKick(Timer::new(KICK_GRACE, TimerMode::Once)));
+}
+
+fn drop_kicked_clients(
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut PendingKick)>,
+    mut commands: Commands,
+) {
+    for (entity, mut pending) in query.iter_mut() {
+        if pending.0.tick(time.delta()).just_finished() {
+            commands.entity(entity).insert(Disconnecting);
+        }
+    }
+}
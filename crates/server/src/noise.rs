@@ -0,0 +1,224 @@
+use avian3d::prelude::*;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use shared::input::{Stance, is_grounded};
+use shared::protocol::PlayerId;
+use shared::scene::WallMarker;
+
+use crate::gameplay::{ShotFired, fire_weapons};
+
+/// Distance at which a noise of loudness 1 fades out, in meters.
+pub const NOISE_RANGE: f32 = 40.0;
+/// Loudness kept by a noise going through a wall.
+pub const WALL_OCCLUSION: f32 = 0.4;
+/// Weakest loudness a listener with a sensitivity of 1 still hears.
+pub const HEARING_THRESHOLD: f32 = 0.02;
+/// Distance walked between two footsteps, in meters.
+const FOOTSTEP_STRIDE: f32 = 1.6;
+/// Falling speed above which landing makes noise, in meters per second.
+const LANDING_MIN_SPEED: f32 = 4.0;
+/// Falling speed of the loudest landing.
+const LANDING_MAX_SPEED: f32 = 12.0;
+
+/// Noises made in the level and who can hear them. Footsteps, gunfire, landings and voices all
+/// produce [`NoiseEvent`]s, and [`Hearing`] tells which [`NoiseListener`]s heard each of them,
+/// muffled by the walls in between.
+pub struct NoisePlugin;
+
+impl Plugin for NoisePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<NoiseEvent>();
+        app.add_event::<ShotFired>();
+        app.add_observer(add_noise_trackers);
+        app.add_systems(
+            FixedUpdate,
+            (
+                footstep_noise,
+                gunfire_noise.after(fire_weapons),
+                landing_noise,
+            ),
+        );
+    }
+}
+
+/// What made a noise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoiseSource {
+    Footstep(Stance),
+    Gunfire,
+    Landing,
+    Door,
+    Voice,
+}
+
+/// A noise heard around `position`, louder noises carrying further.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct NoiseEvent {
+    pub position: Vec3,
+    /// Between 0 (barely audible) and 1 (a shout).
+    pub loudness: f32,
+    pub source: NoiseSource,
+    /// Entity that made the noise.
+    pub emitter: Entity,
+}
+
+/// Something that reacts to noises, such as an enemy.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct NoiseListener {
+    /// Multiplies the loudness of what this listener hears.
+    pub sensitivity: f32,
+}
+
+impl Default for NoiseListener {
+    fn default() -> Self {
+        Self { sensitivity: 1.0 }
+    }
+}
+
+/// A listener that heard a noise, and how loud it was where it stands.
+#[derive(Clone, Copy, Debug)]
+pub struct HeardNoise {
+    pub listener: Entity,
+    pub loudness: f32,
+}
+
+/// Answers which listeners heard a noise. Loudness fades linearly over [`NOISE_RANGE`] and every
+/// wall between the noise and the listener keeps only [`WALL_OCCLUSION`] of it.
+#[derive(SystemParam)]
+pub struct Hearing<'w, 's> {
+    spatial_query: SpatialQuery<'w, 's>,
+    listeners: Query<'w, 's, (Entity, &'static Position, &'static NoiseListener)>,
+    walls: Query<'w, 's, (), With<WallMarker>>,
+}
+
+impl Hearing<'_, '_> {
+    pub fn heard_by(&self, noise: &NoiseEvent) -> Vec<HeardNoise> {
+        let range = noise.loudness * NOISE_RANGE;
+        self.listeners
+            .iter()
+            .filter(|(listener, _, _)| *listener != noise.emitter)
+            .filter_map(|(listener, position, hearing)| {
+                let distance = noise.position.distance(position.0);
+                if distance >= range {
+                    return None;
+                }
+                let walls = self.walls_between(noise.position, position.0);
+                let loudness = noise.loudness
+                    * (1.0 - distance / range)
+                    * WALL_OCCLUSION.powi(walls as i32)
+                    * hearing.sensitivity;
+                (loudness >= HEARING_THRESHOLD).then_some(HeardNoise { listener, loudness })
+            })
+            .collect()
+    }
+
+    fn walls_between(&self, from: Vec3, to: Vec3) -> usize {
+        let Ok(direction) = Dir3::new(to - from) else {
+            return 0;
+        };
+        let mut walls = 0;
+        self.spatial_query.ray_hits_callback(
+            from,
+            direction,
+            from.distance(to),
+            true,
+            &SpatialQueryFilter::default(),
+            |hit| {
+                if self.walls.contains(hit.entity) {
+                    walls += 1;
+                }
+                true
+            },
+        );
+        walls
+    }
+}
+
+/// Movement state used to detect footsteps and landings.
+#[derive(Component, Default)]
+struct NoiseTracker {
+    walked: f32,
+    falling_speed: f32,
+}
+
+fn add_noise_trackers(trigger: Trigger<OnAdd, PlayerId>, mut commands: Commands) {
+    commands
+        .entity(trigger.target())
+        .insert(NoiseTracker::default());
+}
+
+fn footstep_loudness(stance: Stance) -> f32 {
+    match stance {
+        Stance::Crouching => 0.05,
+        Stance::Standing => 0.25,
+        Stance::Sprinting => 0.5,
+    }
+}
+
+fn footstep_noise(
+    time: Res<Time>,
+    spatial_query: Res<SpatialQueryPipeline>,
+    mut players: Query<(
+        Entity,
+        &Position,
+        &LinearVelocity,
+        &Stance,
+        &mut NoiseTracker,
+    )>,
+    mut noises: EventWriter<NoiseEvent>,
+) {
+    for (entity, position, velocity, stance, mut tracker) in players.iter_mut() {
+        let speed = velocity.0.xz().length();
+        if speed < 1.0 || !is_grounded(entity, position.0, *stance, &spatial_query) {
+            continue;
+        }
+        tracker.walked += speed * time.delta_secs();
+        if tracker.walked >= FOOTSTEP_STRIDE {
+            tracker.walked = 0.0;
+            noises.write(NoiseEvent {
+                position: position.0,
+                loudness: footstep_loudness(*stance),
+                source: NoiseSource::Footstep(*stance),
+                emitter: entity,
+            });
+        }
+    }
+}
+
+fn gunfire_noise(
+    mut shots: EventReader<ShotFired>,
+    players: Query<&Position>,
+    mut noises: EventWriter<NoiseEvent>,
+) {
+    for shot in shots.read() {
+        if let Ok(position) = players.get(shot.shooter) {
+            noises.write(NoiseEvent {
+                position: position.0,
+                loudness: 1.0,
+                source: NoiseSource::Gunfire,
+                emitter: shot.shooter,
+            });
+        }
+    }
+}
+
+/// A landing is the tick a falling player stops going down.
+fn landing_noise(
+    mut players: Query<(Entity, &Position, &LinearVelocity, &mut NoiseTracker)>,
+    mut noises: EventWriter<NoiseEvent>,
+) {
+    for (entity, position, velocity, mut tracker) in players.iter_mut() {
+        let falling_speed = (-velocity.0.y).max(0.0);
+        if tracker.falling_speed >= LANDING_MIN_SPEED && falling_speed < 0.5 {
+            let impact = (tracker.falling_speed - LANDING_MIN_SPEED)
+                / (LANDING_MAX_SPEED - LANDING_MIN_SPEED);
+            noises.write(NoiseEvent {
+                position: position.0,
+                loudness: 0.3 + 0.5 * impact.clamp(0.0, 1.0),
+                source: NoiseSource::Landing,
+                emitter: entity,
+            });
+        }
+        tracker.falling_speed = falling_speed;
+    }
+}
@@ -0,0 +1,406 @@
+use std::path::Path;
+
+use avian3d::prelude::*;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use lightyear::connection::server::Started;
+use lightyear::prelude::*;
+use serde::{Deserialize, Serialize};
+use shared::enemy::{Enemy, EnemyState};
+use shared::interaction::{Interactable, InteractableKind, PICKUP_SIZE, TERMINAL_SIZE};
+use shared::objective::{Objective, ObjectiveItem, ObjectiveKind, ObjectiveState, ObjectiveStatus};
+use shared::protocol::PlayerId;
+
+use crate::app::ServerConfig;
+use crate::gameplay::ResetLevel;
+use crate::interaction::Interacted;
+use crate::match_state::{EndRound, StartExtraction, round_in_progress};
+use crate::relevancy::InRoom;
+
+/// Seconds to hold the action to pick an item up.
+const PICKUP_HOLD_SECS: f32 = 0.5;
+
+/// Mission objectives: spawned from a [`MissionDefinition`] every round, unlocked once the
+/// objectives they depend on are completed and progressed by players in their area or by using
+/// their terminal and items. [`ObjectiveCompleted`] is triggered on completion. The mission is won
+/// once every objective is done, or goes to extraction when an [`ObjectiveKind::Extract`]
+/// objective is completed.
+pub struct ObjectivePlugin;
+
+/// A mission, loaded from a RON file.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct MissionDefinition {
+    pub name: String,
+    pub objectives: Vec<ObjectiveDefinition>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ObjectiveDefinition {
+    /// Name other objectives use to depend on this one.
+    pub id: String,
+    pub kind: ObjectiveKind,
+    pub description: String,
+    /// Center of the area, on the floor.
+    pub position: Vec3,
+    pub radius: f32,
+    /// Items to pick up for [`ObjectiveKind::Collect`], seconds to hold the interaction for
+    /// [`ObjectiveKind::Hack`], seconds to hold the area otherwise.
+    pub amount: f32,
+    /// Ids of the objectives to complete before this one unlocks.
+    #[serde(default)]
+    pub requires: Vec<String>,
+}
+
+/// Triggered when an objective is completed.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ObjectiveCompleted {
+    pub objective: Entity,
+    pub kind: ObjectiveKind,
+}
+
+/// Progress of an objective in items or seconds, out of its definition's `amount`.
+#[derive(Component, Debug)]
+struct ObjectiveProgress {
+    current: f32,
+    required: f32,
+}
+
+/// Objectives to complete before this one unlocks.
+#[derive(Component, Debug, Default)]
+struct ObjectiveRequires(Vec<Entity>);
+
+/// The objective an item or a terminal belongs to, unlocked with it.
+#[derive(Component, Debug)]
+struct ObjectivePart(Entity);
+
+type MissionEntityFilter = Or<(With<Objective>, With<ObjectivePart>)>;
+
+type ObjectiveEntry = (
+    Entity,
+    &'static Objective,
+    &'static mut ObjectiveState,
+    &'static mut ObjectiveProgress,
+);
+
+impl Plugin for ObjectivePlugin {
+    fn build(&self, app: &mut App) {
+        let mission_path = app
+            .world()
+            .get_resource::<ServerConfig>()
+            .and_then(|config| config.mission_path.clone());
+        let mission = match mission_path {
+            Some(path) => MissionDefinition::load(&path),
+            None => MissionDefinition::default(),
+        };
+        app.insert_resource(mission);
+        app.add_observer(spawn_mission_on_server_start);
+        app.add_observer(reset_mission);
+        app.add_observer(finish_mission);
+        app.add_observer(use_objective_parts);
+        app.add_systems(
+            FixedUpdate,
+            (unlock_objectives, progress_objectives)
+                .chain()
+                .run_if(round_in_progress),
+        );
+    }
+}
+
+impl MissionDefinition {
+    pub fn from_ron(text: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(text)
+    }
+
+    pub fn load(path: &Path) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(text) => match Self::from_ron(&text) {
+                Ok(mission) => {
+                    info!(
+                        "🎯 Loaded mission '{}' with {} objectives from {}",
+                        mission.name,
+                        mission.objectives.len(),
+                        path.display()
+                    );
+                    mission
+                }
+                Err(e) => {
+                    error!(
+                        "❌ Failed to parse mission {}: {e}, playing without objectives",
+                        path.display()
+                    );
+                    Self::default()
+                }
+            },
+            Err(_) => {
+                info!(
+                    "🎯 No mission at {}, playing without objectives",
+                    path.display()
+                );
+                Self::default()
+            }
+        }
+    }
+}
+
+/// Spawns the objectives of `mission`, the items of its collect objectives and the terminals of
+/// its hack objectives, all locked until the round starts. An objective reusing the id of an
+/// earlier one is skipped, requirements couldn't tell them apart.
+pub fn spawn_mission(commands: &mut Commands, mission: &MissionDefinition) {
+    let mut ids = HashMap::new();
+    let mut spawned = Vec::new();
+    for definition in &mission.objectives {
+        if ids.contains_key(definition.id.as_str()) {
+            warn!(
+                "🎯 Skipping objective '{}', an earlier objective has the same id",
+                definition.id
+            );
+            continue;
+        }
+        let order = spawned.len();
+        let objective = commands
+            .spawn((
+                Name::new(format!("Objective {}", definition.id)),
+                Objective {
+                    order: order as u8,
+                    kind: definition.kind,
+                    description: definition.description.clone(),
+                    position: definition.position,
+                    radius: definition.radius,
+                },
+                ObjectiveState::default(),
+                ObjectiveProgress {
+                    current: 0.0,
+                    required: definition.amount,
+                },
+                Replicate::to_clients(NetworkTarget::All),
+            ))
+            .id();
+        ids.insert(definition.id.as_str(), objective);
+        spawned.push((definition, objective));
+
+        match definition.kind {
+            ObjectiveKind::Collect => {
+                let count = definition.amount.max(0.0) as u32;
+                for i in 0..count {
+                    let angle = i as f32 * std::f32::consts::TAU / count as f32;
+                    let position = definition.position
+                        + Vec3::new(angle.cos(), 0.0, angle.sin()) * definition.radius
+                        + Vec3::Y * PICKUP_SIZE;
+                    commands.spawn((
+                        Name::new(format!("Item {}", definition.id)),
+                        ObjectiveItem,
+                        Interactable {
+                            kind: InteractableKind::Pickup,
+                            hold_secs: PICKUP_HOLD_SECS,
+                            locked: true,
+                        },
+                        ObjectivePart(objective),
+                        objective_part_replication(position),
+                    ));
+                }
+            }
+            ObjectiveKind::Hack => {
+                let position = definition.position + Vec3::Y * TERMINAL_SIZE.y / 2.0;
+                commands.spawn((
+                    Name::new(format!("Terminal {}", definition.id)),
+                    Interactable {
+                        kind: InteractableKind::Terminal,
+                        hold_secs: definition.amount,
+                        locked: true,
+                    },
+                    ObjectivePart(objective),
+                    objective_part_replication(position),
+                ));
+            }
+            ObjectiveKind::Defend | ObjectiveKind::Extract => {}
+        }
+    }
+
+    for (definition, objective) in spawned {
+        let requires = definition
+            .requires
+            .iter()
+            .filter_map(|id| {
+                let required = ids.get(id.as_str()).copied();
+                if required.is_none() {
+                    warn!(
+                        "🎯 Objective '{}' requires unknown objective '{id}'",
+                        definition.id
+                    );
+                }
+                required
+            })
+            .collect();
+        commands
+            .entity(objective)
+            .insert(ObjectiveRequires(requires));
+    }
+}
+
+fn objective_part_replication(position: Vec3) -> impl Bundle {
+    (
+        Position(position),
+        Rotation::default(),
+        Replicate::to_clients(NetworkTarget::All),
+        InRoom::default(),
+        NetworkVisibility::default(),
+        ComponentReplicationOverrides::<Position>::default().replicate_once_all(),
+    )
+}
+
+fn spawn_mission_on_server_start(
+    _trigger: Trigger<OnAdd, Started>,
+    mission: Res<MissionDefinition>,
+    mut commands: Commands,
+) {
+    spawn_mission(&mut commands, &mission);
+}
+
+/// Every round plays the mission from the start on the new level.
+fn reset_mission(
+    _trigger: Trigger<ResetLevel>,
+    mission: Res<MissionDefinition>,
+    objectives: Query<Entity, MissionEntityFilter>,
+    mut commands: Commands,
+) {
+    for entity in objectives.iter() {
+        commands.entity(entity).despawn();
+    }
+    spawn_mission(&mut commands, &mission);
+}
+
+fn unlock_objectives(
+    mut objectives: Query<(Entity, &mut ObjectiveState, &ObjectiveRequires)>,
+    mut parts: Query<(&mut Interactable, &ObjectivePart)>,
+) {
+    let completed: Vec<Entity> = objectives
+        .iter()
+        .filter(|(_, state, _)| state.status == ObjectiveStatus::Completed)
+        .map(|(entity, ..)| entity)
+        .collect();
+    for (entity, mut state, requires) in objectives.iter_mut() {
+        if state.status == ObjectiveStatus::Locked
+            && requires
+                .0
+                .iter()
+                .all(|required| completed.contains(required))
+        {
+            state.status = ObjectiveStatus::Active;
+            for (mut interactable, _) in parts.iter_mut().filter(|(_, part)| part.0 == entity) {
+                interactable.locked = false;
+            }
+            info!(
+                event = "objective_unlocked",
+                objective = ?entity,
+                "🎯 Objective {entity:?} unlocked"
+            );
+        }
+    }
+}
+
+/// Picking an item up counts it for its objective, hacking a terminal completes its objective. A
+/// hacked terminal stops being [`Interactable`], so players don't get a prompt for it anymore.
+fn use_objective_parts(
+    trigger: Trigger<Interacted>,
+    parts: Query<&ObjectivePart, With<Interactable>>,
+    mut objectives: Query<(&ObjectiveState, &mut ObjectiveProgress)>,
+    mut commands: Commands,
+) {
+    let Ok(part) = parts.get(trigger.target) else {
+        return;
+    };
+    let Ok((state, mut progress)) = objectives.get_mut(part.0) else {
+        return;
+    };
+    if state.status != ObjectiveStatus::Active {
+        return;
+    }
+    match trigger.kind {
+        InteractableKind::Pickup => {
+            progress.current += 1.0;
+            commands.entity(trigger.target).despawn();
+        }
+        InteractableKind::Terminal => {
+            progress.current = progress.required;
+            commands.entity(trigger.target).remove::<Interactable>();
+        }
+        InteractableKind::Door => {}
+    }
+}
+
+fn progress_objectives(
+    time: Res<Time>,
+    mut objectives: Query<ObjectiveEntry>,
+    players: Query<&Position, With<PlayerId>>,
+    enemies: Query<(&Position, &EnemyState), With<Enemy>>,
+    mut commands: Commands,
+) {
+    for (entity, objective, mut state, mut progress) in objectives.iter_mut() {
+        if state.status != ObjectiveStatus::Active {
+            continue;
+        }
+        let inside = |position: &Position| {
+            position.0.xz().distance(objective.position.xz()) <= objective.radius
+        };
+        let holding = match objective.kind {
+            ObjectiveKind::Collect | ObjectiveKind::Hack => false,
+            ObjectiveKind::Defend => {
+                players.iter().any(inside)
+                    && !enemies.iter().any(|(position, enemy_state)| {
+                        *enemy_state != EnemyState::Sleeping && inside(position)
+                    })
+            }
+            ObjectiveKind::Extract => !players.is_empty() && players.iter().all(inside),
+        };
+        if holding {
+            progress.current += time.delta_secs();
+        }
+
+        if progress.current < progress.required {
+            let percent = (progress.current / progress.required * 100.0) as u8;
+            state.set_if_neq(ObjectiveState {
+                progress: percent,
+                ..*state
+            });
+            continue;
+        }
+        *state = ObjectiveState {
+            status: ObjectiveStatus::Completed,
+            progress: 100,
+        };
+        info!(
+            event = "objective_completed",
+            objective = ?entity,
+            kind = objective.kind.label(),
+            "✅ Objective completed: {}",
+            objective.description
+        );
+        commands.trigger(ObjectiveCompleted {
+            objective: entity,
+            kind: objective.kind,
+        });
+    }
+}
+
+/// Reaching the extraction starts it, otherwise the round is won with the last objective.
+fn finish_mission(
+    trigger: Trigger<ObjectiveCompleted>,
+    objectives: Query<(&Objective, &ObjectiveState)>,
+    mut commands: Commands,
+) {
+    if trigger.kind == ObjectiveKind::Extract {
+        commands.trigger(StartExtraction);
+        return;
+    }
+    let done = objectives
+        .iter()
+        .all(|(_, state)| state.status == ObjectiveStatus::Completed);
+    let extraction = objectives
+        .iter()
+        .any(|(objective, _)| objective.kind == ObjectiveKind::Extract);
+    if done && !extraction {
+        info!(event = "mission_completed", "🏆 Mission completed");
+        commands.trigger(EndRound { victory: true });
+    }
+}
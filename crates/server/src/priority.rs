@@ -0,0 +1,133 @@
+use avian3d::prelude::Position;
+use bevy::ecs::component::Tick;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use lightyear::connection::client::Connected;
+use lightyear::prelude::server::ClientOf;
+use lightyear::prelude::*;
+use shared::protocol::PlayerId;
+
+use crate::app::ServerConfig;
+
+/// Priority of the player controlled by the client itself.
+pub const OWN_PLAYER_PRIORITY: f32 = 10.0;
+/// Priority of another player standing right next to the client's player.
+pub const PLAYER_PRIORITY: f32 = 4.0;
+/// Priority of any other entity right next to the client's player.
+pub const PROP_PRIORITY: f32 = 1.0;
+/// Distance at which the priority of an entity is halved.
+pub const PRIORITY_HALF_DISTANCE: f32 = 15.0;
+
+/// Shares the bandwidth budget of each client between the entities it receives.
+///
+/// Every replication group gets a priority per client, from what the entity is and how far it is
+/// from the client's player. When a send goes over [`ServerConfig::bandwidth_cap`], the groups
+/// left out keep their accumulated priority and win a later send, so distant entities are updated
+/// less often instead of never. Static level geometry is only sent when it spawns.
+pub struct ReplicationPriorityPlugin;
+
+impl Plugin for ReplicationPriorityPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(apply_bandwidth_cap);
+        app.add_observer(split_player_groups);
+        app.add_systems(Update, update_replication_priorities);
+        app.add_systems(
+            PostUpdate,
+            reset_acked_priorities
+                .after(ReplicationBufferSet::BeforeBuffer)
+                .before(ReplicationBufferSet::Flush),
+        );
+    }
+}
+
+/// Tick at which the client last acked an update of each replication group.
+#[derive(Component, Default)]
+struct AckedGroups(HashMap<ReplicationGroupId, Tick>);
+
+/// The client's link only exists once it is connected, setting the cap any earlier is undone.
+fn apply_bandwidth_cap(
+    trigger: Trigger<OnAdd, Connected>,
+    config: Res<ServerConfig>,
+    mut transports: Query<&mut Transport, With<ClientOf>>,
+    mut commands: Commands,
+) {
+    let Some(cap) = config.bandwidth_cap else {
+        return;
+    };
+    if let Ok(mut transport) = transports.get_mut(trigger.target()) {
+        transport.priority_manager = PriorityManager::new(PriorityConfig::new(cap));
+        commands
+            .entity(trigger.target())
+            .insert(AckedGroups::default());
+        debug!("🚦 Capping client {:?} at {cap} bytes/s", trigger.target());
+    }
+}
+
+/// lightyear never learns which groups made it into a send once a send interval is set, so their
+/// accumulated priority only grows and the groups that start ahead stay ahead forever. A group
+/// starts over from zero once the client acks one of its updates instead.
+fn reset_acked_priorities(
+    mut senders: Query<(&mut ReplicationSender, &mut AckedGroups), With<ClientOf>>,
+) {
+    for (mut sender, mut acked) in senders.iter_mut() {
+        for (group_id, channel) in sender.bypass_change_detection().group_channels.iter_mut() {
+            let Some(ack_tick) = channel.ack_bevy_tick else {
+                continue;
+            };
+            if acked.0.insert(*group_id, ack_tick) != Some(ack_tick) {
+                channel.accumulated_priority = 0.0;
+            }
+        }
+    }
+}
+
+/// lightyear puts every predicted entity in the same replication group, which would give all
+/// players a single priority. Each player is only predicted by its owner, so they don't need to
+/// be updated together.
+fn split_player_groups(
+    trigger: Trigger<OnInsert, ReplicationGroup>,
+    query: Query<&ReplicationGroup, With<PlayerId>>,
+    mut commands: Commands,
+) {
+    if query
+        .get(trigger.target())
+        .is_ok_and(|group| *group == PREDICTION_GROUP)
+    {
+        commands
+            .entity(trigger.target())
+            .insert(ReplicationGroup::new_from_entity());
+    }
+}
+
+fn update_replication_priorities(
+    mut senders: Query<(Entity, &mut ReplicationSender), With<ClientOf>>,
+    players: Query<(Entity, &Position, &ControlledBy), With<PlayerId>>,
+    positions: Query<(&Position, Has<PlayerId>)>,
+) {
+    for (client, mut sender) in senders.iter_mut() {
+        let own_player = players
+            .iter()
+            .find(|(_, _, controlled_by)| controlled_by.owner == client);
+        for (group_id, channel) in sender.bypass_change_detection().group_channels.iter_mut() {
+            let Ok(entity) = Entity::try_from_bits(group_id.0) else {
+                continue;
+            };
+            let Ok((position, is_player)) = positions.get(entity) else {
+                continue;
+            };
+            channel.base_priority = match own_player {
+                Some((own, _, _)) if own == entity => OWN_PLAYER_PRIORITY,
+                Some((_, own_position, _)) => {
+                    let distance = own_position.0.distance(position.0);
+                    let priority = if is_player {
+                        PLAYER_PRIORITY
+                    } else {
+                        PROP_PRIORITY
+                    };
+                    priority / (1.0 + distance / PRIORITY_HALF_DISTANCE)
+                }
+                None => PROP_PRIORITY,
+            };
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use avian3d::prelude::Position;
+use bevy::ecs::entity::EntityHashSet;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use lightyear::prelude::server::ClientOf;
+use lightyear::prelude::*;
+use shared::protocol::PlayerId;
+
+/// Interest management based on the level rooms. Every room of the [`LevelLayout`] gets a
+/// lightyear [`Room`], replicated entities with an [`InRoom`] join the room they are in and each
+/// client joins the room of its player and the adjacent ones. Entities out of these rooms are
+/// despawned on that client, and sent again with their current state when they come back in range.
+///
+/// Level geometry is replicated to every client without an [`InRoom`], so floors, walls and doors
+/// are never hidden.
+pub struct RelevancyPlugin;
+
+impl Plugin for RelevancyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RoomPlugin);
+        app.init_resource::<LevelLayout>();
+        app.init_resource::<LevelRooms>();
+        app.add_observer(add_client_rooms);
+        app.add_observer(leave_room_on_remove);
+        app.add_observer(leave_client_rooms_on_remove);
+        app.add_systems(
+            Update,
+            (track_entity_rooms, join_entity_rooms, update_client_rooms).chain(),
+        );
+    }
+}
+
+/// Room of the level, numbered by the level when it spawns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RoomId(pub u32);
+
+/// A room of the level: where it lies on the floor and the rooms that open into it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LevelRoom {
+    pub id: RoomId,
+    /// Extent on the x and z axes.
+    pub bounds: Rect,
+    /// Rooms reached through a door or an opening, whose entities can be seen or heard from here.
+    pub adjacent: Vec<RoomId>,
+}
+
+/// Rooms of the current level, inserted by the level when it spawns.
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct LevelLayout {
+    pub rooms: Vec<LevelRoom>,
+}
+
+impl LevelLayout {
+    /// Room containing `position`. Rooms may hold smaller ones, such as a closet, the smallest
+    /// wins.
+    pub fn room_at(&self, position: Vec3) -> Option<RoomId> {
+        self.rooms
+            .iter()
+            .filter(|room| room.bounds.contains(position.xz()))
+            .min_by(|a, b| {
+                a.bounds
+                    .size()
+                    .element_product()
+                    .total_cmp(&b.bounds.size().element_product())
+            })
+            .map(|room| room.id)
+    }
+
+    /// `room` and the rooms adjacent to it.
+    pub fn relevant_rooms(&self, room: RoomId) -> impl Iterator<Item = RoomId> + '_ {
+        let adjacent = self
+            .rooms
+            .iter()
+            .find(|level_room| level_room.id == room)
+            .into_iter()
+            .flat_map(|level_room| level_room.adjacent.iter().copied());
+        std::iter::once(room).chain(adjacent)
+    }
+}
+
+/// Level room of a replicated entity, kept up to date from its [`Position`]. Entities with it
+/// should be spawned with a default [`NetworkVisibility`] so they stay hidden until their room is
+/// joined. Entities outside every room stay in the last room they were in.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InRoom(pub Option<RoomId>);
+
+/// Lightyear [`Room`] of each level room, created the first time the room is used.
+#[derive(Resource, Default)]
+pub struct LevelRooms(HashMap<RoomId, Entity>);
+
+impl LevelRooms {
+    fn get_or_spawn(&mut self, room: RoomId, commands: &mut Commands) -> Entity {
+        *self.0.entry(room).or_insert_with(|| {
+            debug!("🚪 Creating relevancy room for level room {}", room.0);
+            commands
+                .spawn((Name::new(format!("Room {}", room.0)), Room::default()))
+                .id()
+        })
+    }
+}
+
+/// Room an entity was added to.
+#[derive(Component)]
+struct JoinedRoom(Entity);
+
+/// Rooms a client receives the entities of.
+#[derive(Component, Default)]
+struct ClientRooms(EntityHashSet);
+
+fn add_client_rooms(trigger: Trigger<OnAdd, ClientOf>, mut commands: Commands) {
+    commands
+        .entity(trigger.target())
+        .insert(ClientRooms::default());
+}
+
+/// Entities that moved, or every one of them when a new level was laid out.
+fn track_entity_rooms(layout: Res<LevelLayout>, mut query: Query<(Ref<Position>, &mut InRoom)>) {
+    for (position, mut in_room) in query.iter_mut() {
+        if !position.is_changed() && !layout.is_changed() {
+            continue;
+        }
+        if let Some(room) = layout.room_at(position.0) {
+            in_room.set_if_neq(InRoom(Some(room)));
+        }
+    }
+}
+
+fn join_entity_rooms(
+    query: Query<(Entity, &InRoom, Option<&JoinedRoom>), Changed<InRoom>>,
+    mut rooms: ResMut<LevelRooms>,
+    mut commands: Commands,
+) {
+    for (entity, in_room, joined) in query.iter() {
+        let Some(room) = in_room.0 else {
+            continue;
+        };
+        let room = rooms.get_or_spawn(room, &mut commands);
+        if joined.is_some_and(|joined| joined.0 == room) {
+            continue;
+        }
+        if let Some(joined) = joined {
+            commands.trigger_targets(RoomEvent::RemoveEntity(entity), joined.0);
+        }
+        commands.trigger_targets(RoomEvent::AddEntity(entity), room);
+        commands.entity(entity).insert(JoinedRoom(room));
+    }
+}
+
+fn update_client_rooms(
+    layout: Res<LevelLayout>,
+    players: Query<(Ref<InRoom>, &ControlledBy), With<PlayerId>>,
+    mut clients: Query<&mut ClientRooms>,
+    mut rooms: ResMut<LevelRooms>,
+    mut commands: Commands,
+) {
+    for (in_room, controlled_by) in players.iter() {
+        if !in_room.is_changed() && !layout.is_changed() {
+            continue;
+        }
+        let (Some(room), Ok(mut client_rooms)) = (in_room.0, clients.get_mut(controlled_by.owner))
+        else {
+            continue;
+        };
+        let relevant: EntityHashSet = layout
+            .relevant_rooms(room)
+            .map(|room| rooms.get_or_spawn(room, &mut commands))
+            .collect();
+
+        for room in client_rooms.0.difference(&relevant) {
+            commands.trigger_targets(RoomEvent::RemoveSender(controlled_by.owner), *room);
+        }
+        for room in relevant.difference(&client_rooms.0) {
+            commands.trigger_targets(RoomEvent::AddSender(controlled_by.owner), *room);
+        }
+        debug!(
+            "🚪 Client {:?} now receives the rooms around room {}",
+            controlled_by.owner, room.0
+        );
+        client_rooms.0 = relevant;
+    }
+}
+
+fn leave_room_on_remove(
+    trigger: Trigger<OnRemove, JoinedRoom>,
+    query: Query<&JoinedRoom>,
+    mut commands: Commands,
+) {
+    if let Ok(joined) = query.get(trigger.target()) {
+        commands.trigger_targets(RoomEvent::RemoveEntity(trigger.target()), joined.0);
+    }
+}
+
+fn leave_client_rooms_on_remove(
+    trigger: Trigger<OnRemove, ClientRooms>,
+    query: Query<&ClientRooms>,
+    mut commands: Commands,
+) {
+    if let Ok(client_rooms) = query.get(trigger.target()) {
+        for room in client_rooms.0.iter() {
+            commands.trigger_targets(RoomEvent::RemoveSender(trigger.target()), *room);
+        }
+    }
+}
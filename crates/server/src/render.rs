@@ -1,15 +1,21 @@
 use avian3d::prelude::{PhysicsDebugPlugin, Position};
+use bevy::color::Alpha;
+use bevy::color::palettes::css::{AQUA, GRAY, LIME, RED, WHITE, YELLOW};
 
 use bevy::prelude::{
-    App, Assets, Camera, Camera3d, Capsule3d, Commands, Entity, Mesh, Mesh3d, MeshMaterial3d, Name,
-    OnAdd, Plugin, Query, ResMut, StandardMaterial, Startup, Transform, Trigger, Vec3, With,
-    Without, debug, default,
+    App, Assets, Camera, Camera3d, Capsule3d, Commands, Entity, Gizmos, Isometry3d, Mesh, Mesh3d,
+    MeshMaterial3d, Name, OnAdd, Plugin, Quat, Query, Res, ResMut, StandardMaterial, Startup,
+    Transform, Trigger, Update, Vec3, With, Without, debug, default,
 };
 use bevy_inspector_egui::{bevy_egui::EguiPlugin, quick::WorldInspectorPlugin};
 use shared::input::{PLAYER_CAPSULE_HEIGHT, PLAYER_CAPSULE_RADIUS};
+
+use crate::navigation::{NavAgent, NavGrid};
 use shared::{
+    enemy::Enemy,
+    objective::{Objective, ObjectiveState, ObjectiveStatus},
     protocol::{PlayerColor, PlayerId},
-    render::{add_floor_visuals, add_wall_visuals, setup_lighting},
+    render::{add_floor_visuals, add_interactable_visuals, add_wall_visuals, setup_lighting},
 };
 pub struct RenderPlugin;
 
@@ -36,6 +42,9 @@ impl Plugin for RenderPlugin {
         app.add_observer(add_floor_visuals);
         app.add_observer(add_wall_visuals);
         app.add_observer(add_player_visuals);
+        app.add_observer(add_enemy_visuals);
+        app.add_observer(add_interactable_visuals);
+        app.add_systems(Update, (draw_navigation, draw_objectives));
         app.add_plugins((
             PhysicsDebugPlugin::default(),
             EguiPlugin::default(),
@@ -65,3 +74,50 @@ fn add_player_visuals(
     ));
     debug!("Added player visuals at position: {:?}", position.0);
 }
+
+fn add_enemy_visuals(
+    trigger: Trigger<OnAdd, Enemy>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut commands: Commands,
+) {
+    commands.entity(trigger.target()).insert((
+        Mesh3d(meshes.add(Capsule3d::new(PLAYER_CAPSULE_RADIUS, PLAYER_CAPSULE_HEIGHT))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: RED.into(),
+            ..default()
+        })),
+    ));
+}
+
+/// Draws the walkable cells of the navigation grid and the path each enemy follows.
+fn draw_navigation(grid: Res<NavGrid>, agents: Query<(&Position, &NavAgent)>, mut gizmos: Gizmos) {
+    let lift = Vec3::Y * 0.05;
+    for (from, to) in grid.edges() {
+        gizmos.line(from + lift, to + lift, AQUA.with_alpha(0.3));
+    }
+    for (position, agent) in agents.iter() {
+        let mut previous = position.0;
+        for waypoint in agent.path() {
+            gizmos.line(previous, *waypoint + lift, YELLOW);
+            previous = *waypoint + lift;
+        }
+    }
+}
+
+fn draw_objectives(objectives: Query<(&Objective, &ObjectiveState)>, mut gizmos: Gizmos) {
+    // Circles are drawn in the XY plane, laid flat on the floor
+    let flat = Quat::from_rotation_x(std::f32::consts::FRAC_PI_2);
+    for (objective, state) in objectives.iter() {
+        let color = match state.status {
+            ObjectiveStatus::Locked => GRAY,
+            ObjectiveStatus::Active => WHITE,
+            ObjectiveStatus::Completed => LIME,
+        };
+        gizmos.circle(
+            Isometry3d::new(objective.position + Vec3::Y * 0.05, flat),
+            objective.radius,
+            color,
+        );
+    }
+}
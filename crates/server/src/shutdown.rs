@@ -0,0 +1,175 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Duration;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use lightyear::prelude::server::*;
+use lightyear::prelude::*;
+use shared::SEND_INTERVAL;
+use shared::protocol::{ServerNoticeChannel, ServerShutdown};
+
+use crate::app::ServerConfig;
+
+/// Exit code used when a signal interrupts the graceful shutdown.
+pub const EXIT_CODE_FORCED: u8 = 2;
+
+/// Time left to the netcode layer to send its disconnect packets before exiting.
+const DISCONNECT_GRACE: Duration = Duration::from_millis(500);
+
+pub struct ShutdownPlugin;
+
+impl Plugin for ShutdownPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShutdownSignal>();
+        app.add_observer(start_shutdown);
+        app.add_systems(Update, (poll_shutdown_signal, advance_shutdown).chain());
+    }
+}
+
+/// Number of SIGINT/SIGTERM received, written by the signal handler thread.
+///
+/// The process can only have one handler, so the binary installs it with
+/// [`ShutdownSignal::install_handler`] and inserts the resource before the app runs.
+#[derive(Resource, Clone, Default)]
+pub struct ShutdownSignal(Arc<AtomicU8>);
+
+impl ShutdownSignal {
+    /// Counts every SIGINT/SIGTERM the process receives from now on.
+    pub fn install_handler(&self) -> Result<(), ctrlc::Error> {
+        let signal = self.clone();
+        ctrlc::set_handler(move || signal.raise())
+    }
+
+    /// Acts as if the process received a signal.
+    pub fn raise(&self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Announces the shutdown to every client, then disconnects them once `countdown` runs out.
+#[derive(Event, Clone, Debug)]
+pub struct RequestShutdown {
+    pub reason: String,
+    pub countdown: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownPhase {
+    /// Clients were told about the shutdown and keep playing.
+    Countdown,
+    /// Waiting one replication interval so the last state update goes out.
+    Flushing,
+    /// The netcode server was stopped, waiting for the disconnect packets to be sent.
+    Disconnecting,
+}
+
+/// Present while the server is shutting down. New connections are refused meanwhile.
+#[derive(Resource, Debug)]
+pub struct ShutdownState {
+    pub phase: ShutdownPhase,
+    timer: Timer,
+}
+
+/// Signals arriving together, before the shutdown started, count as one. Only a signal received
+/// during the shutdown forces the exit.
+fn poll_shutdown_signal(
+    signal: Res<ShutdownSignal>,
+    config: Res<ServerConfig>,
+    state: Option<Res<ShutdownState>>,
+    mut handled: Local<u8>,
+    mut exit: EventWriter<AppExit>,
+    mut commands: Commands,
+) {
+    let received = signal.0.load(Ordering::SeqCst);
+    if received == *handled {
+        return;
+    }
+
+    match state {
+        None => {
+            info!("🛑 Shutdown signal received");
+            commands.trigger(RequestShutdown {
+                reason: "Server is shutting down".to_string(),
+                countdown: config.shutdown_countdown,
+            });
+        }
+        Some(_) => {
+            warn!("🛑 Second shutdown signal received, exiting immediately");
+            exit.write(AppExit::from_code(EXIT_CODE_FORCED));
+        }
+    }
+    *handled = received;
+}
+
+fn start_shutdown(
+    trigger: Trigger<RequestShutdown>,
+    state: Option<Res<ShutdownState>>,
+    mut senders: Query<&mut MessageSender<ServerShutdown>, With<ClientOf>>,
+    mut commands: Commands,
+) {
+    if state.is_some() {
+        return;
+    }
+
+    let notice = ServerShutdown {
+        reason: trigger.reason.clone(),
+        countdown_secs: trigger.countdown.as_secs_f32(),
+    };
+    for mut sender in senders.iter_mut() {
+        sender.send::<ServerNoticeChannel>(notice.clone());
+    }
+    info!(
+        event = "shutdown_started",
+        reason = %notice.reason,
+        "🛑 Shutting down in {:.1}s: {}",
+        notice.countdown_secs,
+        notice.reason
+    );
+
+    commands.insert_resource(ShutdownState {
+        phase: ShutdownPhase::Countdown,
+        timer: Timer::new(trigger.countdown, TimerMode::Once),
+    });
+}
+
+fn advance_shutdown(
+    time: Res<Time>,
+    state: Option<ResMut<ShutdownState>>,
+    servers: Query<Entity, With<Server>>,
+    clients: Query<(), (With<ClientOf>, With<Connected>)>,
+    mut exit: EventWriter<AppExit>,
+    mut commands: Commands,
+) {
+    let Some(mut state) = state else {
+        return;
+    };
+    if !state.timer.tick(time.delta()).finished() {
+        return;
+    }
+
+    match state.phase {
+        ShutdownPhase::Countdown => {
+            debug!("Flushing replication before disconnecting clients");
+            state.phase = ShutdownPhase::Flushing;
+            state.timer = Timer::new(SEND_INTERVAL, TimerMode::Once);
+        }
+        ShutdownPhase::Flushing => {
+            // Stopping the netcode server sends the disconnect packets to every client
+            for server in servers.iter() {
+                commands.trigger_targets(Stop, server);
+            }
+            info!(
+                event = "clients_disconnecting",
+                "👋 Disconnecting {} client(s)",
+                clients.iter().count()
+            );
+            state.phase = ShutdownPhase::Disconnecting;
+            state.timer = Timer::new(DISCONNECT_GRACE, TimerMode::Once);
+        }
+        ShutdownPhase::Disconnecting => {
+            info!(event = "server_stopped", "✅ Server shut down cleanly");
+            exit.write(AppExit::Success);
+        }
+    }
+}
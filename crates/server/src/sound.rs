@@ -0,0 +1,56 @@
+use avian3d::prelude::Position;
+use bevy::prelude::*;
+use lightyear::prelude::server::ClientOf;
+use lightyear::prelude::*;
+use shared::protocol::{PlayerId, PlayerSound, SOUND_RANGE, SoundChannel, SoundMade};
+
+use crate::director::PlayerDamaged;
+use crate::gameplay::ShotFired;
+
+type SoundPlayer<'a> = (&'a PlayerId, &'a Position, &'a ControlledBy);
+
+/// Tells the clients in range about the shots and hits only the server sees, so they can play
+/// them from the player who made them.
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, send_player_sounds);
+    }
+}
+
+fn send_player_sounds(
+    mut shots: EventReader<ShotFired>,
+    mut damaged: EventReader<PlayerDamaged>,
+    players: Query<SoundPlayer>,
+    mut senders: Query<&mut MessageSender<SoundMade>, With<ClientOf>>,
+) {
+    let sounds = shots
+        .read()
+        .map(|shot| (shot.shooter, PlayerSound::Gunshot))
+        .chain(
+            damaged
+                .read()
+                .map(|damage| (damage.player, PlayerSound::DamageTaken)),
+        );
+    for (player, sound) in sounds {
+        let Ok((player_id, position, owner)) = players.get(player) else {
+            continue;
+        };
+        for (_, listener_position, listener) in players.iter() {
+            // The shooter's client already played its own shot when predicting it, and hears its
+            // own damage from the hit the server sends it
+            if listener.owner == owner.owner
+                || position.0.distance(listener_position.0) > SOUND_RANGE
+            {
+                continue;
+            }
+            if let Ok(mut sender) = senders.get_mut(listener.owner) {
+                sender.send::<SoundChannel>(SoundMade {
+                    player: player_id.0,
+                    sound,
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use avian3d::prelude::*;
+use bevy::ecs::system::SystemParam;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use shared::enemy::{Enemy, EnemyState};
+use shared::input::Stance;
+use shared::protocol::{PlayerId, Team};
+use shared::scene::SpawnPoint;
+
+use crate::enemy::{EYE_HEIGHT, sight_range};
+use crate::navigation::LevelGeometryFilter;
+
+/// Seconds during which a used spawn point is left to the others.
+pub const SPAWN_POINT_COOLDOWN: f32 = 2.0;
+/// Where players spawn when the level has no spawn point at all.
+const FALLBACK_SPAWN: Vec3 = Vec3::new(0.0, 10.0, 0.0);
+/// Gap left between the capsule and the floor so it does not start inside it.
+const SPAWN_CLEARANCE: f32 = 0.05;
+
+pub struct SpawnPlugin;
+
+impl Plugin for SpawnPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpawnPointUsage>();
+    }
+}
+
+/// When each spawn point was last used, so players spawning together get different points.
+#[derive(Resource, Default, Debug)]
+pub struct SpawnPointUsage(HashMap<Entity, f32>);
+
+type SpawnPointItem = (Entity, &'static SpawnPoint, &'static Position);
+type SpawnEnemyItem = (Entity, &'static Position, &'static EnemyState);
+
+/// Picks where a player (re)spawns: a free point of its team, away from enemies.
+///
+/// Reads the [`SpatialQueryPipeline`] instead of a `SpatialQuery`, so systems moving players can
+/// use it next to their `&mut Position`.
+#[derive(SystemParam)]
+pub struct SpawnPicker<'w, 's> {
+    time: Res<'w, Time>,
+    usage: ResMut<'w, SpawnPointUsage>,
+    points: Query<'w, 's, SpawnPointItem, Without<PlayerId>>,
+    enemies: Query<'w, 's, SpawnEnemyItem, (With<Enemy>, Without<PlayerId>)>,
+    level: Query<'w, 's, (), LevelGeometryFilter>,
+    spatial_query: Res<'w, SpatialQueryPipeline>,
+}
+
+impl SpawnPicker<'_, '_> {
+    /// Returns the capsule center to spawn `player` at and marks the chosen point as used.
+    ///
+    /// Points of the player's team come first, then points without a team. Points used
+    /// recently, blocked by another collider or seen by an enemy are skipped, and the one
+    /// farthest from enemies wins. When none is free, the least recently used point is taken.
+    pub fn pick(&mut self, team: Team, player: Option<Entity>) -> Vec3 {
+        let now = self.time.elapsed_secs();
+        let collider = Stance::Standing.collider();
+        let half_height = collider.aabb(Vec3::ZERO, Quat::IDENTITY).size().y / 2.0;
+        let filter = SpatialQueryFilter::from_excluded_entities(player);
+
+        let mut best: Option<(Entity, Vec3, u8, f32)> = None;
+        let mut fallback: Option<(Entity, Vec3, f32)> = None;
+        for (entity, point, position) in self.points.iter() {
+            let preference = match point.team {
+                Some(point_team) if point_team == team.0 => 0,
+                None => 1,
+                Some(_) => continue,
+            };
+            let center = position.0 + Vec3::Y * (half_height + SPAWN_CLEARANCE);
+            let last_used = self.usage.0.get(&entity).copied().unwrap_or(f32::MIN);
+            if fallback.is_none_or(|(_, _, oldest)| last_used < oldest) {
+                fallback = Some((entity, center, last_used));
+            }
+
+            if now - last_used < SPAWN_POINT_COOLDOWN
+                || !self
+                    .spatial_query
+                    .shape_intersections(&collider, center, Quat::IDENTITY, &filter)
+                    .is_empty()
+                || self.seen_by_enemy(center)
+            {
+                continue;
+            }
+            let safety = self
+                .enemies
+                .iter()
+                .map(|(_, enemy, _)| enemy.0.distance(center))
+                .fold(f32::MAX, f32::min);
+            let better = best.is_none_or(|(_, _, best_preference, best_safety)| {
+                (preference, -safety) < (best_preference, -best_safety)
+            });
+            if better {
+                best = Some((entity, center, preference, safety));
+            }
+        }
+
+        let (entity, center) = match (best, fallback) {
+            (Some((entity, center, ..)), _) => (entity, center),
+            (None, Some((entity, center, _))) => {
+                warn!(
+                    event = "spawn_point_unavailable",
+                    team = team.0,
+                    "🚩 No free spawn point for team {}, reusing {entity:?}",
+                    team.0
+                );
+                (entity, center)
+            }
+            (None, None) => {
+                warn!(
+                    event = "spawn_point_missing",
+                    team = team.0,
+                    "🚩 Level has no spawn point for team {}",
+                    team.0
+                );
+                return FALLBACK_SPAWN;
+            }
+        };
+        self.usage.0.insert(entity, now);
+        debug!("🚩 Spawning at {entity:?} ({center}) for team {}", team.0);
+        center
+    }
+
+    /// Whether an enemy would see a player standing at `center`, whichever way it faces.
+    fn seen_by_enemy(&self, center: Vec3) -> bool {
+        let is_level = |entity: Entity| self.level.contains(entity);
+        self.enemies.iter().any(|(enemy, position, state)| {
+            let eye = position.0 + Vec3::Y * EYE_HEIGHT;
+            let distance = eye.distance(center);
+            let Ok(direction) = Dir3::new(center - eye) else {
+                return true;
+            };
+            distance <= sight_range(*state)
+                && self
+                    .spatial_query
+                    .cast_ray_predicate(
+                        eye,
+                        direction,
+                        distance,
+                        true,
+                        &SpatialQueryFilter::from_excluded_entities([enemy]),
+                        &is_level,
+                    )
+                    .is_none()
+        })
+    }
+}
@@ -0,0 +1,77 @@
+use avian3d::prelude::Position;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use lightyear::prelude::server::ClientOf;
+use lightyear::prelude::*;
+use shared::protocol::PlayerId;
+use shared::voice::{MAX_VOICE_FRAME_BYTES, VoiceChannel, VoiceFrame, VoicePacket};
+
+use crate::noise::{NoiseEvent, NoiseSource};
+
+/// Seconds between two noise events of the same speaker.
+const VOICE_NOISE_INTERVAL: f32 = 0.25;
+
+type VoicePlayer<'a> = (Entity, &'a PlayerId, &'a Position, &'a ControlledBy);
+
+/// Relays voice frames to the players within range of the speaker's
+/// [`VoiceLevel`](shared::voice::VoiceLevel) and lets enemies hear them through [`NoiseEvent`]s.
+/// The server never decodes the audio.
+pub struct VoicePlugin;
+
+impl Plugin for VoicePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, relay_voice);
+    }
+}
+
+fn relay_voice(
+    time: Res<Time>,
+    mut receivers: Query<(Entity, &mut MessageReceiver<VoiceFrame>), With<ClientOf>>,
+    mut senders: Query<&mut MessageSender<VoicePacket>, With<ClientOf>>,
+    players: Query<VoicePlayer>,
+    mut last_noise: Local<HashMap<Entity, f32>>,
+    mut noises: EventWriter<NoiseEvent>,
+) {
+    let now = time.elapsed_secs();
+    last_noise.retain(|_, at| now - *at < VOICE_NOISE_INTERVAL);
+
+    for (client, mut receiver) in receivers.iter_mut() {
+        let frames: Vec<VoiceFrame> = receiver.receive().collect();
+        let Some((speaker, player_id, position, _)) = players
+            .iter()
+            .find(|(_, _, _, controlled_by)| controlled_by.owner == client)
+        else {
+            continue;
+        };
+
+        for frame in frames {
+            if frame.data.is_empty() || frame.data.len() > MAX_VOICE_FRAME_BYTES {
+                continue;
+            }
+            let range = frame.level.range();
+            let packet = VoicePacket {
+                speaker: player_id.0,
+                level: frame.level,
+                data: frame.data,
+            };
+            for (listener, _, listener_position, controlled_by) in players.iter() {
+                if listener == speaker || position.0.distance(listener_position.0) > range {
+                    continue;
+                }
+                if let Ok(mut sender) = senders.get_mut(controlled_by.owner) {
+                    sender.send::<VoiceChannel>(packet.clone());
+                }
+            }
+
+            if !last_noise.contains_key(&speaker) {
+                last_noise.insert(speaker, now);
+                noises.write(NoiseEvent {
+                    position: position.0,
+                    loudness: frame.level.loudness(),
+                    source: NoiseSource::Voice,
+                    emitter: speaker,
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+/// Longest chat message accepted by the server, in characters.
+pub const MAX_CHAT_LENGTH: usize = 200;
+
+/// Players further away than this don't receive proximity messages.
+pub const PROXIMITY_CHAT_RANGE: f32 = 20.0;
+
+/// Reliable ordered channel carrying chat in both directions.
+pub struct ChatChannel;
+
+/// Who receives a chat message.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChatScope {
+    #[default]
+    All,
+    /// Players of the sender's [`Team`](crate::protocol::Team).
+    Team,
+    /// Players within [`PROXIMITY_CHAT_RANGE`] of the sender.
+    Proximity,
+}
+
+impl ChatScope {
+    pub fn next(self) -> Self {
+        match self {
+            ChatScope::All => ChatScope::Team,
+            ChatScope::Team => ChatScope::Proximity,
+            ChatScope::Proximity => ChatScope::All,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ChatScope::All => "All",
+            ChatScope::Team => "Team",
+            ChatScope::Proximity => "Nearby",
+        }
+    }
+}
+
+/// Sent by a client, relayed by the server as [`ChatLine`]s.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChatSend {
+    pub scope: ChatScope,
+    pub text: String,
+}
+
+/// A chat message as shown to the players receiving it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChatLine {
+    pub sender: String,
+    pub scope: ChatScope,
+    pub text: String,
+    /// Sent before the player joined, replayed from the server history.
+    pub history: bool,
+}
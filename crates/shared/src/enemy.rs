@@ -0,0 +1,31 @@
+use bevy::prelude::{Component, Reflect};
+use serde::{Deserialize, Serialize};
+
+/// Speed of an enemy walking to a noise or searching around.
+pub const ENEMY_WALK_SPEED: f32 = 3.0;
+/// Speed of an enemy chasing a player, a bit faster than a walking player.
+pub const ENEMY_CHASE_SPEED: f32 = 6.0;
+
+/// A server-controlled enemy. Enemies share the player capsule and character controller.
+#[derive(
+    Component, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect,
+)]
+pub struct Enemy;
+
+/// What an enemy is doing, decided on the server from what it sees and hears.
+#[derive(
+    Component, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect,
+)]
+pub enum EnemyState {
+    /// Standing still until a loud enough noise or a player in front of it wakes it up.
+    #[default]
+    Sleeping,
+    /// Walking to a noise it heard.
+    Investigating,
+    /// Running after a player it sees, or to where it last saw them.
+    Chasing,
+    /// Hitting a player within reach.
+    Attacking,
+    /// Wandering around where it lost its target before falling asleep again.
+    Searching,
+}
@@ -1,10 +1,57 @@
-use bevy::prelude::States;
+use bevy::prelude::{Component, Reflect};
+use serde::{Deserialize, Serialize};
 
-#[derive(States, Debug, Default, Clone, PartialEq, Eq, Hash)]
-pub enum GameState {
+/// Phase of the current round, decided by the server.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect)]
+pub enum MatchPhase {
+    /// Waiting for players before the round starts.
     #[default]
-    MainMenu,
-    Loading,
-    Spawning,
-    Playing,
+    Warmup,
+    InProgress,
+    /// The team holds out until it gets extracted.
+    Extraction,
+    Victory,
+    Defeat,
+    /// Between two rounds, while the level is rebuilt.
+    Intermission,
+}
+
+impl MatchPhase {
+    pub fn label(self) -> &'static str {
+        match self {
+            MatchPhase::Warmup => "Warmup",
+            MatchPhase::InProgress => "In progress",
+            MatchPhase::Extraction => "Extraction",
+            MatchPhase::Victory => "Victory",
+            MatchPhase::Defeat => "Defeat",
+            MatchPhase::Intermission => "Intermission",
+        }
+    }
+
+    /// Whether players can move. Controls are locked once the round is over.
+    pub fn allows_input(self) -> bool {
+        matches!(
+            self,
+            MatchPhase::Warmup | MatchPhase::InProgress | MatchPhase::Extraction
+        )
+    }
+}
+
+/// State of the match, on a single entity the server replicates to every client.
+#[derive(
+    Component, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect,
+)]
+pub struct MatchState {
+    pub phase: MatchPhase,
+    /// Current round, starting at 1.
+    pub round: u32,
+    /// Whole seconds left in the phase, so it only changes once per second on the wire.
+    pub seconds_left: u32,
+}
+
+impl MatchState {
+    /// Whether players can move, `true` before the match state has been replicated.
+    pub fn allows_input(state: Option<&MatchState>) -> bool {
+        state.is_none_or(|state| state.phase.allows_input())
+    }
 }
@@ -1,4 +1,7 @@
-use avian3d::prelude::{LinearVelocity, Rotation};
+use avian3d::prelude::{
+    Collider, LinearVelocity, Position, Rotation, ShapeCastConfig, SpatialQueryFilter,
+    SpatialQueryPipeline,
+};
 use bevy::prelude::{Reflect, Vec2, Vec3};
 
 use leafwing_input_manager::Actionlike;
@@ -25,12 +28,89 @@ pub enum PlayerAction {
 
     #[actionlike(Button)]
     Shoot,
+
+    #[actionlike(Button)]
+    Sprint,
+
+    #[actionlike(Button)]
+    Crouch,
+
+    #[actionlike(Button)]
+    Interact,
+}
+
+/// How a player is moving, set from its inputs by [`update_player_stance`].
+#[derive(
+    Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect, Serialize, Deserialize,
+)]
+pub enum Stance {
+    #[default]
+    Standing,
+    Sprinting,
+    Crouching,
+}
+
+impl Stance {
+    pub fn speed(self) -> f32 {
+        match self {
+            Stance::Standing => WALK_SPEED,
+            Stance::Sprinting => SPRINT_SPEED,
+            Stance::Crouching => CROUCH_SPEED,
+        }
+    }
+
+    /// Height of the cylinder part of the player capsule.
+    pub fn capsule_height(self) -> f32 {
+        match self {
+            Stance::Crouching => CROUCH_CAPSULE_HEIGHT,
+            _ => PLAYER_CAPSULE_HEIGHT,
+        }
+    }
+
+    pub fn collider(self) -> Collider {
+        Collider::capsule(self.capsule_height(), PLAYER_CAPSULE_RADIUS)
+    }
+
+    /// Height of the camera above the capsule center, which is lowered when crouching.
+    pub fn eye_height(self) -> f32 {
+        (PLAYER_CAPSULE_HEIGHT + self.capsule_height()) / 2.0 + EYE_OFFSET
+    }
+}
+
+/// Drained by sprinting and jumping, regenerated while resting. An exhausted player can only walk
+/// until it has caught its breath.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct Stamina {
+    pub current: f32,
+    pub exhausted: bool,
+}
+
+impl Default for Stamina {
+    fn default() -> Self {
+        Self {
+            current: STAMINA_MAX,
+            exhausted: false,
+        }
+    }
 }
 
 pub const PLAYER_CAPSULE_RADIUS: f32 = 0.5;
 pub const PLAYER_CAPSULE_HEIGHT: f32 = 1.5;
-pub const MAX_SPEED: f32 = 5.0;
+pub const CROUCH_CAPSULE_HEIGHT: f32 = 0.5;
+pub const WALK_SPEED: f32 = 5.0;
+pub const SPRINT_SPEED: f32 = 8.0;
+pub const CROUCH_SPEED: f32 = 2.0;
 pub const JUMP_HEIGHT: f32 = 1.5;
+/// Camera height above the middle of the standing capsule cylinder.
+const EYE_OFFSET: f32 = 0.6;
+pub const STAMINA_MAX: f32 = 100.0;
+pub const SPRINT_STAMINA_PER_SEC: f32 = 20.0;
+pub const JUMP_STAMINA_COST: f32 = 20.0;
+pub const STAMINA_REGEN_PER_SEC: f32 = 15.0;
+/// Stamina an exhausted player needs to sprint and jump again.
+pub const STAMINA_RECOVERED: f32 = 30.0;
+/// Same as avian's default `Gravity`.
+const GRAVITY: f32 = 9.81;
 pub const MOUSE_SENSITIVITY: f32 = 0.005;
 const LOOK_DEADZONE_SQUARED: f32 = 0.000001; // 0.001^2
 const MOVEMENT_DEADZONE_SQUARED: f32 = 0.000001;
@@ -39,9 +119,12 @@ pub const ROTATION_SMOOTHING_RATE: f32 = 25.0; // Higher = more responsive
 
 pub fn shared_player_movement(
     action_state: &ActionState<PlayerAction>,
-
+    stance: Stance,
+    grounded: bool,
+    mut stamina: Mut<Stamina>,
     rotation: &mut Rotation,
     velocity: &mut LinearVelocity,
+    delta_secs: f32,
 ) {
     let move_input = get_movement_input(action_state);
 
@@ -49,9 +132,77 @@ pub fn shared_player_movement(
         update_player_rotation(rotation, mouse_delta);
     }
 
-    update_player_velocity(velocity, rotation, move_input);
+    update_player_velocity(velocity, rotation, move_input * stance.speed());
     // OR
     // apply_movement_force(external_force, rotation, move_input, velocity);
+
+    // Still going up right after a jump, before leaving the ground
+    let can_jump = grounded && velocity.0.y < 0.5 && stance != Stance::Crouching;
+    if can_jump
+        && action_state.pressed(&PlayerAction::Jump)
+        && !stamina.exhausted
+        && stamina.current >= JUMP_STAMINA_COST
+    {
+        velocity.0.y = (2.0 * GRAVITY * JUMP_HEIGHT).sqrt();
+        stamina.current -= JUMP_STAMINA_COST;
+    }
+    stamina.set_if_neq(update_stamina(*stamina, stance, delta_secs));
+}
+
+/// Moves a character that is not driven by inputs, such as an enemy, the same way as a player:
+/// it turns to face `direction` and walks forward at `speed`. A zero `direction` stops it.
+pub fn steer_character(
+    rotation: &mut Rotation,
+    velocity: &mut LinearVelocity,
+    direction: Vec3,
+    speed: f32,
+) {
+    let Ok(direction) = Dir3::new(direction.with_y(0.0)) else {
+        update_player_velocity(velocity, rotation, Vec2::ZERO);
+        return;
+    };
+    rotation.0 = Transform::default().looking_to(direction, Vec3::Y).rotation;
+    update_player_velocity(velocity, rotation, Vec2::Y * speed);
+}
+
+/// Stamina after `delta_secs` in `stance`: sprinting drains it, anything else regenerates it.
+/// Running dry exhausts the player until it is back to [`STAMINA_RECOVERED`].
+pub fn update_stamina(stamina: Stamina, stance: Stance, delta_secs: f32) -> Stamina {
+    let current = if stance == Stance::Sprinting {
+        (stamina.current - SPRINT_STAMINA_PER_SEC * delta_secs).max(0.0)
+    } else {
+        (stamina.current + STAMINA_REGEN_PER_SEC * delta_secs).min(STAMINA_MAX)
+    };
+    let exhausted = if current <= 0.0 {
+        true
+    } else {
+        stamina.exhausted && current < STAMINA_RECOVERED
+    };
+    Stamina { current, exhausted }
+}
+
+/// Whether the player stands on something, from a short sweep under its capsule.
+///
+/// Takes the [`SpatialQueryPipeline`] rather than a `SpatialQuery`, whose collider query would
+/// conflict with the `&mut Position` of the movement systems. The pipeline is as of the last
+/// physics step either way.
+pub fn is_grounded(
+    entity: Entity,
+    position: Vec3,
+    stance: Stance,
+    spatial_query: &SpatialQueryPipeline,
+) -> bool {
+    let feet = position - Vec3::Y * stance.capsule_height() / 2.0;
+    spatial_query
+        .cast_shape(
+            &Collider::sphere(PLAYER_CAPSULE_RADIUS * 0.9),
+            feet,
+            Quat::IDENTITY,
+            Dir3::NEG_Y,
+            &ShapeCastConfig::from_max_distance(PLAYER_CAPSULE_RADIUS * 0.1 + 0.1),
+            &SpatialQueryFilter::from_excluded_entities([entity]),
+        )
+        .is_some()
 }
 
 #[inline]
@@ -85,15 +236,73 @@ fn update_player_rotation(rotation: &mut Rotation, mouse_delta: Vec2) {
     rotation.0 = rotation.0.normalize();
 }
 
+/// `move_input` is scaled to the speed of the current stance.
 fn update_player_velocity(velocity: &mut LinearVelocity, rotation: &Rotation, move_input: Vec2) {
     let yaw_rotation = rotation.0;
 
     let input_direction = Vec3::new(move_input.x, 0.0, -move_input.y);
-    let world_direction = yaw_rotation * input_direction;
-    let desired_velocity = world_direction * MAX_SPEED;
+    let desired_velocity = yaw_rotation * input_direction;
     velocity.0 = Vec3::new(desired_velocity.x, velocity.0.y, desired_velocity.z);
 }
 
+/// Crouching lowers the capsule center so the feet stay on the ground, standing back up raises
+/// it. A crouched player stays down while something is right above its head. Returns the new
+/// stance.
+pub fn update_player_stance(
+    action_state: &ActionState<PlayerAction>,
+    entity: Entity,
+    mut stance: Mut<Stance>,
+    mut position: Mut<Position>,
+    stamina: &Stamina,
+    spatial_query: &SpatialQueryPipeline,
+) -> Stance {
+    let moving = get_movement_input(action_state) != Vec2::ZERO;
+    let next = if action_state.pressed(&PlayerAction::Crouch)
+        || (*stance == Stance::Crouching && !can_stand_up(entity, position.0, spatial_query))
+    {
+        Stance::Crouching
+    } else if action_state.pressed(&PlayerAction::Sprint) && moving && !stamina.exhausted {
+        Stance::Sprinting
+    } else {
+        Stance::Standing
+    };
+    if *stance == next {
+        return next;
+    }
+
+    let center_drop = (PLAYER_CAPSULE_HEIGHT - CROUCH_CAPSULE_HEIGHT) / 2.0;
+    if next == Stance::Crouching {
+        position.0.y -= center_drop;
+    } else if *stance == Stance::Crouching {
+        position.0.y += center_drop;
+    }
+    *stance = next;
+    next
+}
+
+fn can_stand_up(entity: Entity, position: Vec3, spatial_query: &SpatialQueryPipeline) -> bool {
+    // Sweep the top of the crouched capsule up to where the standing one would reach
+    let head = position + Vec3::Y * CROUCH_CAPSULE_HEIGHT / 2.0;
+    let clearance = PLAYER_CAPSULE_HEIGHT - CROUCH_CAPSULE_HEIGHT;
+    spatial_query
+        .cast_shape(
+            &Collider::sphere(PLAYER_CAPSULE_RADIUS * 0.9),
+            head,
+            Quat::IDENTITY,
+            Dir3::Y,
+            &ShapeCastConfig::from_max_distance(clearance),
+            &SpatialQueryFilter::from_excluded_entities([entity]),
+        )
+        .is_none()
+}
+
+/// Keeps the collider of each player in line with its [`Stance`], including after rollbacks.
+pub fn apply_stance_collider(mut query: Query<(&Stance, &mut Collider), Changed<Stance>>) {
+    for (stance, mut collider) in query.iter_mut() {
+        *collider = stance.collider();
+    }
+}
+
 // fn apply_movement_force(
 //     external_force: &mut ExternalForce,
 //     rotation: &Rotation,
@@ -103,7 +312,7 @@ fn update_player_velocity(velocity: &mut LinearVelocity, rotation: &Rotation, mo
 //     let yaw_rotation = rotation.0;
 //     let input_direction = Vec3::new(move_input.x, 0.0, -move_input.y);
 //     let world_direction = yaw_rotation * input_direction;
-//     let desired_velocity = world_direction * MAX_SPEED;
+//     let desired_velocity = world_direction * WALK_SPEED;
 
 //     // Calculate force needed to reach desired velocity
 //     let current_horizontal = Vec3::new(current_velocity.0.x, 0.0, current_velocity.0.z);
@@ -0,0 +1,119 @@
+use avian3d::prelude::*;
+use bevy::ecs::entity::{EntityMapper, MapEntities};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Farthest a player can reach an interactable from, measured from the eyes to its collider.
+pub const INTERACT_RANGE: f32 = 2.5;
+/// Minimum dot product between the player's facing and the direction to an interactable.
+pub const INTERACT_MIN_FACING: f32 = 0.5;
+
+/// Size of a terminal, the box players hack.
+pub const TERMINAL_SIZE: Vec3 = Vec3::new(0.6, 1.2, 0.4);
+/// Size of a pickup.
+pub const PICKUP_SIZE: f32 = 0.4;
+
+/// Reliable ordered channel carrying the interactable each client looks at.
+pub struct InteractionChannel;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub enum InteractableKind {
+    Door,
+    Terminal,
+    Pickup,
+}
+
+impl InteractableKind {
+    /// What using it does, shown in the prompt. Open doors are closed instead.
+    pub fn verb(self) -> &'static str {
+        match self {
+            InteractableKind::Door => "Open door",
+            InteractableKind::Terminal => "Hack terminal",
+            InteractableKind::Pickup => "Pick up",
+        }
+    }
+}
+
+/// Something players use by looking at it and holding
+/// [`PlayerAction::Interact`](crate::input::PlayerAction::Interact).
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct Interactable {
+    pub kind: InteractableKind,
+    /// Seconds to hold the action for, 0 to use it on press.
+    pub hold_secs: f32,
+    /// Locked interactables can't be used, objectives unlock theirs once active.
+    pub locked: bool,
+}
+
+/// A wall piece that opens, its collider is disabled while it is open.
+#[derive(
+    Component, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect,
+)]
+pub struct Door {
+    pub open: bool,
+}
+
+/// Sent by a client whenever the interactable under its crosshair changes, the server checks
+/// range, facing and line of sight itself before letting the player use it.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct InteractTarget {
+    pub target: Option<Entity>,
+}
+
+impl MapEntities for InteractTarget {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        if let Some(target) = self.target.as_mut() {
+            *target = entity_mapper.get_mapped(*target);
+        }
+    }
+}
+
+/// Terminals are solid boxes, pickups are sensors players walk through. Doors are walls and
+/// get their collider from [`add_wall_physics`](crate::scene::add_wall_physics).
+pub fn add_interactable_physics(
+    trigger: Trigger<OnAdd, Interactable>,
+    interactable_query: Query<&Interactable, Without<Collider>>,
+    mut commands: Commands,
+) {
+    let Ok(interactable) = interactable_query.get(trigger.target()) else {
+        return;
+    };
+    match interactable.kind {
+        InteractableKind::Door => {}
+        InteractableKind::Terminal => {
+            commands.entity(trigger.target()).insert((
+                Collider::cuboid(TERMINAL_SIZE.x, TERMINAL_SIZE.y, TERMINAL_SIZE.z),
+                RigidBody::Static,
+            ));
+        }
+        InteractableKind::Pickup => {
+            commands.entity(trigger.target()).insert((
+                Collider::cuboid(PICKUP_SIZE, PICKUP_SIZE, PICKUP_SIZE),
+                RigidBody::Static,
+                Sensor,
+            ));
+        }
+    }
+}
+
+/// Open doors let players and enemies through and are hidden. Runs before the physics step so
+/// the spatial queries, and the navigation grid, see the door as it is.
+pub fn apply_door_state(
+    mut doors: Query<(Entity, &Door, Option<&mut Visibility>), Changed<Door>>,
+    mut commands: Commands,
+) {
+    for (entity, door, visibility) in doors.iter_mut() {
+        if door.open {
+            commands.entity(entity).insert(ColliderDisabled);
+        } else {
+            commands.entity(entity).remove::<ColliderDisabled>();
+        }
+        if let Some(mut visibility) = visibility {
+            *visibility = if door.open {
+                Visibility::Hidden
+            } else {
+                Visibility::Inherited
+            };
+        }
+    }
+}
@@ -5,12 +5,22 @@ use bevy::prelude::{App, Plugin, Resource};
 
 use avian3d::prelude::*;
 
+use link_conditioner::LinkConditionerPlugin;
+use network_stats::NetworkStatsPlugin;
 use protocol::ProtocolPlugin;
+pub mod chat;
+pub mod enemy;
 pub mod game_state;
 pub mod input;
+pub mod interaction;
+pub mod link_conditioner;
+pub mod network_stats;
+pub mod objective;
 pub mod protocol;
+pub mod quantization;
 pub mod render;
 pub mod scene;
+pub mod voice;
 
 pub struct SharedSettings {
     pub protocol_id: u64,
@@ -44,6 +54,11 @@ pub struct SharedPlugin;
 
 impl Plugin for SharedPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((ProtocolPlugin, PhysicsPlugins::default()));
+        app.add_plugins((
+            ProtocolPlugin,
+            NetworkStatsPlugin,
+            LinkConditionerPlugin,
+            PhysicsPlugins::default(),
+        ));
     }
 }
@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use bevy::platform::time::Instant;
+use bevy::prelude::*;
+use lightyear::link::SendPayload;
+use lightyear::prelude::*;
+use rand::Rng;
+
+pub struct LinkConditionerPlugin;
+
+impl Plugin for LinkConditionerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NetworkConditions>();
+        app.add_observer(add_send_queue);
+        app.add_systems(
+            Update,
+            apply_network_conditions.run_if(resource_changed::<NetworkConditions>),
+        );
+        app.add_systems(
+            PostUpdate,
+            delay_sent_packets
+                .after(ConnectionSet::Send)
+                .before(LinkSet::Send),
+        );
+    }
+}
+
+/// Simulated network conditions, applied to every [`Link`] of the peer that sets them.
+///
+/// Half of the latency and jitter delays what the peer sends, the other half what it receives,
+/// so a client alone adds the whole round trip. The loss applies in each direction. When the
+/// server sets them too, its links add the same on top. Everything at zero disables them.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct NetworkConditions {
+    /// Added round trip time.
+    pub latency: Duration,
+    /// Random variation of the round trip time, added or subtracted.
+    pub jitter: Duration,
+    /// Share of the packets dropped, from 0 to 1.
+    pub loss: f32,
+}
+
+impl NetworkConditions {
+    pub fn is_enabled(&self) -> bool {
+        !self.latency.is_zero() || !self.jitter.is_zero() || self.loss > 0.0
+    }
+
+    /// Conditioner to put on the receiving side of a [`Link`], `None` when disabled.
+    pub fn recv_conditioner(&self) -> Option<RecvLinkConditioner> {
+        self.is_enabled().then(|| {
+            RecvLinkConditioner::new(LinkConditionerConfig::new(
+                self.latency / 2,
+                self.jitter / 2,
+                self.loss,
+            ))
+        })
+    }
+}
+
+/// Swaps the conditioner of the existing links. Packets already delayed carry over to the new
+/// conditioner, they are dropped when the conditioner is turned off.
+fn apply_network_conditions(conditions: Res<NetworkConditions>, mut links: Query<&mut Link>) {
+    for mut link in links.iter_mut() {
+        let previous = link.recv.conditioner.take();
+        link.recv.conditioner = conditions.recv_conditioner().map(|mut conditioner| {
+            if let Some(previous) = previous {
+                conditioner.time_queue = previous.time_queue;
+            }
+            conditioner
+        });
+    }
+}
+
+/// Outgoing packets held back by the simulated conditions, with the time they leave at.
+#[derive(Component, Default, Debug)]
+pub(crate) struct SendQueue(Vec<(Instant, SendPayload)>);
+
+fn add_send_queue(trigger: Trigger<OnAdd, Link>, mut commands: Commands) {
+    commands
+        .entity(trigger.target())
+        .insert(SendQueue::default());
+}
+
+/// The sending half of the conditioner, lightyear only conditions the receiving side. Packets
+/// are taken off the link when they are written and put back once their delay is over. Held
+/// packets all leave at once when the conditions are turned off.
+pub(crate) fn delay_sent_packets(
+    conditions: Res<NetworkConditions>,
+    mut links: Query<(&mut Link, &mut SendQueue)>,
+) {
+    let now = Instant::now();
+    let mut rng = rand::rng();
+    let enabled = conditions.is_enabled();
+    let latency = conditions.latency / 2;
+    let jitter = conditions.jitter / 2;
+    for (mut link, mut queue) in links.iter_mut() {
+        if enabled {
+            for packet in link.send.drain() {
+                if rng.random_range(0.0..1.0) < conditions.loss {
+                    continue;
+                }
+                let mut delay = latency;
+                if !jitter.is_zero() {
+                    delay =
+                        (delay + jitter.mul_f32(rng.random_range(0.0..2.0))).saturating_sub(jitter);
+                }
+                queue.0.push((now + delay, packet));
+            }
+        }
+        queue.0.sort_by_key(|(leaves_at, _)| *leaves_at);
+        let due = queue
+            .0
+            .partition_point(|(leaves_at, _)| !enabled || *leaves_at <= now);
+        for (_, packet) in queue.0.drain(..due) {
+            link.send.push(packet);
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use std::time::Duration;
+
+use avian3d::prelude::Position;
+use bevy::prelude::*;
+use lightyear::prelude::*;
+
+use crate::link_conditioner::delay_sent_packets;
+
+/// How often the per-second rates of [`NetworkStats`] are refreshed.
+const STATS_WINDOW: Duration = Duration::from_secs(1);
+
+pub struct NetworkStatsPlugin;
+
+impl Plugin for NetworkStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RollbackSnapshot>();
+        app.add_observer(add_network_stats);
+        app.add_systems(
+            PreUpdate,
+            (
+                count_received_bytes
+                    .after(LinkSet::Receive)
+                    .before(ConnectionSet::Receive),
+                (count_rollbacks, snapshot_predicted_positions)
+                    .after(RollbackSet::Check)
+                    .before(RollbackSet::Prepare),
+                measure_corrections.after(RollbackSet::EndRollback),
+            ),
+        );
+        app.add_systems(Update, (count_message_acks, refresh_rates).chain());
+        app.add_systems(
+            PostUpdate,
+            count_sent_bytes
+                .after(delay_sent_packets)
+                .before(LinkSet::Send),
+        );
+    }
+}
+
+/// Traffic, loss and rollback figures of a [`Link`], added to every entity that has one.
+///
+/// Byte counts are taken on the encrypted packets, right next to the IO. Every field is
+/// refreshed once per second.
+#[derive(Component, Debug, Clone, Default)]
+pub struct NetworkStats {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub bytes_sent_per_sec: f32,
+    pub bytes_received_per_sec: f32,
+    /// Share of the messages sent on acked channels that were reported lost, from 0 to 1.
+    pub packet_loss: f32,
+    /// Rollbacks of the predicted entities, only counted where a `PredictionManager` lives.
+    pub rollbacks: u64,
+    pub rollbacks_per_sec: f32,
+    /// Largest jump of a `Predicted` position caused by a rollback during the last second.
+    pub max_correction: f32,
+    window: StatsWindow,
+}
+
+/// Counters accumulated since the last refresh of the rates.
+#[derive(Debug, Clone, Default)]
+struct StatsWindow {
+    elapsed: Duration,
+    bytes_sent: u64,
+    bytes_received: u64,
+    messages_acked: u64,
+    messages_lost: u64,
+    rollbacks: u64,
+    max_correction: f32,
+}
+
+/// Positions of the predicted entities right before the rollback of this frame.
+#[derive(Resource, Default)]
+struct RollbackSnapshot(Vec<(Entity, Vec3)>);
+
+fn add_network_stats(trigger: Trigger<OnAdd, Link>, mut commands: Commands) {
+    commands
+        .entity(trigger.target())
+        .insert(NetworkStats::default());
+}
+
+fn count_received_bytes(mut query: Query<(&mut Link, &mut NetworkStats)>) {
+    for (mut link, mut stats) in query.iter_mut() {
+        // Pop every packet and push it back so the queue order is untouched
+        for _ in 0..link.recv.len() {
+            if let Some(packet) = link.recv.pop() {
+                stats.window.bytes_received += packet.len() as u64;
+                link.recv.push_raw(packet);
+            }
+        }
+    }
+}
+
+fn count_sent_bytes(mut query: Query<(&mut Link, &mut NetworkStats)>) {
+    for (mut link, mut stats) in query.iter_mut() {
+        for _ in 0..link.send.len() {
+            if let Some(packet) = link.send.pop() {
+                stats.window.bytes_sent += packet.len() as u64;
+                link.send.push(packet);
+            }
+        }
+    }
+}
+
+fn count_rollbacks(mut query: Query<(&PredictionManager, &mut NetworkStats)>) {
+    for (manager, mut stats) in query.iter_mut() {
+        if manager.is_rollback() {
+            stats.window.rollbacks += 1;
+        }
+    }
+}
+
+fn snapshot_predicted_positions(
+    managers: Query<&PredictionManager>,
+    predicted: Query<(Entity, &Position), With<Predicted>>,
+    mut snapshot: ResMut<RollbackSnapshot>,
+) {
+    if !managers.iter().any(|manager| manager.is_rollback()) {
+        return;
+    }
+    snapshot.0.extend(
+        predicted
+            .iter()
+            .map(|(entity, position)| (entity, position.0)),
+    );
+}
+
+fn measure_corrections(
+    mut snapshot: ResMut<RollbackSnapshot>,
+    predicted: Query<&Position, With<Predicted>>,
+    mut query: Query<&mut NetworkStats, With<PredictionManager>>,
+) {
+    if snapshot.0.is_empty() {
+        return;
+    }
+    let correction = snapshot
+        .0
+        .drain(..)
+        .filter_map(|(entity, before)| {
+            let after = predicted.get(entity).ok()?;
+            Some(before.distance(after.0))
+        })
+        .fold(0.0, f32::max);
+    for mut stats in query.iter_mut() {
+        stats.window.max_correction = stats.window.max_correction.max(correction);
+    }
+}
+
+/// Acks and losses are only kept by the transport for the frame they were detected in.
+fn count_message_acks(mut query: Query<(&Transport, &mut NetworkStats)>) {
+    for (transport, mut stats) in query.iter_mut() {
+        for sender in transport.senders.values() {
+            stats.window.messages_acked += sender.message_acks.len() as u64;
+            stats.window.messages_lost += sender.message_nacks.len() as u64;
+        }
+    }
+}
+
+fn refresh_rates(time: Res<Time<Real>>, mut query: Query<&mut NetworkStats>) {
+    for mut stats in query.iter_mut() {
+        stats.window.elapsed += time.delta();
+        if stats.window.elapsed < STATS_WINDOW {
+            continue;
+        }
+
+        let window = std::mem::take(&mut stats.window);
+        let secs = window.elapsed.as_secs_f32();
+        stats.bytes_sent += window.bytes_sent;
+        stats.bytes_received += window.bytes_received;
+        stats.bytes_sent_per_sec = window.bytes_sent as f32 / secs;
+        stats.bytes_received_per_sec = window.bytes_received as f32 / secs;
+        stats.rollbacks += window.rollbacks;
+        stats.rollbacks_per_sec = window.rollbacks as f32 / secs;
+        stats.max_correction = window.max_correction;
+        let tracked = window.messages_acked + window.messages_lost;
+        if tracked > 0 {
+            stats.packet_loss = window.messages_lost as f32 / tracked as f32;
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use bevy::prelude::{Component, Reflect, Vec3};
+use serde::{Deserialize, Serialize};
+
+/// What players have to do to complete an objective.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect)]
+pub enum ObjectiveKind {
+    /// Pick up the items scattered in the area.
+    Collect,
+    /// Hack the terminal by holding the interaction on it.
+    Hack,
+    /// Hold the area, enemies in it stop the progress.
+    Defend,
+    /// Get the whole team into the area.
+    Extract,
+}
+
+impl ObjectiveKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ObjectiveKind::Collect => "Collect",
+            ObjectiveKind::Hack => "Hack",
+            ObjectiveKind::Defend => "Defend",
+            ObjectiveKind::Extract => "Extract",
+        }
+    }
+}
+
+/// An objective of the current mission, replicated once to every client for the tracker.
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+pub struct Objective {
+    /// Position in the mission, the tracker lists objectives in this order.
+    pub order: u8,
+    pub kind: ObjectiveKind,
+    pub description: String,
+    /// Center of the area, on the floor.
+    pub position: Vec3,
+    pub radius: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum ObjectiveStatus {
+    /// Waiting for the objectives it depends on.
+    #[default]
+    Locked,
+    Active,
+    Completed,
+}
+
+/// Where an objective is at, only replicated when its status or rounded progress changes.
+#[derive(
+    Component, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect,
+)]
+pub struct ObjectiveState {
+    pub status: ObjectiveStatus,
+    /// Progress in percent.
+    pub progress: u8,
+}
+
+/// An item to pick up for a [`ObjectiveKind::Collect`] objective.
+#[derive(
+    Component, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect,
+)]
+pub struct ObjectiveItem;
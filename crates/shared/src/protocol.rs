@@ -1,9 +1,16 @@
-use crate::input::PlayerAction;
+use crate::chat::{ChatChannel, ChatLine, ChatSend};
+use crate::enemy::{Enemy, EnemyState};
+use crate::game_state::MatchState;
+use crate::input::{PlayerAction, Stamina, Stance};
+use crate::interaction::{Door, InteractTarget, Interactable, InteractionChannel};
+use crate::objective::{Objective, ObjectiveItem, ObjectiveState};
+use crate::quantization::{self, WireFormat};
 use crate::scene::*;
+use crate::voice::{VoiceChannel, VoiceFrame, VoicePacket};
 use avian3d::prelude::{LinearVelocity, Position, Rotation};
 use bevy::{
     log::debug,
-    prelude::{App, Color, Component, Name, Plugin, Reflect, default},
+    prelude::{App, Color, Component, Mut, Name, Plugin, Reflect, default},
 };
 
 use lightyear::input::prelude::InputConfig;
@@ -18,6 +25,67 @@ pub struct PlayerId(pub PeerId);
 #[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct PlayerColor(pub Color);
 
+/// Number of teams players are split into.
+pub const TEAM_COUNT: u8 = 2;
+
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Team(pub u8);
+
+/// Reliable ordered channel for server notices such as kicks and shutdown announcements.
+pub struct ServerNoticeChannel;
+
+/// Sent to a client right before the server drops it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Kicked {
+    pub reason: String,
+}
+
+/// Sent to every client when the server starts shutting down.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ServerShutdown {
+    pub reason: String,
+    /// Seconds left before the server disconnects everyone.
+    pub countdown_secs: f32,
+}
+
+/// Channel for the hits of a client's own player.
+pub struct HitChannel;
+
+/// Priority of [`HitChannel`]. lightyear still sends messages from a priority of 100 000 once the
+/// bandwidth cap is reached, so a hit always goes in the next packet, ahead of every replication
+/// update.
+pub const HIT_PRIORITY: f32 = 1_000_000.0;
+
+/// Sent to a client when its player hit something or got hit.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hit {
+    /// A shot of the player hit an enemy or another player.
+    Dealt,
+    /// An enemy hit the player.
+    Taken,
+}
+
+/// Distance at which other players' sounds fade out, in meters. The server doesn't send them
+/// further.
+pub const SOUND_RANGE: f32 = 25.0;
+
+/// Unreliable channel for sounds made by players. A late sound is not worth resending.
+pub struct SoundChannel;
+
+/// Sounds the server tells clients a player made.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayerSound {
+    Gunshot,
+    DamageTaken,
+}
+
+/// Sent to the clients in [`SOUND_RANGE`] of the player identified by `player`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SoundMade {
+    pub player: PeerId,
+    pub sound: PlayerSound,
+}
+
 #[derive(Clone)]
 pub struct ProtocolPlugin;
 
@@ -31,6 +99,73 @@ impl Plugin for ProtocolPlugin {
             },
         });
 
+        // Notices go before replication updates when the bandwidth is capped
+        app.add_channel::<ServerNoticeChannel>(ChannelSettings {
+            mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
+            priority: 10.0,
+            ..default()
+        })
+        .add_direction(NetworkDirection::ServerToClient);
+
+        app.add_message::<Kicked>()
+            .add_direction(NetworkDirection::ServerToClient);
+
+        app.add_message::<ServerShutdown>()
+            .add_direction(NetworkDirection::ServerToClient);
+
+        app.add_channel::<ChatChannel>(ChannelSettings {
+            mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
+            ..default()
+        })
+        .add_direction(NetworkDirection::Bidirectional);
+
+        app.add_message::<ChatSend>()
+            .add_direction(NetworkDirection::ClientToServer);
+
+        app.add_message::<ChatLine>()
+            .add_direction(NetworkDirection::ServerToClient);
+
+        app.add_channel::<VoiceChannel>(ChannelSettings {
+            mode: ChannelMode::SequencedUnreliable,
+            ..default()
+        })
+        .add_direction(NetworkDirection::Bidirectional);
+
+        app.add_message::<VoiceFrame>()
+            .add_direction(NetworkDirection::ClientToServer);
+
+        app.add_message::<VoicePacket>()
+            .add_direction(NetworkDirection::ServerToClient);
+
+        app.add_channel::<HitChannel>(ChannelSettings {
+            mode: ChannelMode::SequencedUnreliable,
+            priority: HIT_PRIORITY,
+            ..default()
+        })
+        .add_direction(NetworkDirection::ServerToClient);
+
+        app.add_message::<Hit>()
+            .add_direction(NetworkDirection::ServerToClient);
+
+        app.add_channel::<SoundChannel>(ChannelSettings {
+            mode: ChannelMode::UnorderedUnreliable,
+            ..default()
+        })
+        .add_direction(NetworkDirection::ServerToClient);
+
+        app.add_message::<SoundMade>()
+            .add_direction(NetworkDirection::ServerToClient);
+
+        app.add_channel::<InteractionChannel>(ChannelSettings {
+            mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
+            ..default()
+        })
+        .add_direction(NetworkDirection::ClientToServer);
+
+        app.add_message::<InteractTarget>()
+            .add_map_entities()
+            .add_direction(NetworkDirection::ClientToServer);
+
         app.register_component::<PlayerId>()
             .add_prediction(PredictionMode::Once)
             .add_interpolation(InterpolationMode::Once);
@@ -43,26 +178,107 @@ impl Plugin for ProtocolPlugin {
             .add_prediction(PredictionMode::Once)
             .add_interpolation(InterpolationMode::Once);
 
+        app.register_component::<WallSize>()
+            .add_prediction(PredictionMode::Once)
+            .add_interpolation(InterpolationMode::Once);
+
         app.register_component::<PlayerColor>()
             .add_prediction(PredictionMode::Once)
             .add_interpolation(InterpolationMode::Once);
 
+        app.register_component::<Team>()
+            .add_prediction(PredictionMode::Once)
+            .add_interpolation(InterpolationMode::Once);
+
+        app.register_component::<Stance>()
+            .add_prediction(PredictionMode::Full)
+            .add_interpolation(InterpolationMode::Simple);
+
+        app.register_component::<Stamina>()
+            .add_prediction(PredictionMode::Full)
+            .add_should_rollback(|old: &Stamina, new: &Stamina| {
+                old.exhausted != new.exhausted || (old.current - new.current).abs() > 1.0
+            });
+
+        app.register_component::<Enemy>()
+            .add_interpolation(InterpolationMode::Once);
+
+        app.register_component::<EnemyState>()
+            .add_interpolation(InterpolationMode::Simple);
+
+        // A single entity, only updated when the phase or the countdown changes
+        app.register_component::<MatchState>();
+
+        app.register_component::<Objective>();
+
+        app.register_component::<ObjectiveState>();
+
+        app.register_component::<ObjectiveItem>();
+
+        app.register_component::<Interactable>();
+
+        app.register_component::<Door>();
+
         app.register_component::<Name>()
             .add_prediction(PredictionMode::Once)
             .add_interpolation(InterpolationMode::Once);
 
-        app.register_component::<Rotation>()
+        // Physics state is quantized on the wire unless the app asks otherwise, see
+        // `crate::quantization`. The delta compressed positions need a `DeltaManager` on every
+        // client link: the one on the server entity is shared by every client and drops the
+        // values older than a tick once the clients sent that tick acked it, including the ones
+        // clients sent on other ticks still diff from.
+        let format = app
+            .world()
+            .get_resource::<WireFormat>()
+            .copied()
+            .unwrap_or_default();
+        let quantized = format != WireFormat::Plain;
+
+        // The confirmed rotation is quantized, only roll back on a real difference
+        let rotation = if quantized {
+            app.register_component_custom_serde::<Rotation>(quantization::rotation_serde())
+        } else {
+            app.register_component::<Rotation>()
+        };
+        rotation
             .add_prediction(PredictionMode::Full)
             .add_interpolation(InterpolationMode::Full)
-            .add_linear_interpolation_fn();
+            .add_linear_interpolation_fn()
+            .add_should_rollback(|old: &Rotation, new: &Rotation| {
+                old.0.angle_between(new.0) > 0.01
+            });
 
-        app.register_component::<Position>()
+        let position = if quantized {
+            app.register_component_custom_serde::<Position>(quantization::position_serde())
+        } else {
+            app.register_component::<Position>()
+        };
+        let position = position
             .add_prediction(PredictionMode::Full)
             .add_interpolation(InterpolationMode::Full)
             .add_linear_interpolation_fn()
             .add_should_rollback(|old: &Position, new: &Position| (old.0.y - new.0.y).abs() > 2.0);
+        if format == WireFormat::QuantizedDelta {
+            position.add_delta_compression();
+            // Replaces the bincode encoding `add_delta_compression` registers for the deltas
+            app.world_mut()
+                .resource_scope(|world, mut registry: Mut<ComponentRegistry>| {
+                    registry.register_component_custom_serde(
+                        world,
+                        quantization::position_delta_serde(),
+                    );
+                });
+        }
 
-        app.register_component::<LinearVelocity>()
+        let velocity = if quantized {
+            app.register_component_custom_serde::<LinearVelocity>(
+                quantization::linear_velocity_serde(),
+            )
+        } else {
+            app.register_component::<LinearVelocity>()
+        };
+        velocity
             .add_prediction(PredictionMode::Full)
             .add_interpolation(InterpolationMode::Full)
             .add_interpolation_fn(|a, b, t| LinearVelocity(a.0.lerp(b.0, t)))
@@ -73,8 +289,3 @@ impl Plugin for ProtocolPlugin {
         debug!("✅ Protocol plugin initialized with components, messages, inputs, and events");
     }
 }
-
-// .add_delta_compression()
-// .add_linear_interpolation_fn()
-// .add_interpolation(InterpolationMode::Full);
-// .add_interpolation_fn(|a, b, t| LinearVelocity(a.0.lerp(b.0, t)))
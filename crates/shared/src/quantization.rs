@@ -0,0 +1,194 @@
+//! Compact wire formats for the replicated physics components.
+//!
+//! Positions and velocities are sent as whole millimetres in zigzag varints, so small values take
+//! 1 or 2 bytes per axis instead of 4. Rotations use the "smallest three" encoding: the largest
+//! quaternion component is dropped and rebuilt from the others, which fit in 10 bits each.
+//!
+//! With [`WireFormat::QuantizedDelta`], positions are sent as the difference from the last one the
+//! client acked. The server keeps its positions on the millimetre grid so the differences add up
+//! exactly on the client.
+
+use avian3d::prelude::{LinearVelocity, Position, Rotation};
+use bevy::prelude::{Quat, Resource, Vec3, Vec4};
+use lightyear::prelude::Tick;
+use lightyear_replication::delta::{DeltaMessage, DeltaType};
+use lightyear_serde::SerializationError;
+use lightyear_serde::reader::{ReadInteger, ReadVarInt, Reader};
+use lightyear_serde::registry::SerializeFns;
+use lightyear_serde::writer::{WriteInteger, Writer};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Resolution of positions on the wire, in metres.
+pub const POSITION_STEP: f32 = 0.001;
+
+/// Resolution of linear velocities on the wire, in metres per second.
+pub const VELOCITY_STEP: f32 = 0.001;
+
+/// How the physics components are written on the wire. Read when the protocol is registered, so
+/// it must be inserted before [`crate::SharedPlugin`], the same on the server and the clients.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WireFormat {
+    /// lightyear's default bincode encoding, at full precision.
+    Plain,
+    /// Millimetre positions and velocities, smallest three rotations.
+    #[default]
+    Quantized,
+    /// Quantized, with positions diffed against the last value each client acked. No smaller than
+    /// `Quantized` in `bandwidth_bench`: the acked tick costs about what the difference saves.
+    QuantizedDelta,
+}
+
+/// Bits for each of the three quaternion components sent.
+const ROTATION_BITS: u32 = 10;
+const ROTATION_MAX: u32 = (1 << ROTATION_BITS) - 1;
+
+/// The three smallest components of a unit quaternion are within ±1/√2.
+const ROTATION_RANGE: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+pub fn position_serde() -> SerializeFns<Position> {
+    SerializeFns {
+        serialize: |position, writer| write_vec3(writer, position.0, POSITION_STEP),
+        deserialize: |reader| read_vec3(reader, POSITION_STEP).map(Position),
+    }
+}
+
+pub fn linear_velocity_serde() -> SerializeFns<LinearVelocity> {
+    SerializeFns {
+        serialize: |velocity, writer| write_vec3(writer, velocity.0, VELOCITY_STEP),
+        deserialize: |reader| read_vec3(reader, VELOCITY_STEP).map(LinearVelocity),
+    }
+}
+
+pub fn rotation_serde() -> SerializeFns<Rotation> {
+    SerializeFns {
+        serialize: |rotation, writer| {
+            writer.write_u32(pack_quat(rotation.0))?;
+            Ok(())
+        },
+        deserialize: |reader| Ok(Rotation(unpack_quat(reader.read_u32()?))),
+    }
+}
+
+/// Positions diffed against an acked one, see [`WireFormat::QuantizedDelta`]. A tag byte, the tick
+/// of the acked position, then the difference like a position.
+pub fn position_delta_serde() -> SerializeFns<DeltaMessage<Position>> {
+    SerializeFns {
+        serialize: |message, writer| {
+            let WirePositionDelta { delta_type, delta } = WirePositionDelta::from_message(message)?;
+            match delta_type {
+                DeltaType::FromBase => writer.write_u8(0)?,
+                DeltaType::Normal { previous_tick } => {
+                    writer.write_u8(1)?;
+                    writer.write_u16(previous_tick.0)?;
+                }
+            }
+            write_vec3(writer, delta.0, POSITION_STEP)
+        },
+        deserialize: |reader| {
+            let delta_type = match reader.read_u8()? {
+                0 => DeltaType::FromBase,
+                _ => DeltaType::Normal {
+                    previous_tick: Tick(reader.read_u16()?),
+                },
+            };
+            let delta = Position(read_vec3(reader, POSITION_STEP)?);
+            WirePositionDelta { delta_type, delta }.into_message()
+        },
+    }
+}
+
+/// Rounds a position to what [`position_serde`] sends.
+pub fn snap_position(position: Vec3) -> Vec3 {
+    (position / POSITION_STEP).round() * POSITION_STEP
+}
+
+/// Same fields as [`DeltaMessage`], which keeps them private. The two convert through their
+/// default serde encoding.
+#[derive(Serialize, Deserialize)]
+struct WirePositionDelta {
+    delta_type: DeltaType,
+    delta: Position,
+}
+
+impl WirePositionDelta {
+    fn from_message(message: &DeltaMessage<Position>) -> Result<Self, SerializationError> {
+        convert(message)
+    }
+
+    fn into_message(self) -> Result<DeltaMessage<Position>, SerializationError> {
+        convert(&self)
+    }
+}
+
+fn convert<A: Serialize + DeserializeOwned, B: Serialize + DeserializeOwned>(
+    value: &A,
+) -> Result<B, SerializationError> {
+    let mut writer = Writer::default();
+    (SerializeFns::<A>::default().serialize)(value, &mut writer)?;
+    (SerializeFns::<B>::default().deserialize)(&mut Reader::from(writer.to_bytes()))
+}
+
+fn write_vec3(writer: &mut Writer, value: Vec3, step: f32) -> Result<(), SerializationError> {
+    for axis in value.to_array() {
+        let steps = (axis / step).round() as i32;
+        writer.write_varint(zigzag(steps))?;
+    }
+    Ok(())
+}
+
+fn read_vec3(reader: &mut Reader, step: f32) -> Result<Vec3, SerializationError> {
+    let mut axes = [0.0; 3];
+    for axis in axes.iter_mut() {
+        *axis = unzigzag(reader.read_varint()?) as f32 * step;
+    }
+    Ok(Vec3::from_array(axes))
+}
+
+fn zigzag(value: i32) -> u64 {
+    ((value << 1) ^ (value >> 31)) as u32 as u64
+}
+
+fn unzigzag(value: u64) -> i32 {
+    let value = value as u32;
+    ((value >> 1) as i32) ^ -((value & 1) as i32)
+}
+
+/// Index of the dropped component in the top 2 bits, then the other three in order.
+fn pack_quat(quat: Quat) -> u32 {
+    let mut components = quat.normalize().to_array();
+    let largest = (0..4)
+        .max_by(|a, b| components[*a].abs().total_cmp(&components[*b].abs()))
+        .unwrap_or(3);
+    // q and -q are the same rotation, make the dropped component positive
+    if components[largest] < 0.0 {
+        components.iter_mut().for_each(|c| *c = -*c);
+    }
+
+    let mut packed = largest as u32;
+    for (index, component) in components.iter().enumerate() {
+        if index == largest {
+            continue;
+        }
+        let normalized = (component / ROTATION_RANGE * 0.5 + 0.5).clamp(0.0, 1.0);
+        packed = (packed << ROTATION_BITS) | (normalized * ROTATION_MAX as f32).round() as u32;
+    }
+    packed
+}
+
+fn unpack_quat(packed: u32) -> Quat {
+    let largest = (packed >> (3 * ROTATION_BITS)) as usize;
+    let mut components = [0.0; 4];
+    let mut shift = 3 * ROTATION_BITS;
+    for (index, component) in components.iter_mut().enumerate() {
+        if index == largest {
+            continue;
+        }
+        shift -= ROTATION_BITS;
+        let normalized = ((packed >> shift) & ROTATION_MAX) as f32 / ROTATION_MAX as f32;
+        *component = (normalized - 0.5) * 2.0 * ROTATION_RANGE;
+    }
+    let sum_squares: f32 = components.iter().map(|c| c * c).sum();
+    components[largest] = (1.0 - sum_squares).max(0.0).sqrt();
+    Quat::from_vec4(Vec4::from_array(components)).normalize()
+}
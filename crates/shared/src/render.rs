@@ -1,6 +1,6 @@
 use avian3d::prelude::Position;
 use bevy::{
-    color::palettes::css::{GREEN, WHITE},
+    color::palettes::css::{DARK_SLATE_GRAY, GOLD, GREEN, WHITE},
     pbr::{AmbientLight, DirectionalLight},
     prelude::{
         Assets, Commands, Cuboid, Entity, Mesh, Mesh3d, MeshMaterial3d, Name, OnAdd, Query, ResMut,
@@ -8,9 +8,8 @@ use bevy::{
     },
 };
 
-use crate::scene::{
-    FLOOR_THICKNESS, FloorMarker, ROOM_SIZE, WALL_HEIGHT, WALL_THICKNESS, WallMarker,
-};
+use crate::interaction::{Interactable, InteractableKind, PICKUP_SIZE, TERMINAL_SIZE};
+use crate::scene::{FLOOR_THICKNESS, FloorMarker, ROOM_SIZE, WallMarker, WallSize, wall_size};
 
 pub fn add_floor_visuals(
     trigger: Trigger<OnAdd, FloorMarker>,
@@ -37,6 +36,34 @@ pub fn add_floor_visuals(
     debug!("Added floor visuals at position: {:?}", position.0);
 }
 
+/// Terminals and pickups are boxes, their position is only replicated once. Doors look like the
+/// walls they are part of.
+pub fn add_interactable_visuals(
+    trigger: Trigger<OnAdd, Interactable>,
+    interactable_query: Query<(&Interactable, &Position)>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let Ok((interactable, position)) = interactable_query.get(trigger.target()) else {
+        debug!("Failed to get interactable entity for visual addition.");
+        return;
+    };
+    let (size, color) = match interactable.kind {
+        InteractableKind::Door => return,
+        InteractableKind::Terminal => (TERMINAL_SIZE, DARK_SLATE_GRAY),
+        InteractableKind::Pickup => (Vec3::splat(PICKUP_SIZE), GOLD),
+    };
+    commands.entity(trigger.target()).insert((
+        Transform::from_translation(position.0),
+        Mesh3d(meshes.add(Cuboid::from_size(size))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: color.into(),
+            ..default()
+        })),
+    ));
+}
+
 pub fn setup_lighting(mut commands: Commands) {
     // Add ambient lighting for better visibility
     commands.insert_resource(AmbientLight {
@@ -61,24 +88,19 @@ pub fn setup_lighting(mut commands: Commands) {
 
 pub fn add_wall_visuals(
     trigger: Trigger<OnAdd, WallMarker>,
-    wall_query: Query<(Entity, &Position, &Name), Without<Mesh3d>>,
+    wall_query: Query<(Entity, &Position, &Name, Option<&WallSize>), Without<Mesh3d>>,
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    let Ok((entity, position, name)) = wall_query.get(trigger.target()) else {
+    let Ok((entity, position, name, size)) = wall_query.get(trigger.target()) else {
         debug!("Failed to get wall entity for visual addition.");
         return;
     };
-    let (width, height, depth) =
-        if name.as_str().contains("North") || name.as_str().contains("South") {
-            (ROOM_SIZE, WALL_HEIGHT, WALL_THICKNESS)
-        } else {
-            (WALL_THICKNESS, WALL_HEIGHT, ROOM_SIZE)
-        };
+    let size = wall_size(name, size);
 
     commands.entity(entity).insert((
-        Mesh3d(meshes.add(Cuboid::new(width, height, depth))),
+        Mesh3d(meshes.add(Cuboid::from_size(size))),
         MeshMaterial3d(materials.add(StandardMaterial {
             base_color: WHITE.into(),
             ..default()
@@ -17,6 +17,18 @@ pub struct WallMarker;
 #[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct CeilingMarker;
 
+/// Size of a wall piece that doesn't span a whole side of the room. Walls without it are sized
+/// from their name.
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct WallSize(pub Vec3);
+
+/// A place on the floor where players spawn, placed by the level.
+/// Points without a team can be used by any team once its own points are taken.
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SpawnPoint {
+    pub team: Option<u8>,
+}
+
 #[derive(Bundle)]
 pub struct FloorPhysicsBundle {
     pub collider: Collider,
@@ -97,24 +109,30 @@ pub fn add_floor_physics(
     debug!("Added floor physics at position");
 }
 
+/// Walls on the north and south sides of the room span it along x, the others along z.
+pub fn wall_size(name: &Name, size: Option<&WallSize>) -> Vec3 {
+    if let Some(size) = size {
+        size.0
+    } else if name.as_str().contains("North") || name.as_str().contains("South") {
+        Vec3::new(ROOM_SIZE, WALL_HEIGHT, WALL_THICKNESS)
+    } else {
+        Vec3::new(WALL_THICKNESS, WALL_HEIGHT, ROOM_SIZE)
+    }
+}
+
 pub fn add_wall_physics(
     trigger: Trigger<OnAdd, WallMarker>,
-    wall_query: Query<(Entity, &Name), Without<Collider>>,
+    wall_query: Query<(Entity, &Name, Option<&WallSize>), Without<Collider>>,
     mut commands: Commands,
 ) {
-    let Ok((entity, name)) = wall_query.get(trigger.target()) else {
+    let Ok((entity, name, size)) = wall_query.get(trigger.target()) else {
         debug!("Failed to get wall entity for visual addition.");
         return;
     };
-    let (width, height, depth) =
-        if name.as_str().contains("North") || name.as_str().contains("South") {
-            (ROOM_SIZE, WALL_HEIGHT, WALL_THICKNESS)
-        } else {
-            (WALL_THICKNESS, WALL_HEIGHT, ROOM_SIZE)
-        };
+    let size = wall_size(name, size);
 
     commands.entity(entity).insert(WallPhysicsBundle {
-        collider: Collider::cuboid(width, height, depth),
+        collider: Collider::cuboid(size.x, size.y, size.z),
         rigid_body: RigidBody::Static,
     });
     debug!("Added wall physics for {}", name.as_str());
@@ -0,0 +1,75 @@
+use lightyear::prelude::PeerId;
+use serde::{Deserialize, Serialize};
+
+/// Sample rate of the voice stream, in Hz.
+pub const VOICE_SAMPLE_RATE: u32 = 48_000;
+
+/// Mono samples in one Opus frame (20 ms).
+pub const VOICE_FRAME_SAMPLES: usize = 960;
+
+/// Largest encoded frame the server relays, in bytes.
+pub const MAX_VOICE_FRAME_BYTES: usize = 400;
+
+/// Unreliable channel carrying voice frames in both directions. A late frame is useless, so lost
+/// ones are never resent.
+pub struct VoiceChannel;
+
+/// How loud a player speaks. Louder voices carry further, to players and to enemies.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VoiceLevel {
+    Whisper,
+    #[default]
+    Normal,
+    Shout,
+}
+
+impl VoiceLevel {
+    pub fn next(self) -> Self {
+        match self {
+            VoiceLevel::Whisper => VoiceLevel::Normal,
+            VoiceLevel::Normal => VoiceLevel::Shout,
+            VoiceLevel::Shout => VoiceLevel::Whisper,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            VoiceLevel::Whisper => "Whisper",
+            VoiceLevel::Normal => "Normal",
+            VoiceLevel::Shout => "Shout",
+        }
+    }
+
+    /// Distance at which other players stop hearing the voice.
+    pub fn range(self) -> f32 {
+        match self {
+            VoiceLevel::Whisper => 5.0,
+            VoiceLevel::Normal => 20.0,
+            VoiceLevel::Shout => 40.0,
+        }
+    }
+
+    /// Loudness of the noise the voice makes, between 0 and 1.
+    pub fn loudness(self) -> f32 {
+        match self {
+            VoiceLevel::Whisper => 0.1,
+            VoiceLevel::Normal => 0.4,
+            VoiceLevel::Shout => 1.0,
+        }
+    }
+}
+
+/// One Opus frame of the sender's voice, relayed by the server as a [`VoicePacket`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct VoiceFrame {
+    pub level: VoiceLevel,
+    pub data: Vec<u8>,
+}
+
+/// A voice frame received from the player identified by `speaker`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct VoicePacket {
+    pub speaker: PeerId,
+    pub level: VoiceLevel,
+    pub data: Vec<u8>,
+}